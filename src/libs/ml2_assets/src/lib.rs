@@ -9,4 +9,4 @@ mod vorbis_data;
 
 pub use assets::AssetStore;
 pub use soundbank::Soundbank;
-pub use strings::StringHasher;
+pub use strings::{StringHasher, StringsFile};
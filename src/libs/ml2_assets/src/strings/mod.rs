@@ -0,0 +1,625 @@
+pub mod po;
+mod remap;
+pub mod xliff;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{BufRead, Read, Write};
+use std::str::FromStr;
+
+use crc32fast::Hasher;
+use thiserror::Error;
+
+pub use remap::{remap, ChangedString, RemapReport};
+
+const UTF8_BOM: &str = "\u{feff}";
+
+#[derive(Error, Debug)]
+pub enum StringsError {
+    #[error("IoError")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Strings data isn't valid UTF-8")]
+    Utf8Error(#[from] std::string::FromUtf8Error),
+
+    #[error("Line {0} doesn't start with a string hash")]
+    MissingHash(usize),
+
+    #[error("Expected {expected} lines but found {found}")]
+    LineCountMismatch { expected: usize, found: usize },
+
+    #[error("Line {line} is {found} in one file but {expected} in the other")]
+    LineKindMismatch {
+        line: usize,
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    #[error("No string with hash {0}")]
+    HashNotFound(StringHash),
+
+    #[error("String values can't contain line breaks")]
+    MultilineValue,
+
+    #[error("Translation of {hash} has placeholders {found:?} but expected {expected:?}")]
+    PlaceholderMismatch {
+        hash: StringHash,
+        expected: Vec<String>,
+        found: Vec<String>,
+    },
+
+    #[error("Expected translations for {expected} strings but found {found}")]
+    TranslationCountMismatch { expected: usize, found: usize },
+
+    #[error("Syntax error on line {line}: {reason}")]
+    PoSyntaxError { line: usize, reason: String },
+
+    #[error("XmlError")]
+    XmlError(#[from] quick_xml::Error),
+
+    #[error("Invalid XLIFF: {0}")]
+    XliffSyntaxError(String),
+}
+
+/// CRC32 of a line's trimmed text followed by the name of its section.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StringHash(pub u32);
+
+impl StringHash {
+    pub fn compute(text: &str, section: Option<&str>) -> Self {
+        let mut hasher = Hasher::new();
+        hasher.update(text.trim().as_bytes());
+        if let Some(section) = section {
+            hasher.update(section.as_bytes());
+        }
+        Self(hasher.finalize())
+    }
+}
+
+impl fmt::Display for StringHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:08x}", self.0)
+    }
+}
+
+impl FromStr for StringHash {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .unwrap_or(s);
+        u32::from_str_radix(digits, 16).map(Self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    /// Only valid for the last line of a file.
+    None,
+}
+
+impl LineEnding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::None => "",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StringsEntry {
+    pub hash: StringHash,
+    /// The most recent non-empty comment preceding this entry.
+    pub section: Option<String>,
+    /// The line exactly as it appears in the file, including any surrounding whitespace.
+    pub value: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StringsLine {
+    /// A line starting with `#`, kept verbatim.
+    Comment(String),
+    Entry(StringsEntry),
+}
+
+impl StringsLine {
+    fn kind(&self) -> &'static str {
+        match self {
+            StringsLine::Comment(_) => "a comment",
+            StringsLine::Entry(_) => "an entry",
+        }
+    }
+}
+
+/// A parsed strings00.str through strings0N.str file.
+///
+/// Parsing retains line endings and a leading BOM so that an unedited file is written back
+/// byte-for-byte.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StringsFile {
+    bom: bool,
+    lines: Vec<(StringsLine, LineEnding)>,
+}
+
+impl StringsFile {
+    /// Parses a file, hashing each entry from its own text.
+    ///
+    /// This is correct for the base (English) strings file. Translated files should be
+    /// parsed with this and then given the base's hashes via [`StringsFile::rekey`].
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, StringsError> {
+        Self::parse(reader, false)
+    }
+
+    /// Parses a file where each entry has been prefixed with its hash, as written by
+    /// [`StringsFile::write_hashed`] or `StringHasher::merge_hashes`.
+    pub fn from_hashed_reader<R: Read>(reader: R) -> Result<Self, StringsError> {
+        Self::parse(reader, true)
+    }
+
+    fn parse<R: Read>(mut reader: R, hashed: bool) -> Result<Self, StringsError> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+        let text = String::from_utf8(buf)?;
+        let (bom, mut rest) = match text.strip_prefix(UTF8_BOM) {
+            Some(rest) => (true, rest),
+            None => (false, text.as_str()),
+        };
+
+        let mut lines = Vec::new();
+        let mut section: Option<String> = None;
+        while !rest.is_empty() {
+            let (line, ending, remaining) = split_line(rest);
+            rest = remaining;
+
+            if line.trim_start().starts_with('#') {
+                let comment_section = line.trim().trim_matches(&[' ', '#'][..]);
+                if !comment_section.is_empty() {
+                    section = Some(comment_section.into());
+                }
+                lines.push((StringsLine::Comment(line.into()), ending));
+                continue;
+            }
+
+            let (hash, value) = if hashed {
+                let (hash, value) = line
+                    .split_once(": ")
+                    .ok_or(StringsError::MissingHash(lines.len() + 1))?;
+                let hash = hash
+                    .parse()
+                    .map_err(|_| StringsError::MissingHash(lines.len() + 1))?;
+                (hash, value)
+            } else {
+                (StringHash::compute(line, section.as_deref()), line)
+            };
+            lines.push((
+                StringsLine::Entry(StringsEntry {
+                    hash,
+                    section: section.clone(),
+                    value: value.into(),
+                }),
+                ending,
+            ));
+        }
+
+        Ok(Self { bom, lines })
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &StringsLine> {
+        self.lines.iter().map(|(line, _)| line)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &StringsEntry> {
+        self.lines().filter_map(|line| match line {
+            StringsLine::Entry(entry) => Some(entry),
+            StringsLine::Comment(_) => None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Returns the value of the first entry with the given hash.
+    pub fn get(&self, hash: StringHash) -> Option<&str> {
+        self.entries()
+            .find(|entry| entry.hash == hash)
+            .map(|entry| entry.value.as_str())
+    }
+
+    /// Replaces the value of every entry with the given hash.
+    ///
+    /// Identical lines within a section share a hash, so they're all updated together.
+    pub fn set(&mut self, hash: StringHash, value: &str) -> Result<(), StringsError> {
+        if value.contains(['\r', '\n']) {
+            return Err(StringsError::MultilineValue);
+        }
+
+        let mut found = false;
+        for (line, _) in self.lines.iter_mut() {
+            if let StringsLine::Entry(entry) = line {
+                if entry.hash == hash {
+                    entry.value = value.into();
+                    found = true;
+                }
+            }
+        }
+
+        if found {
+            Ok(())
+        } else {
+            Err(StringsError::HashNotFound(hash))
+        }
+    }
+
+    /// Replaces this file's hashes with those of `base`, matching lines by position.
+    pub fn rekey(&mut self, base: &StringsFile) -> Result<(), StringsError> {
+        if self.lines.len() != base.lines.len() {
+            return Err(StringsError::LineCountMismatch {
+                expected: base.lines.len(),
+                found: self.lines.len(),
+            });
+        }
+
+        for (idx, ((line, _), (base_line, _))) in
+            self.lines.iter_mut().zip(base.lines.iter()).enumerate()
+        {
+            match (line, base_line) {
+                (StringsLine::Entry(entry), StringsLine::Entry(base_entry)) => {
+                    entry.hash = base_entry.hash;
+                    entry.section = base_entry.section.clone();
+                }
+                (StringsLine::Comment(_), StringsLine::Comment(_)) => {}
+                (line, base_line) => {
+                    return Err(StringsError::LineKindMismatch {
+                        line: idx + 1,
+                        expected: base_line.kind(),
+                        found: line.kind(),
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the file in the format the game reads.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), StringsError> {
+        self.write_lines(writer, false)
+    }
+
+    /// Writes the file with each entry prefixed by its hash.
+    pub fn write_hashed<W: Write>(&self, writer: &mut W) -> Result<(), StringsError> {
+        self.write_lines(writer, true)
+    }
+
+    fn write_lines<W: Write>(&self, writer: &mut W, hashed: bool) -> Result<(), StringsError> {
+        if self.bom {
+            writer.write_all(UTF8_BOM.as_bytes())?;
+        }
+        for (line, ending) in &self.lines {
+            match line {
+                StringsLine::Comment(comment) => writer.write_all(comment.as_bytes())?,
+                StringsLine::Entry(entry) => {
+                    if hashed {
+                        write!(writer, "{}: ", entry.hash)?;
+                    }
+                    writer.write_all(entry.value.as_bytes())?;
+                }
+            }
+            writer.write_all(ending.as_str().as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// A string as exchanged with translation tools, keyed by the base file's hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranslationUnit {
+    pub hash: StringHash,
+    pub section: Option<String>,
+    pub source: String,
+    /// `None` if the string hasn't been translated.
+    pub target: Option<String>,
+}
+
+impl StringsFile {
+    /// Pairs each distinct string in this base file with its value in `translation`.
+    ///
+    /// `translation` must already carry this file's hashes.
+    pub fn translation_units(&self, translation: Option<&StringsFile>) -> Vec<TranslationUnit> {
+        let mut seen = HashSet::new();
+        self.entries()
+            .filter(|entry| seen.insert(entry.hash))
+            .map(|entry| TranslationUnit {
+                hash: entry.hash,
+                section: entry.section.clone(),
+                source: entry.value.clone(),
+                target: translation
+                    .and_then(|translation| translation.get(entry.hash))
+                    .map(Into::into),
+            })
+            .collect()
+    }
+
+    /// Builds a translation with this base file's layout from translated units.
+    ///
+    /// Every string in the base must have exactly one unit, and each translation must keep
+    /// the placeholders of the base text. Untranslated units keep the base text.
+    pub fn apply_translations(
+        &self,
+        units: &[TranslationUnit],
+    ) -> Result<StringsFile, StringsError> {
+        let base_hashes: HashSet<StringHash> = self.entries().map(|entry| entry.hash).collect();
+        let mut targets = HashMap::with_capacity(units.len());
+        for unit in units {
+            if !base_hashes.contains(&unit.hash) {
+                return Err(StringsError::HashNotFound(unit.hash));
+            }
+            targets.insert(unit.hash, unit.target.as_deref());
+        }
+        if targets.len() != base_hashes.len() || units.len() != base_hashes.len() {
+            return Err(StringsError::TranslationCountMismatch {
+                expected: base_hashes.len(),
+                found: units.len(),
+            });
+        }
+
+        let mut translated = self.clone();
+        for (line, _) in translated.lines.iter_mut() {
+            let entry = match line {
+                StringsLine::Entry(entry) => entry,
+                StringsLine::Comment(_) => continue,
+            };
+            let target = match targets[&entry.hash] {
+                Some(target) => target,
+                None => continue,
+            };
+            if target.contains(['\r', '\n']) {
+                return Err(StringsError::MultilineValue);
+            }
+
+            let mut expected = placeholders(&entry.value);
+            let mut found = placeholders(target);
+            expected.sort();
+            found.sort();
+            if expected != found {
+                return Err(StringsError::PlaceholderMismatch {
+                    hash: entry.hash,
+                    expected,
+                    found,
+                });
+            }
+            entry.value = target.into();
+        }
+        Ok(translated)
+    }
+}
+
+/// Finds printf-style placeholders such as `%s`, `%d` or `%1$.2f`. `%%` isn't a placeholder.
+pub fn placeholders(text: &str) -> Vec<String> {
+    // No space flag, which would turn prose like "100% sure" into a placeholder
+    const FLAGS: &[u8] = b"-+#0";
+    const LENGTHS: &[u8] = b"hlLqjzt";
+    const CONVERSIONS: &[u8] = b"diouxXeEfFgGaAcsp";
+
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut start = 0;
+    while let Some(offset) = text[start..].find('%') {
+        let begin = start + offset;
+        let mut idx = begin + 1;
+        if bytes.get(idx) == Some(&b'%') {
+            start = idx + 1;
+            continue;
+        }
+
+        let skip = |idx: &mut usize, set: &dyn Fn(u8) -> bool| {
+            while bytes.get(*idx).is_some_and(|b| set(*b)) {
+                *idx += 1;
+            }
+        };
+        // Positional argument, e.g. the "1$" in "%1$s"
+        let mut digits_end = idx;
+        skip(&mut digits_end, &|b| b.is_ascii_digit());
+        if digits_end > idx && bytes.get(digits_end) == Some(&b'$') {
+            idx = digits_end + 1;
+        }
+        skip(&mut idx, &|b| FLAGS.contains(&b));
+        skip(&mut idx, &|b| b.is_ascii_digit() || b == b'*');
+        if bytes.get(idx) == Some(&b'.') {
+            idx += 1;
+            skip(&mut idx, &|b| b.is_ascii_digit() || b == b'*');
+        }
+        skip(&mut idx, &|b| LENGTHS.contains(&b));
+
+        if bytes.get(idx).is_some_and(|b| CONVERSIONS.contains(b)) {
+            found.push(text[begin..=idx].into());
+            start = idx + 1;
+        } else {
+            start = begin + 1;
+        }
+    }
+    found
+}
+
+fn split_line(text: &str) -> (&str, LineEnding, &str) {
+    match text.find('\n') {
+        Some(idx) => {
+            let rest = &text[idx + 1..];
+            match text[..idx].strip_suffix('\r') {
+                Some(line) => (line, LineEnding::CrLf, rest),
+                None => (&text[..idx], LineEnding::Lf, rest),
+            }
+        }
+        None => (text, LineEnding::None, ""),
+    }
+}
+
+pub struct StringHasher {
+    pub hashes: Vec<Option<String>>,
+}
+
+impl StringHasher {
+    pub fn from_reader<R: BufRead>(reader: R) -> Self {
+        let mut hashes = Vec::with_capacity(2600);
+        let mut current_section: Option<String> = None;
+
+        let comment_matches = &[' ', '#'];
+
+        for line in reader.lines() {
+            let line: String = match line {
+                Ok(line) => line.trim().into(),
+                Err(_) => continue,
+            };
+
+            let mut string_hash: Option<String> = None;
+            if line.starts_with('#') {
+                let comment_section = line.trim_matches(&comment_matches[..]);
+                if !comment_section.is_empty() {
+                    current_section = Some(comment_section.into());
+                }
+            } else {
+                let mut to_hash: String = line;
+                if let Some(current_section) = &current_section {
+                    to_hash.push_str(current_section);
+                }
+
+                let mut hasher = Hasher::new();
+                hasher.update(to_hash.as_bytes());
+                let checksum = hasher.finalize();
+
+                string_hash = Some(format!("0x{checksum:08x}"));
+            }
+
+            hashes.push(string_hash);
+        }
+
+        Self { hashes }
+    }
+
+    pub fn merge_hashes<W: Write>(
+        &self,
+        lines: &Vec<String>,
+        writer: &mut W,
+    ) -> Result<(), std::io::Error> {
+        assert!(lines.len() == self.hashes.len());
+
+        for (line_num, line) in lines.iter().enumerate() {
+            let hash = &self.hashes[line_num];
+
+            if let Some(hash) = hash {
+                writer.write_all(format!("{}: {}\n", &hash, line).as_bytes())?;
+            } else {
+                writer.write_all(format!("{line}\n").as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{placeholders, StringHash, StringHasher, StringsError, StringsFile, StringsLine};
+
+    const BASE: &str =
+        "\u{feff}# Menu\r\nPlay\r\n  Options  \r\n#\r\nQuit\r\n# Journal\r\nPlay\r\nEnd";
+    const TRANSLATED: &str = "# Menu\nJouer\nOptions\n#\nQuitter\n# Journal\nJouer\nFin\n";
+
+    #[test]
+    fn test_round_trip() {
+        let file = StringsFile::from_reader(BASE.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        file.write(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), BASE);
+
+        let file = StringsFile::from_reader(TRANSLATED.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        file.write(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), TRANSLATED);
+    }
+
+    #[test]
+    fn test_hashes_match_hasher() {
+        let file = StringsFile::from_reader(BASE.as_bytes()).unwrap();
+        let hasher = StringHasher::from_reader(BASE.trim_start_matches('\u{feff}').as_bytes());
+        let hashes: Vec<Option<String>> = file
+            .lines()
+            .map(|line| match line {
+                StringsLine::Comment(_) => None,
+                StringsLine::Entry(entry) => Some(entry.hash.to_string()),
+            })
+            .collect();
+        assert_eq!(hashes, hasher.hashes);
+
+        let entries: Vec<_> = file.entries().collect();
+        assert_eq!(entries[0].section.as_deref(), Some("Menu"));
+        assert_eq!(entries[2].section.as_deref(), Some("Menu"));
+        assert_eq!(entries[3].section.as_deref(), Some("Journal"));
+        // The same text in different sections hashes differently
+        assert_ne!(entries[0].hash, entries[3].hash);
+    }
+
+    #[test]
+    fn test_edit_by_hash() {
+        let base = StringsFile::from_reader(BASE.as_bytes()).unwrap();
+        let mut translated = StringsFile::from_reader(TRANSLATED.as_bytes()).unwrap();
+        translated.rekey(&base).unwrap();
+
+        let quit = StringHash::compute("Quit", Some("Menu"));
+        assert_eq!(translated.get(quit), Some("Quitter"));
+        translated.set(quit, "Sortir").unwrap();
+        assert_eq!(translated.get(quit), Some("Sortir"));
+
+        let missing = StringHash(0);
+        assert!(matches!(
+            translated.set(missing, "x"),
+            Err(StringsError::HashNotFound(h)) if h == missing
+        ));
+        assert!(matches!(
+            translated.set(quit, "a\nb"),
+            Err(StringsError::MultilineValue)
+        ));
+    }
+
+    #[test]
+    fn test_hashed_round_trip() {
+        let base = StringsFile::from_reader(BASE.as_bytes()).unwrap();
+        let mut hashed = Vec::new();
+        base.write_hashed(&mut hashed).unwrap();
+
+        let parsed = StringsFile::from_hashed_reader(&hashed[..]).unwrap();
+        assert_eq!(parsed, base);
+    }
+
+    #[test]
+    fn test_rekey_mismatch() {
+        let base = StringsFile::from_reader(BASE.as_bytes()).unwrap();
+        let mut short = StringsFile::from_reader("# Menu\nJouer\n".as_bytes()).unwrap();
+        assert!(matches!(
+            short.rekey(&base),
+            Err(StringsError::LineCountMismatch {
+                expected: 8,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(
+            placeholders("%d of %s at 100%% with %1$.2f%"),
+            vec!["%d", "%s", "%1$.2f"]
+        );
+        assert!(placeholders("Nothing here").is_empty());
+        assert!(placeholders("100% sure, 50% de").is_empty());
+    }
+}
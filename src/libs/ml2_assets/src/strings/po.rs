@@ -0,0 +1,296 @@
+//! Gettext PO export and import for strings files.
+//!
+//! Each string becomes a message whose context is its [`StringHash`], with its section as an
+//! extracted comment.
+
+use std::io::{Read, Write};
+
+use super::{StringHash, StringsError, StringsFile, TranslationUnit};
+
+/// Writes `base` as a PO file, with `translation`'s values as the message strings.
+///
+/// Without a translation, this writes a template (POT) file.
+pub fn export<W: Write>(
+    base: &StringsFile,
+    translation: Option<&StringsFile>,
+    writer: &mut W,
+) -> Result<(), StringsError> {
+    writer.write_all(b"msgid \"\"\n")?;
+    writer.write_all(b"msgstr \"\"\n")?;
+    writer.write_all(b"\"Content-Type: text/plain; charset=UTF-8\\n\"\n")?;
+
+    for unit in base.translation_units(translation) {
+        writer.write_all(b"\n")?;
+        if let Some(section) = &unit.section {
+            writeln!(writer, "#. {section}")?;
+        }
+        writeln!(writer, "msgctxt \"{}\"", unit.hash)?;
+        writeln!(writer, "msgid \"{}\"", escape(&unit.source))?;
+        writeln!(
+            writer,
+            "msgstr \"{}\"",
+            escape(unit.target.as_deref().unwrap_or_default())
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads a PO file and builds a translation with `base`'s layout.
+///
+/// Empty and fuzzy messages are treated as untranslated.
+pub fn import<R: Read>(reader: R, base: &StringsFile) -> Result<StringsFile, StringsError> {
+    let units = parse(reader)?;
+    base.apply_translations(&units)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Context,
+    Id,
+    Str,
+}
+
+#[derive(Default)]
+struct Message {
+    section: Option<String>,
+    fuzzy: bool,
+    context: Option<String>,
+    id: Option<String>,
+    str: Option<String>,
+}
+
+impl Message {
+    fn is_empty(&self) -> bool {
+        self.context.is_none() && self.id.is_none() && self.str.is_none()
+    }
+
+    fn field(&mut self, field: Field) -> &mut Option<String> {
+        match field {
+            Field::Context => &mut self.context,
+            Field::Id => &mut self.id,
+            Field::Str => &mut self.str,
+        }
+    }
+
+    fn into_unit(self, line: usize) -> Result<Option<TranslationUnit>, StringsError> {
+        let syntax_error = |reason: &str| StringsError::PoSyntaxError {
+            line,
+            reason: reason.into(),
+        };
+
+        let id = self
+            .id
+            .ok_or_else(|| syntax_error("message without msgid"))?;
+        let context = match self.context {
+            Some(context) => context,
+            // The header has neither context nor id
+            None if id.is_empty() => return Ok(None),
+            None => return Err(syntax_error("message without msgctxt")),
+        };
+        let hash: StringHash = context
+            .parse()
+            .map_err(|_| syntax_error("msgctxt isn't a string hash"))?;
+        let target = self.str.filter(|target| !self.fuzzy && !target.is_empty());
+
+        Ok(Some(TranslationUnit {
+            hash,
+            section: self.section,
+            source: id,
+            target,
+        }))
+    }
+}
+
+fn parse<R: Read>(mut reader: R) -> Result<Vec<TranslationUnit>, StringsError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+
+    let mut units = Vec::new();
+    let mut message = Message::default();
+    let mut field = None;
+    let mut message_line = 0;
+    for (idx, line) in text.lines().enumerate() {
+        let line_num = idx + 1;
+        let line = line.trim();
+        let syntax_error = |reason: &str| StringsError::PoSyntaxError {
+            line: line_num,
+            reason: reason.into(),
+        };
+
+        let (keyword, rest) = if line.is_empty() || line.starts_with("#~") {
+            continue;
+        } else if let Some(comment) = line.strip_prefix('#') {
+            if !message.is_empty() {
+                units.extend(std::mem::take(&mut message).into_unit(message_line)?);
+                field = None;
+            }
+            if let Some(section) = comment.strip_prefix(". ") {
+                message.section = Some(section.into());
+            } else if let Some(flags) = comment.strip_prefix(", ") {
+                message.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            }
+            continue;
+        } else if line.starts_with('"') {
+            let current = field.ok_or_else(|| syntax_error("string without a keyword"))?;
+            let value = unescape(line).ok_or_else(|| syntax_error("malformed string"))?;
+            message
+                .field(current)
+                .get_or_insert_with(String::new)
+                .push_str(&value);
+            continue;
+        } else {
+            line.split_once(' ')
+                .ok_or_else(|| syntax_error("expected a keyword and a string"))?
+        };
+
+        let next = match keyword {
+            "msgctxt" => Field::Context,
+            "msgid" => Field::Id,
+            "msgstr" => Field::Str,
+            "msgid_plural" => return Err(syntax_error("plural messages aren't supported")),
+            _ if keyword.starts_with("msgstr[") => {
+                return Err(syntax_error("plural messages aren't supported"))
+            }
+            _ => return Err(syntax_error(&format!("unknown keyword {keyword:?}"))),
+        };
+        // A new context or id after a msgstr starts the next message
+        if field == Some(Field::Str) && next != Field::Str {
+            units.extend(std::mem::take(&mut message).into_unit(message_line)?);
+        }
+        if message.is_empty() {
+            message_line = line_num;
+        }
+        if message.field(next).is_some() {
+            return Err(syntax_error(&format!("duplicate {keyword}")));
+        }
+        let value = unescape(rest.trim()).ok_or_else(|| syntax_error("malformed string"))?;
+        *message.field(next) = Some(value);
+        field = Some(next);
+    }
+    if !message.is_empty() {
+        units.extend(message.into_unit(message_line)?);
+    }
+
+    Ok(units)
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\\"),
+            '"' => escaped.push_str("\\\""),
+            '\t' => escaped.push_str(r"\t"),
+            '\r' => escaped.push_str(r"\r"),
+            '\n' => escaped.push_str(r"\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Unescapes a quoted PO string, returning `None` if it's malformed.
+fn unescape(quoted: &str) -> Option<String> {
+    let inner = quoted.strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(match chars.next()? {
+                '\\' => '\\',
+                '"' => '"',
+                't' => '\t',
+                'r' => '\r',
+                'n' => '\n',
+                _ => return None,
+            }),
+            '"' => return None,
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export, import};
+    use crate::strings::{StringHash, StringsError, StringsFile};
+
+    const BASE: &str = "# Menu\nPlay\nGot %d \"coins\"\n# Journal\nPlay\n";
+    const TRANSLATION: &str = "# Menu\nJouer\n%d \"pièces\"\n# Journal\nJouer\n";
+
+    fn parse(text: &str) -> StringsFile {
+        StringsFile::from_reader(text.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let base = parse(BASE);
+        let mut translation = parse(TRANSLATION);
+        translation.rekey(&base).unwrap();
+
+        let mut po = Vec::new();
+        export(&base, Some(&translation), &mut po).unwrap();
+        let po = String::from_utf8(po).unwrap();
+        let play = StringHash::compute("Play", Some("Menu"));
+        assert!(po.contains(&format!(
+            "#. Menu\nmsgctxt \"{play}\"\nmsgid \"Play\"\nmsgstr \"Jouer\"\n"
+        )));
+        assert!(po.contains("msgid \"Got %d \\\"coins\\\"\"\n"));
+
+        let imported = import(po.as_bytes(), &base).unwrap();
+        assert_eq!(imported, translation);
+    }
+
+    #[test]
+    fn test_import_untranslated() {
+        let base = parse(BASE);
+        let mut pot = Vec::new();
+        export(&base, None, &mut pot).unwrap();
+
+        let imported = import(&pot[..], &base).unwrap();
+        assert_eq!(imported, base);
+    }
+
+    #[test]
+    fn test_import_checks() {
+        let base = parse(BASE);
+        let coins = StringHash::compute("Got %d \"coins\"", Some("Menu"));
+        let play = StringHash::compute("Play", Some("Menu"));
+        let journal = StringHash::compute("Play", Some("Journal"));
+        let po = |coins_str: &str| {
+            format!(
+                "msgctxt \"{play}\"\nmsgid \"Play\"\nmsgstr \"\"\n\n\
+                 msgctxt \"{coins}\"\nmsgid \"Got %d coins\"\nmsgstr \"{coins_str}\"\n\n\
+                 msgctxt \"{journal}\"\nmsgid \"Play\"\nmsgstr \"\"\n"
+            )
+        };
+
+        let err = import(po("%s pièces").as_bytes(), &base).unwrap_err();
+        assert!(matches!(
+            err,
+            StringsError::PlaceholderMismatch { hash, .. } if hash == coins
+        ));
+
+        let err = import(po("%d\\npièces").as_bytes(), &base).unwrap_err();
+        assert!(matches!(err, StringsError::MultilineValue));
+
+        let missing = po("%d pièces").replace(&format!("msgctxt \"{journal}\""), "#");
+        let err = import(missing.as_bytes(), &base).unwrap_err();
+        assert!(matches!(err, StringsError::PoSyntaxError { .. }));
+
+        let truncated: String = po("%d pièces")
+            .split("\n\n")
+            .take(2)
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let err = import(truncated.as_bytes(), &base).unwrap_err();
+        assert!(matches!(
+            err,
+            StringsError::TranslationCountMismatch {
+                expected: 3,
+                found: 2
+            }
+        ));
+    }
+}
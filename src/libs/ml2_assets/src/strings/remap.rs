@@ -0,0 +1,168 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{StringHash, StringsEntry, StringsFile, StringsLine};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangedString {
+    pub old_hash: StringHash,
+    pub new_hash: StringHash,
+    pub section: Option<String>,
+    /// The translation of the old base text. It's likely stale, so it isn't carried over.
+    pub old_value: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RemapReport {
+    /// Strings in the new base file without a translation. They keep the base text.
+    pub added: Vec<StringHash>,
+    /// Translated strings that no longer exist in the new base file.
+    pub removed: Vec<StringHash>,
+    /// Strings whose base text changed, detected by position within their section.
+    pub changed: Vec<ChangedString>,
+}
+
+impl RemapReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Aligns a translation to a new version of the base strings file.
+///
+/// `old_translation` must already carry the hashes of the base file it was written against,
+/// either by being parsed in hashed form or via [`StringsFile::rekey`]. The result follows
+/// the line layout of `new_base`, with every string the translation still covers filled in.
+pub fn remap(old_translation: &StringsFile, new_base: &StringsFile) -> (StringsFile, RemapReport) {
+    let mut translated: HashMap<StringHash, &StringsEntry> = HashMap::new();
+    for entry in old_translation.entries() {
+        translated.entry(entry.hash).or_insert(entry);
+    }
+    let new_hashes: HashSet<StringHash> = new_base.entries().map(|entry| entry.hash).collect();
+
+    // Strings that disappeared, keyed by where they were so we can spot edits in place
+    let mut removed_at: HashMap<(Option<&str>, usize), &StringsEntry> = HashMap::new();
+    let mut removed = Vec::new();
+    let mut seen = HashSet::new();
+    for (position, entry) in section_positions(old_translation) {
+        if new_hashes.contains(&entry.hash) || !seen.insert(entry.hash) {
+            continue;
+        }
+        removed_at.insert(position, entry);
+        removed.push(entry.hash);
+    }
+
+    let mut report = RemapReport::default();
+    let mut changed_hashes = HashSet::new();
+    let mut added_seen = HashSet::new();
+    for (position, entry) in section_positions(new_base) {
+        if translated.contains_key(&entry.hash) || !added_seen.insert(entry.hash) {
+            continue;
+        }
+        match removed_at.remove(&position) {
+            Some(old) => {
+                changed_hashes.insert(old.hash);
+                report.changed.push(ChangedString {
+                    old_hash: old.hash,
+                    new_hash: entry.hash,
+                    section: entry.section.clone(),
+                    old_value: old.value.clone(),
+                });
+            }
+            None => report.added.push(entry.hash),
+        }
+    }
+    report.removed = removed
+        .into_iter()
+        .filter(|hash| !changed_hashes.contains(hash))
+        .collect();
+
+    let lines = new_base
+        .lines
+        .iter()
+        .map(|(line, ending)| {
+            let line = match line {
+                StringsLine::Entry(entry) => match translated.get(&entry.hash) {
+                    Some(old) => StringsLine::Entry(StringsEntry {
+                        value: old.value.clone(),
+                        ..entry.clone()
+                    }),
+                    None => line.clone(),
+                },
+                StringsLine::Comment(_) => line.clone(),
+            };
+            (line, *ending)
+        })
+        .collect();
+    let remapped = StringsFile {
+        bom: old_translation.bom,
+        lines,
+    };
+
+    (remapped, report)
+}
+
+/// Pairs each entry with its section and its index among that section's entries.
+fn section_positions(
+    file: &StringsFile,
+) -> impl Iterator<Item = ((Option<&str>, usize), &StringsEntry)> {
+    let mut counts: HashMap<Option<&str>, usize> = HashMap::new();
+    file.entries().map(move |entry| {
+        let section = entry.section.as_deref();
+        let count = counts.entry(section).or_default();
+        let position = (section, *count);
+        *count += 1;
+        (position, entry)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{remap, ChangedString};
+    use crate::strings::{StringHash, StringsFile};
+
+    const OLD_BASE: &str = "# Menu\nPlay\nOptions\nQuit\n# Journal\nPeople\nBestiary\n";
+    const OLD_TRANSLATION: &str =
+        "# Menu\nJouer\nOptions\nQuitter\n# Journal\nPersonnes\nBestiaire\n";
+    const NEW_BASE: &str = "# Menu\nPlay\nDaily\nSettings\nQuit\n# Journal\nPeople\n";
+
+    fn parse(text: &str) -> StringsFile {
+        StringsFile::from_reader(text.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_remap() {
+        let mut old = parse(OLD_TRANSLATION);
+        old.rekey(&parse(OLD_BASE)).unwrap();
+        let (remapped, report) = remap(&old, &parse(NEW_BASE));
+
+        let mut out = Vec::new();
+        remapped.write(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "# Menu\nJouer\nDaily\nSettings\nQuitter\n# Journal\nPersonnes\n"
+        );
+
+        let hash = |text, section| StringHash::compute(text, Some(section));
+        // "Options" became "Daily" in place, while "Settings" is new
+        assert_eq!(
+            report.changed,
+            vec![ChangedString {
+                old_hash: hash("Options", "Menu"),
+                new_hash: hash("Daily", "Menu"),
+                section: Some("Menu".into()),
+                old_value: "Options".into(),
+            }]
+        );
+        assert_eq!(report.added, vec![hash("Settings", "Menu")]);
+        assert_eq!(report.removed, vec![hash("Bestiary", "Journal")]);
+    }
+
+    #[test]
+    fn test_remap_unchanged() {
+        let mut old = parse(OLD_TRANSLATION);
+        old.rekey(&parse(OLD_BASE)).unwrap();
+        let (remapped, report) = remap(&old, &parse(OLD_BASE));
+        assert!(report.is_empty());
+        assert_eq!(remapped, old);
+    }
+}
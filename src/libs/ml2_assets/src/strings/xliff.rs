@@ -0,0 +1,242 @@
+//! XLIFF 1.2 export and import for strings files.
+//!
+//! Each string becomes a `trans-unit` whose id is its [`StringHash`], with its section as a
+//! note.
+
+use std::io::{BufReader, Read, Write};
+
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use super::{StringHash, StringsError, StringsFile, TranslationUnit};
+
+const XLIFF_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:1.2";
+
+/// Writes `base` as an XLIFF document, with `translation`'s values as targets.
+///
+/// `original` names the source file, e.g. `strings00.str`, and the languages are BCP 47 tags.
+pub fn export<W: Write>(
+    base: &StringsFile,
+    translation: Option<&StringsFile>,
+    original: &str,
+    source_language: &str,
+    target_language: &str,
+    writer: &mut W,
+) -> Result<(), StringsError> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<xliff version="1.2" xmlns="{XLIFF_NAMESPACE}">"#)?;
+    writeln!(
+        writer,
+        r#"  <file original="{}" source-language="{}" target-language="{}" datatype="plaintext">"#,
+        escape(original),
+        escape(source_language),
+        escape(target_language),
+    )?;
+    writeln!(writer, "    <body>")?;
+    for unit in base.translation_units(translation) {
+        writeln!(
+            writer,
+            r#"      <trans-unit id="{0}" resname="{0}">"#,
+            unit.hash
+        )?;
+        writeln!(writer, "        <source>{}</source>", escape(&unit.source))?;
+        if let Some(target) = &unit.target {
+            writeln!(writer, "        <target>{}</target>", escape(target))?;
+        }
+        if let Some(section) = &unit.section {
+            writeln!(writer, "        <note>{}</note>", escape(section))?;
+        }
+        writeln!(writer, "      </trans-unit>")?;
+    }
+    writeln!(writer, "    </body>")?;
+    writeln!(writer, "  </file>")?;
+    writeln!(writer, "</xliff>")?;
+    Ok(())
+}
+
+/// Reads an XLIFF document and builds a translation with `base`'s layout.
+///
+/// Units without a target, or with an empty one, are treated as untranslated.
+pub fn import<R: Read>(reader: R, base: &StringsFile) -> Result<StringsFile, StringsError> {
+    let units = parse(reader)?;
+    base.apply_translations(&units)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Element {
+    Source,
+    Target,
+    Note,
+}
+
+impl Element {
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"source" => Some(Element::Source),
+            b"target" => Some(Element::Target),
+            b"note" => Some(Element::Note),
+            _ => None,
+        }
+    }
+}
+
+fn parse<R: Read>(reader: R) -> Result<Vec<TranslationUnit>, StringsError> {
+    let mut reader = Reader::from_reader(BufReader::new(reader));
+    let mut buf = Vec::new();
+
+    let mut units = Vec::new();
+    let mut unit: Option<TranslationUnit> = None;
+    let mut element: Option<Element> = None;
+    let mut text = String::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) => match start.local_name().as_ref() {
+                b"trans-unit" => {
+                    let id = start
+                        .try_get_attribute("id")?
+                        .ok_or_else(|| {
+                            StringsError::XliffSyntaxError("trans-unit without an id".into())
+                        })?
+                        .unescape_value()?;
+                    let hash: StringHash = id.parse().map_err(|_| {
+                        StringsError::XliffSyntaxError(format!("{id:?} isn't a string hash"))
+                    })?;
+                    unit = Some(TranslationUnit {
+                        hash,
+                        section: None,
+                        source: String::new(),
+                        target: None,
+                    });
+                }
+                // Inline markup such as <g> or <ph> is part of the surrounding text
+                name if unit.is_some() => {
+                    if let Some(started) = Element::from_name(name) {
+                        element = Some(started);
+                        text.clear();
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(content) if element.is_some() => {
+                text.push_str(&content.unescape()?);
+            }
+            Event::CData(content) if element.is_some() => {
+                text.push_str(&String::from_utf8_lossy(&content));
+            }
+            Event::End(end) => match end.local_name().as_ref() {
+                b"trans-unit" => {
+                    units.extend(unit.take());
+                }
+                name if element.is_some() && Element::from_name(name) == element => {
+                    if let (Some(current), Some(unit)) = (element.take(), unit.as_mut()) {
+                        let value = std::mem::take(&mut text);
+                        match current {
+                            Element::Source => unit.source = value,
+                            Element::Target => {
+                                unit.target = Some(value).filter(|target| !target.is_empty())
+                            }
+                            Element::Note => unit.section = Some(value),
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export, import};
+    use crate::strings::{StringHash, StringsError, StringsFile};
+
+    const BASE: &str = "# Menu\nPlay\nGot %d <coins> & gems\n# Journal\nPlay\n";
+    const TRANSLATION: &str = "# Menu\nJouer\n%d <pièces> & gemmes\n# Journal\nJouer\n";
+
+    fn parse(text: &str) -> StringsFile {
+        StringsFile::from_reader(text.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let base = parse(BASE);
+        let mut translation = parse(TRANSLATION);
+        translation.rekey(&base).unwrap();
+
+        let mut xliff = Vec::new();
+        export(
+            &base,
+            Some(&translation),
+            "strings00.str",
+            "en",
+            "fr",
+            &mut xliff,
+        )
+        .unwrap();
+        let xliff = String::from_utf8(xliff).unwrap();
+        assert!(xliff.contains("<source>Got %d &lt;coins&gt; &amp; gems</source>"));
+        assert!(xliff.contains("<note>Journal</note>"));
+
+        let imported = import(xliff.as_bytes(), &base).unwrap();
+        assert_eq!(imported, translation);
+    }
+
+    #[test]
+    fn test_import_checks() {
+        let base = parse(BASE);
+        let mut translation = parse(TRANSLATION);
+        translation.rekey(&base).unwrap();
+        translation
+            .set(
+                StringHash::compute("Got %d <coins> & gems", Some("Menu")),
+                "<pièces> & gemmes",
+            )
+            .unwrap();
+
+        let mut xliff = Vec::new();
+        export(
+            &base,
+            Some(&translation),
+            "strings00.str",
+            "en",
+            "fr",
+            &mut xliff,
+        )
+        .unwrap();
+        let err = import(&xliff[..], &base).unwrap_err();
+        assert!(matches!(err, StringsError::PlaceholderMismatch { .. }));
+    }
+
+    #[test]
+    fn test_import_inline_markup() {
+        let base = parse(BASE);
+        let mut translation = parse(TRANSLATION);
+        translation.rekey(&base).unwrap();
+
+        let mut xliff = Vec::new();
+        export(
+            &base,
+            Some(&translation),
+            "strings00.str",
+            "en",
+            "fr",
+            &mut xliff,
+        )
+        .unwrap();
+        // CAT tools wrap parts of a segment in inline elements
+        let xliff = String::from_utf8(xliff).unwrap().replacen(
+            "<target>Jouer</target>",
+            r#"<target>Jo<g id="1">u</g><x id="2"/>er</target>"#,
+            1,
+        );
+
+        let imported = import(xliff.as_bytes(), &base).unwrap();
+        assert_eq!(imported, translation);
+    }
+}
@@ -1,17 +1,169 @@
 use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
 
 use ml2_types::{Entity, Texture};
 
+const ENTITY_PREFIX: &str = "ENT_TYPE_";
+
 fn main() -> anyhow::Result<()> {
     let entities: HashMap<String, Entity> = serde_json::from_str(include_str!(
         "../../modlunky2/static/game_data/entities.json"
     ))?;
-
+    write_out("entity_type.rs", &entity_type(&entities)?)?;
     uneval::to_out_dir(entities, "entities.rs")?;
 
     let textures: HashMap<String, Texture> = serde_json::from_str(include_str!(
         "../../modlunky2/static/game_data/textures.json"
     ))?;
+    write_out("texture_id.rs", &texture_id(&textures)?)?;
     uneval::to_out_dir(textures, "textures.rs")?;
     Ok(())
 }
+
+fn write_out(name: &str, contents: &str) -> anyhow::Result<()> {
+    let path = PathBuf::from(std::env::var("OUT_DIR")?).join(name);
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+/// An `EntityType` enum with a variant per entity, e.g. `MONS_SNAKE` for `ENT_TYPE_MONS_SNAKE`.
+fn entity_type(entities: &HashMap<String, Entity>) -> anyhow::Result<String> {
+    let mut sorted: Vec<(&str, i64)> = entities
+        .iter()
+        .map(|(name, entity)| (name.as_str(), entity.id))
+        .collect();
+    sorted.sort_by_key(|(_, id)| *id);
+    let variant = |name: &str| -> anyhow::Result<String> {
+        name.strip_prefix(ENTITY_PREFIX)
+            .map(Into::into)
+            .ok_or_else(|| anyhow::anyhow!("{name} doesn't start with {ENTITY_PREFIX}"))
+    };
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "/// An entity type, named as in the game's `ENT_TYPE_*` constants."
+    )?;
+    writeln!(out, "#[allow(non_camel_case_types)]")?;
+    writeln!(
+        out,
+        "#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]"
+    )?;
+    writeln!(out, "#[repr(u32)]")?;
+    writeln!(out, "pub enum EntityType {{")?;
+    for (name, id) in &sorted {
+        writeln!(out, "    {} = {id},", variant(name)?)?;
+    }
+    writeln!(out, "}}")?;
+
+    writeln!(out, "impl EntityType {{")?;
+    writeln!(out, "    /// Every entity type, by id.")?;
+    writeln!(out, "    pub const ALL: &'static [EntityType] = &[")?;
+    for (name, _) in &sorted {
+        writeln!(out, "        EntityType::{},", variant(name)?)?;
+    }
+    writeln!(out, "    ];")?;
+
+    writeln!(out, "    /// The full name, e.g. `ENT_TYPE_MONS_SNAKE`.")?;
+    writeln!(out, "    pub fn name(self) -> &'static str {{")?;
+    writeln!(out, "        match self {{")?;
+    for (name, _) in &sorted {
+        writeln!(
+            out,
+            "            EntityType::{} => {name:?},",
+            variant(name)?
+        )?;
+    }
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+
+    writeln!(out, "    pub fn from_id(id: u32) -> Option<Self> {{")?;
+    writeln!(out, "        match id {{")?;
+    for (name, id) in &sorted {
+        writeln!(
+            out,
+            "            {id} => Some(EntityType::{}),",
+            variant(name)?
+        )?;
+    }
+    writeln!(out, "            _ => None,")?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+
+    writeln!(
+        out,
+        "    /// Looks up a full name, e.g. `ENT_TYPE_MONS_SNAKE`."
+    )?;
+    writeln!(out, "    pub fn from_name(name: &str) -> Option<Self> {{")?;
+    writeln!(out, "        match name {{")?;
+    for (name, _) in &sorted {
+        writeln!(
+            out,
+            "            {name:?} => Some(EntityType::{}),",
+            variant(name)?
+        )?;
+    }
+    writeln!(out, "            _ => None,")?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(out)
+}
+
+/// `TextureId` constants named after their sheet and id, e.g. `DATA_TEXTURES_FLOOR_CAVE_129`.
+///
+/// Several ids share a sheet, and the id keeps each name stable as textures are added.
+fn texture_id(textures: &HashMap<String, Texture>) -> anyhow::Result<String> {
+    let mut sorted: Vec<(i64, &str)> = textures
+        .iter()
+        .map(|(id, texture)| Ok((id.parse()?, texture.path.as_str())))
+        .collect::<anyhow::Result<_>>()?;
+    sorted.sort();
+
+    let mut names = Vec::with_capacity(sorted.len());
+    for (id, path) in &sorted {
+        let stem = path.strip_suffix(".DDS").unwrap_or(path);
+        let stem: String = stem
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        names.push((*id, format!("{stem}_{id}")));
+    }
+
+    let mut out = String::new();
+    writeln!(out, "impl TextureId {{")?;
+    for (id, name) in &names {
+        writeln!(out, "    pub const {name}: TextureId = TextureId({id});")?;
+    }
+
+    writeln!(
+        out,
+        "    /// The constant's name, e.g. `DATA_TEXTURES_FLOOR_CAVE_129`."
+    )?;
+    writeln!(out, "    pub fn name(self) -> Option<&'static str> {{")?;
+    writeln!(out, "        match self.0 {{")?;
+    for (id, name) in &names {
+        writeln!(out, "            {id} => Some({name:?}),")?;
+    }
+    writeln!(out, "            _ => None,")?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+
+    writeln!(out, "    pub fn from_name(name: &str) -> Option<Self> {{")?;
+    writeln!(out, "        match name {{")?;
+    for (_, name) in &names {
+        writeln!(out, "            {name:?} => Some(TextureId::{name}),")?;
+    }
+    writeln!(out, "            _ => None,")?;
+    writeln!(out, "        }}")?;
+    writeln!(out, "    }}")?;
+    writeln!(out, "}}")?;
+    Ok(out)
+}
@@ -0,0 +1,284 @@
+//! A catalog of entities for item spawners, grouped into categories and searchable by name.
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use ml2_types::Entity;
+use serde::{Deserialize, Serialize};
+
+use crate::GameData;
+
+const ENTITY_PREFIX: &str = "ENT_TYPE_";
+
+/// Categories, in the order a spawner lists them.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Category {
+    Item,
+    Monster,
+    Mount,
+    Character,
+    Floor,
+    ActiveFloor,
+    Liquid,
+    Decoration,
+    Background,
+    Effect,
+    Logical,
+    Other,
+}
+
+impl Category {
+    pub const ALL: [Category; 12] = [
+        Category::Item,
+        Category::Monster,
+        Category::Mount,
+        Category::Character,
+        Category::Floor,
+        Category::ActiveFloor,
+        Category::Liquid,
+        Category::Decoration,
+        Category::Background,
+        Category::Effect,
+        Category::Logical,
+        Category::Other,
+    ];
+
+    /// The category of an entity name like `ENT_TYPE_MONS_SNAKE`, with the rest of the name.
+    fn of(name: &str) -> (Self, &str) {
+        const PREFIXES: &[(&str, Category)] = &[
+            ("ITEM_", Category::Item),
+            ("MONS_", Category::Monster),
+            ("MOUNT_", Category::Mount),
+            ("CHAR_", Category::Character),
+            ("FLOOR_", Category::Floor),
+            ("FLOORSTYLED_", Category::Floor),
+            ("ACTIVEFLOOR_", Category::ActiveFloor),
+            ("LIQUID_", Category::Liquid),
+            ("DECORATION_", Category::Decoration),
+            ("EMBED_", Category::Decoration),
+            ("BG_", Category::Background),
+            ("MIDBG_", Category::Background),
+            ("FX_", Category::Effect),
+            ("LOGICAL_", Category::Logical),
+        ];
+        let name = name.strip_prefix(ENTITY_PREFIX).unwrap_or(name);
+        PREFIXES
+            .iter()
+            .find_map(|(prefix, category)| Some((*category, name.strip_prefix(prefix)?)))
+            .unwrap_or((Category::Other, name))
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Category::Item => "Items",
+            Category::Monster => "Monsters",
+            Category::Mount => "Mounts",
+            Category::Character => "Characters",
+            Category::Floor => "Floor",
+            Category::ActiveFloor => "Active Floor",
+            Category::Liquid => "Liquids",
+            Category::Decoration => "Decorations",
+            Category::Background => "Backgrounds",
+            Category::Effect => "Effects",
+            Category::Logical => "Logical",
+            Category::Other => "Other",
+        })
+    }
+}
+
+/// Where an entity's idle frame is in its texture sheet, in pixels.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Sprite {
+    pub texture: i64,
+    /// e.g. `Data/Textures/monstersbasic01.DDS`
+    pub path: String,
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+}
+
+impl Sprite {
+    /// Where [`Entity::idle_tile`] is in the entity's texture, if it has one.
+    fn of(data: &GameData, entity: &Entity) -> Option<Self> {
+        let texture = data.texture(entity.texture)?;
+        let columns = texture.num_tiles.width.max(1);
+        let index = entity.idle_tile(columns);
+        if index < 0 || index >= columns * texture.num_tiles.height {
+            return None;
+        }
+        Some(Self {
+            texture: entity.texture,
+            path: texture.path.clone(),
+            x: texture.offset.width + index % columns * texture.tile_width,
+            y: texture.offset.height + index / columns * texture.tile_height,
+            width: texture.tile_width,
+            height: texture.tile_height,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CatalogEntry {
+    /// e.g. `ENT_TYPE_MONS_SNAKE`
+    pub name: String,
+    pub id: i64,
+    pub category: Category,
+    /// Made from `name`, e.g. `Snake`.
+    pub display_name: String,
+    /// `None` for entities drawn with a theme's texture, or without one.
+    pub sprite: Option<Sprite>,
+}
+
+/// Every entity in some game data, sorted by category then display name.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Catalog {
+    entries: Vec<CatalogEntry>,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::new(&GameData::default())
+    }
+}
+
+impl Catalog {
+    pub fn new(data: &GameData) -> Self {
+        let mut entries: Vec<CatalogEntry> = data
+            .entities
+            .iter()
+            .map(|(name, entity)| {
+                let (category, rest) = Category::of(name);
+                CatalogEntry {
+                    name: name.clone(),
+                    id: entity.id,
+                    category,
+                    display_name: title_case(rest),
+                    sprite: Sprite::of(data, entity),
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            (a.category, &a.display_name, a.id).cmp(&(b.category, &b.display_name, b.id))
+        });
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[CatalogEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CatalogEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn category(&self, category: Category) -> impl Iterator<Item = &CatalogEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.category == category)
+    }
+
+    /// Entries whose display or entity name contains every word of `query`, ignoring case.
+    ///
+    /// Exact display name matches come first, then ones starting with the query, then the
+    /// rest in catalog order.
+    pub fn search(&self, query: &str) -> Vec<&CatalogEntry> {
+        let query = query.to_lowercase();
+        let words: Vec<&str> = query.split_whitespace().collect();
+        let mut found: Vec<(Reverse<u8>, usize, &CatalogEntry)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(position, entry)| {
+                let display_name = entry.display_name.to_lowercase();
+                let name = entry.name.to_lowercase();
+                if !words
+                    .iter()
+                    .all(|word| display_name.contains(word) || name.contains(word))
+                {
+                    return None;
+                }
+                let rank = if display_name == query.trim() {
+                    2
+                } else if display_name.starts_with(query.trim()) {
+                    1
+                } else {
+                    0
+                };
+                Some((Reverse(rank), position, entry))
+            })
+            .collect();
+        found.sort_by_key(|(rank, position, _)| (*rank, *position));
+        found.into_iter().map(|(_, _, entry)| entry).collect()
+    }
+}
+
+/// `PICKUP_ROPEPILE` to `Pickup Ropepile`.
+fn title_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let word = word.to_ascii_lowercase();
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => word,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Catalog, Category, Sprite};
+
+    #[test]
+    fn test_catalog() {
+        let catalog = Catalog::default();
+        assert_eq!(catalog.entries().len(), crate::ENTITIES.len());
+
+        let snake = catalog.get("ENT_TYPE_MONS_SNAKE").unwrap();
+        assert_eq!(snake.category, Category::Monster);
+        assert_eq!(snake.display_name, "Snake");
+        assert_eq!(
+            snake.sprite,
+            Some(Sprite {
+                texture: 308,
+                path: "Data/Textures/monstersbasic01.DDS".into(),
+                x: 256,
+                y: 0,
+                width: 128,
+                height: 128,
+            })
+        );
+        let rock = catalog.get("ENT_TYPE_ITEM_ROCK").unwrap();
+        assert_eq!(rock.sprite.as_ref().map(|s| (s.x, s.y)), Some((0, 128)));
+
+        assert!(catalog
+            .category(Category::Mount)
+            .all(|entry| entry.name.starts_with("ENT_TYPE_MOUNT_")));
+        let categories: Vec<Category> = catalog.entries().iter().map(|e| e.category).collect();
+        assert!(categories.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn test_search() {
+        let catalog = Catalog::default();
+        let found: Vec<&str> = catalog
+            .search("SNAKE")
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        // The exact display name comes first
+        assert_eq!(found[0], "ENT_TYPE_MONS_SNAKE");
+
+        let found = catalog.search("rock");
+        assert_eq!(found[0].name, "ENT_TYPE_ITEM_ROCK");
+        assert!(found.len() > 1);
+        assert!(catalog.search("mons snake").iter().any(|e| e.id == 220));
+        assert!(catalog.search("not a thing").is_empty());
+    }
+}
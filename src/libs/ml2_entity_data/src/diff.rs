@@ -0,0 +1,205 @@
+//! Compares two snapshots of game data, e.g. before and after a game patch.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::GameData;
+
+/// A change to one field, named by its path, e.g. `life` or `animations.2.interval`.
+///
+/// `old` is `None` for added fields and `new` is `None` for removed ones, such as animations.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "(none)".into(),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.field,
+            show(&self.old),
+            show(&self.new)
+        )
+    }
+}
+
+/// Differences between two maps of entities or textures, keyed as in the JSON files.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MapDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Field changes of each key in both maps, for keys with any.
+    pub changed: BTreeMap<String, Vec<FieldChange>>,
+}
+
+impl MapDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GameDataDiff {
+    pub entities: MapDiff,
+    pub textures: MapDiff,
+}
+
+impl GameDataDiff {
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.textures.is_empty()
+    }
+}
+
+impl fmt::Display for GameDataDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (kind, diff) in [("entity", &self.entities), ("texture", &self.textures)] {
+            for key in &diff.added {
+                writeln!(f, "+ {kind} {key}")?;
+            }
+            for key in &diff.removed {
+                writeln!(f, "- {kind} {key}")?;
+            }
+            for (key, changes) in &diff.changed {
+                writeln!(f, "~ {kind} {key}")?;
+                for change in changes {
+                    writeln!(f, "    {change}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares the entities and textures of two snapshots.
+pub fn diff(old: &GameData, new: &GameData) -> GameDataDiff {
+    GameDataDiff {
+        entities: diff_map(&old.entities, &new.entities),
+        textures: diff_map(&old.textures, &new.textures),
+    }
+}
+
+/// Compares two maps of entities or textures field by field.
+pub fn diff_map<T: Serialize>(old: &HashMap<String, T>, new: &HashMap<String, T>) -> MapDiff {
+    let old_keys: BTreeSet<&String> = old.keys().collect();
+    let new_keys: BTreeSet<&String> = new.keys().collect();
+
+    let mut changed = BTreeMap::new();
+    for key in old_keys.intersection(&new_keys) {
+        let mut changes = Vec::new();
+        diff_values(
+            String::new(),
+            Some(to_value(&old[*key])),
+            Some(to_value(&new[*key])),
+            &mut changes,
+        );
+        if !changes.is_empty() {
+            changed.insert((*key).clone(), changes);
+        }
+    }
+
+    MapDiff {
+        added: sorted_keys(new_keys.difference(&old_keys).map(|key| (*key).clone())),
+        removed: sorted_keys(old_keys.difference(&new_keys).map(|key| (*key).clone())),
+        changed,
+    }
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    // Entities and textures only hold numbers, strings and string-keyed maps
+    serde_json::to_value(value).expect("game data is representable as JSON")
+}
+
+/// Keys are sorted numerically where they're ids, e.g. animations and textures.
+fn sorted_keys(keys: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut keys: Vec<String> = keys.into_iter().collect();
+    keys.sort_by_key(|key| (key.parse::<i64>().ok(), key.clone()));
+    keys
+}
+
+fn diff_values(
+    field: String,
+    old: Option<Value>,
+    new: Option<Value>,
+    changes: &mut Vec<FieldChange>,
+) {
+    match (old, new) {
+        (Some(Value::Object(mut old)), Some(Value::Object(mut new))) => {
+            let keys: BTreeSet<String> = old.keys().chain(new.keys()).cloned().collect();
+            for key in sorted_keys(keys) {
+                let path = if field.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{field}.{key}")
+                };
+                diff_values(path, old.remove(&key), new.remove(&key), changes);
+            }
+        }
+        (old, new) if old != new => changes.push(FieldChange { field, old, new }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{diff, FieldChange};
+    use crate::GameData;
+
+    #[test]
+    fn test_diff() {
+        let old = GameData::default();
+        assert!(diff(&old, &old).is_empty());
+
+        let mut new = old.clone();
+        new.entities.remove("ENT_TYPE_MONS_SNAKE");
+        let mut cobra = new.entities["ENT_TYPE_MONS_COBRA"].clone();
+        cobra.id = 10_000;
+        new.entities.insert("ENT_TYPE_MONS_BIG_COBRA".into(), cobra);
+        let caveman = new.entities.get_mut("ENT_TYPE_MONS_CAVEMAN").unwrap();
+        caveman.life += 1;
+        caveman.max_speed = 0.5;
+        caveman.animations.remove("0");
+        new.textures.get_mut("373").unwrap().tile_width = 64;
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.entities.added, vec!["ENT_TYPE_MONS_BIG_COBRA"]);
+        assert_eq!(diff.entities.removed, vec!["ENT_TYPE_MONS_SNAKE"]);
+        assert_eq!(diff.entities.changed.len(), 1);
+
+        let old_caveman = &old.entities["ENT_TYPE_MONS_CAVEMAN"];
+        let changes = &diff.entities.changed["ENT_TYPE_MONS_CAVEMAN"];
+        let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["animations.0", "life", "max_speed"]);
+        assert_eq!(changes[0].new, None);
+        assert_eq!(
+            changes[1],
+            FieldChange {
+                field: "life".into(),
+                old: Some(json!(old_caveman.life)),
+                new: Some(json!(old_caveman.life + 1)),
+            }
+        );
+        assert_eq!(
+            changes[1].to_string(),
+            format!("life: {} -> {}", old_caveman.life, old_caveman.life + 1)
+        );
+
+        let texture_changes = &diff.textures.changed["373"];
+        assert_eq!(texture_changes[0].field, "tile_width");
+        assert!(diff
+            .to_string()
+            .contains("~ texture 373\n    tile_width: 128 -> 64\n"));
+    }
+}
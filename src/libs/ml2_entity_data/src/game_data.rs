@@ -0,0 +1,153 @@
+//! Game data loaded at runtime, for tools that need to match the user's game build.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use ml2_types::{Entity, Mask, Texture};
+use thiserror::Error;
+
+use crate::{ENTITIES, TEXTURES};
+
+pub const ENTITIES_FILE: &str = "entities.json";
+pub const TEXTURES_FILE: &str = "textures.json";
+/// Optional, holding the game build the data was extracted from.
+pub const VERSION_FILE: &str = "version.txt";
+
+#[derive(Error, Debug)]
+pub enum GameDataError {
+    #[error("IoError")]
+    IoError(#[from] std::io::Error),
+
+    #[error("JSON error in {0}: {1}")]
+    JsonError(&'static str, serde_json::Error),
+}
+
+/// Entities by name (e.g. `ENT_TYPE_MONS_SNAKE`) and textures by id, as in the game's
+/// `entities.json` and `textures.json`.
+///
+/// The default is the data compiled into this crate.
+#[derive(Clone, Debug)]
+pub struct GameData {
+    /// The game build the data is from, if known. `None` for the compiled-in data.
+    pub version: Option<String>,
+    pub entities: HashMap<String, Entity>,
+    pub textures: HashMap<String, Texture>,
+}
+
+impl Default for GameData {
+    fn default() -> Self {
+        Self {
+            version: None,
+            entities: ENTITIES.clone(),
+            textures: TEXTURES.clone(),
+        }
+    }
+}
+
+impl GameData {
+    pub fn from_readers<E: Read, T: Read>(entities: E, textures: T) -> Result<Self, GameDataError> {
+        Ok(Self {
+            version: None,
+            entities: serde_json::from_reader(entities)
+                .map_err(|err| GameDataError::JsonError(ENTITIES_FILE, err))?,
+            textures: serde_json::from_reader(textures)
+                .map_err(|err| GameDataError::JsonError(TEXTURES_FILE, err))?,
+        })
+    }
+
+    /// Loads `entities.json`, `textures.json` and, if present, `version.txt` from `dir`.
+    pub fn load(dir: &Path) -> Result<Self, GameDataError> {
+        let entities = BufReader::new(File::open(dir.join(ENTITIES_FILE))?);
+        let textures = BufReader::new(File::open(dir.join(TEXTURES_FILE))?);
+        let mut data = Self::from_readers(entities, textures)?;
+        data.version = match std::fs::read_to_string(dir.join(VERSION_FILE)) {
+            Ok(version) => Some(version.trim().to_string()).filter(|v| !v.is_empty()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(data)
+    }
+
+    /// Loads from `dir` if it has game data, falling back to the compiled-in data otherwise.
+    pub fn load_or_default(dir: &Path) -> Result<Self, GameDataError> {
+        if dir.join(ENTITIES_FILE).exists() || dir.join(TEXTURES_FILE).exists() {
+            Self::load(dir)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn entity(&self, name: &str) -> Option<&Entity> {
+        self.entities.get(name)
+    }
+
+    pub fn texture(&self, id: i64) -> Option<&Texture> {
+        self.textures.get(&id.to_string())
+    }
+
+    /// Entities whose search mask has any of `mask`'s bits, e.g. every monster.
+    pub fn entities_in(&self, mask: Mask) -> impl Iterator<Item = (&String, &Entity)> {
+        self.entities
+            .iter()
+            .filter(move |(_, entity)| entity.search_mask().intersects(mask))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ml2_types::{Mask, Technique};
+
+    use super::{GameData, GameDataError, ENTITIES_FILE, TEXTURES_FILE, VERSION_FILE};
+
+    const ENTITIES_JSON: &str = include_str!("../../../modlunky2/static/game_data/entities.json");
+    const TEXTURES_JSON: &str = include_str!("../../../modlunky2/static/game_data/textures.json");
+
+    #[test]
+    fn test_load() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            GameData::load(dir.path()),
+            Err(GameDataError::IoError(_))
+        ));
+        let data = GameData::load_or_default(dir.path()).unwrap();
+        assert_eq!(data.version, None);
+        assert_eq!(data.entities.len(), crate::ENTITIES.len());
+
+        fs::write(dir.path().join(ENTITIES_FILE), ENTITIES_JSON).unwrap();
+        fs::write(dir.path().join(TEXTURES_FILE), TEXTURES_JSON).unwrap();
+        let data = GameData::load_or_default(dir.path()).unwrap();
+        assert_eq!(data.version, None);
+        assert_eq!(data.entities.len(), crate::ENTITIES.len());
+        assert_eq!(data.textures.len(), crate::TEXTURES.len());
+        let snake = data.entity("ENT_TYPE_MONS_SNAKE").unwrap();
+        assert_eq!(snake.attach_offset_x, 0.25);
+        assert_eq!(
+            data.texture(snake.texture).unwrap().path,
+            "Data/Textures/monstersbasic01.DDS"
+        );
+
+        fs::write(dir.path().join(VERSION_FILE), "1.28.1\n").unwrap();
+        let data = GameData::load(dir.path()).unwrap();
+        assert_eq!(data.version.as_deref(), Some("1.28.1"));
+
+        let monsters: Vec<&String> = data.entities_in(Mask::MONSTER).map(|(n, _)| n).collect();
+        assert!(monsters.contains(&&"ENT_TYPE_MONS_SNAKE".to_string()));
+        assert!(monsters
+            .iter()
+            .all(|name| name.starts_with("ENT_TYPE_MONS_")));
+        assert!(data
+            .entities
+            .values()
+            .all(|entity| !matches!(entity.draw_technique(), Technique::Other(_))));
+
+        fs::write(dir.path().join(TEXTURES_FILE), "{").unwrap();
+        assert!(matches!(
+            GameData::load(dir.path()),
+            Err(GameDataError::JsonError(TEXTURES_FILE, _))
+        ));
+    }
+}
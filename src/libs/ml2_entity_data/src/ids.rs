@@ -0,0 +1,102 @@
+use std::fmt;
+use std::str::FromStr;
+
+use ml2_types::{Entity, Texture};
+
+use crate::{ENTITIES, TEXTURES};
+
+include!(concat!(env!("OUT_DIR"), "/entity_type.rs"));
+include!(concat!(env!("OUT_DIR"), "/texture_id.rs"));
+
+impl EntityType {
+    pub fn id(self) -> u32 {
+        self as u32
+    }
+
+    pub fn entity(self) -> &'static Entity {
+        &ENTITIES[self.name()]
+    }
+
+    pub fn texture(self) -> TextureId {
+        TextureId(self.entity().texture)
+    }
+}
+
+impl fmt::Display for EntityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for EntityType {
+    type Err = UnknownName;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s).ok_or_else(|| UnknownName(s.into()))
+    }
+}
+
+/// A texture id, as used by [`Entity::texture`] and [`Animation`](ml2_types::Animation).
+///
+/// Negative ids are textures that depend on the level's theme, and have no constant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextureId(pub i64);
+
+impl TextureId {
+    pub fn texture(self) -> Option<&'static Texture> {
+        TEXTURES.get(&self.0.to_string())
+    }
+}
+
+impl fmt::Display for TextureId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownName(pub String);
+
+impl fmt::Display for UnknownName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown entity type {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownName {}
+
+#[cfg(test)]
+mod tests {
+    use super::{EntityType, TextureId};
+    use crate::{ENTITIES, TEXTURES};
+
+    #[test]
+    fn test_lookups() {
+        assert_eq!(EntityType::ALL.len(), ENTITIES.len());
+        for entity_type in EntityType::ALL {
+            assert_eq!(EntityType::from_id(entity_type.id()), Some(*entity_type));
+            assert_eq!(entity_type.entity().id, i64::from(entity_type.id()));
+        }
+
+        let snake: EntityType = "ENT_TYPE_MONS_SNAKE".parse().unwrap();
+        assert_eq!(snake, EntityType::MONS_SNAKE);
+        assert_eq!(snake.to_string(), "ENT_TYPE_MONS_SNAKE");
+        assert_eq!(
+            snake.texture(),
+            TextureId::DATA_TEXTURES_MONSTERSBASIC01_308
+        );
+        assert!("ENT_TYPE_MONS_SNAKEE".parse::<EntityType>().is_err());
+
+        let items = TextureId::DATA_TEXTURES_ITEMS_373;
+        assert_eq!(items.texture().unwrap().path, "Data/Textures/items.DDS");
+        assert_eq!(TextureId::from_name(&items.to_string()), Some(items));
+        assert_eq!(TextureId::DATA_TEXTURES_FLOOR_CAVE_137, TextureId(137));
+        assert_eq!(TextureId(-5).to_string(), "-5");
+        assert!(TEXTURES
+            .keys()
+            .all(|id| TextureId(id.parse().unwrap()).name().is_some()));
+    }
+}
@@ -1,3 +1,8 @@
+mod catalog;
+mod diff;
+mod game_data;
+mod ids;
+
 use std::collections::HashMap;
 
 use lazy_static::lazy_static;
@@ -8,6 +13,11 @@ use ml2_types::RectCollision;
 use ml2_types::Size;
 use ml2_types::Texture;
 
+pub use catalog::{Catalog, CatalogEntry, Category, Sprite};
+pub use diff::{diff, diff_map, FieldChange, GameDataDiff, MapDiff};
+pub use game_data::{GameData, GameDataError, ENTITIES_FILE, TEXTURES_FILE, VERSION_FILE};
+pub use ids::{EntityType, TextureId, UnknownName};
+
 lazy_static! {
     pub static ref ENTITIES: HashMap<String, Entity> =
         include!(concat!(env!("OUT_DIR"), "/entities.rs"));
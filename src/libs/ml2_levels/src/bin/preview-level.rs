@@ -0,0 +1,34 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use ml2_levels::{LevelFile, Previewer};
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<PathBuf> = std::env::args_os().skip(1).map(Into::into).collect();
+    let [level_path, extract_dir, out_dir] = &args[..] else {
+        anyhow::bail!("Usage: ml2-preview-level <level.lvl> <extract dir> <output dir>");
+    };
+
+    let level = LevelFile::from_path(level_path)?;
+    // Most levels rely on tile codes from generic.lvl
+    let generic_path = extract_dir.join(Path::new("Data/Levels/generic.lvl"));
+    let generic = if generic_path.is_file() {
+        Some(LevelFile::from_path(generic_path)?)
+    } else {
+        None
+    };
+    let levels: Vec<&LevelFile> = std::iter::once(&level).chain(generic.as_ref()).collect();
+
+    std::fs::create_dir_all(out_dir)?;
+    let mut previewer = Previewer::new(extract_dir);
+    for template in level.templates() {
+        for (idx, image) in previewer.template(&levels, template)?.iter().enumerate() {
+            let path = out_dir.join(format!("{}-{idx}.png", template.name));
+            image
+                .save(&path)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,468 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::line::{decode, encode, split_lines, Decor, LineEnding, LineParts};
+use crate::template::{Chunk, ChunkLine, Template};
+use crate::LevelError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DirectiveKind {
+    /// `\-`, e.g. `\-size 4 4`
+    LevelSetting,
+    /// `\?`, e.g. `\?floor 1`
+    TileCode,
+    /// `\%`, e.g. `\%arrowtrap_chance 35`
+    LevelChance,
+    /// `\+`, e.g. `\+snake 20`
+    MonsterChance,
+}
+
+impl DirectiveKind {
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            DirectiveKind::LevelSetting => r"\-",
+            DirectiveKind::TileCode => r"\?",
+            DirectiveKind::LevelChance => r"\%",
+            DirectiveKind::MonsterChance => r"\+",
+        }
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        [
+            DirectiveKind::LevelSetting,
+            DirectiveKind::TileCode,
+            DirectiveKind::LevelChance,
+            DirectiveKind::MonsterChance,
+        ]
+        .into_iter()
+        .find(|kind| line.starts_with(kind.prefix()))
+    }
+}
+
+const TEMPLATE_PREFIX: &str = r"\.";
+
+/// A single-line `name value` directive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Directive {
+    pub kind: DirectiveKind,
+    pub name: String,
+    /// The value as written, e.g. `1` for a tile code or `0, 5, 10, 0` for a chance.
+    pub value: String,
+    /// Everything after `//`, verbatim.
+    pub comment: Option<String>,
+    pub(crate) decor: Decor,
+}
+
+impl Directive {
+    pub fn new(kind: DirectiveKind, name: &str, value: &str) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+            value: value.into(),
+            comment: None,
+            decor: Decor::default(),
+        }
+    }
+
+    /// Parses a chance's value, which is either one number or one per level of an area.
+    pub fn chance_values(&self) -> Result<Vec<i64>, LevelError> {
+        self.value
+            .split(',')
+            .map(|v| {
+                v.trim()
+                    .parse()
+                    .map_err(|_| LevelError::InvalidValue(self.name.clone(), self.value.clone()))
+            })
+            .collect()
+    }
+
+    fn parse(
+        kind: DirectiveKind,
+        line: &str,
+        line_num: usize,
+        ending: LineEnding,
+    ) -> Result<Self, LevelError> {
+        let parts = LineParts::split(line, true);
+        let name = &parts.head[kind.prefix().len()..];
+        if name.is_empty() {
+            return Err(LevelError::MissingName(line_num));
+        }
+        Ok(Self {
+            kind,
+            name: name.into(),
+            value: parts.tail.into(),
+            comment: parts.comment.map(Into::into),
+            decor: Decor {
+                indent: parts.indent.into(),
+                gap: parts.gap.into(),
+                trailing: parts.trailing.into(),
+                ending,
+            },
+        })
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push_str(&self.decor.indent);
+        out.push_str(self.kind.prefix());
+        out.push_str(&self.name);
+        if !self.value.is_empty() {
+            out.push_str(if self.decor.gap.is_empty() {
+                " "
+            } else {
+                &self.decor.gap
+            });
+            out.push_str(&self.value);
+        }
+        write_comment(out, &self.decor, self.comment.as_deref());
+    }
+}
+
+/// A blank line, a comment, or any other line that isn't a directive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trivia {
+    pub text: String,
+    pub(crate) ending: LineEnding,
+}
+
+impl Trivia {
+    pub fn new(text: &str) -> Self {
+        Self::with_ending(text, LineEnding::default())
+    }
+
+    pub(crate) fn with_ending(text: &str, ending: LineEnding) -> Self {
+        Self {
+            text: text.into(),
+            ending,
+        }
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    pub fn is_comment(&self) -> bool {
+        self.text.trim_start().starts_with("//")
+    }
+
+    pub(crate) fn write(&self, out: &mut String) {
+        out.push_str(&self.text);
+        out.push_str(self.ending.as_str());
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Item {
+    Trivia(Trivia),
+    Directive(Directive),
+    Template(Template),
+}
+
+/// A parsed .lvl file.
+///
+/// Items are kept in file order along with their whitespace and comments, so writing an
+/// unedited file reproduces it exactly.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LevelFile {
+    pub items: Vec<Item>,
+}
+
+impl LevelFile {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, LevelError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, LevelError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LevelError> {
+        let text = decode(bytes);
+        let lines = split_lines(&text);
+
+        let mut items = Vec::new();
+        let mut idx = 0;
+        while idx < lines.len() {
+            let (line, ending) = lines[idx];
+            let trimmed = line.trim_start();
+            idx += 1;
+
+            if let Some(kind) = DirectiveKind::from_line(trimmed) {
+                items.push(Item::Directive(Directive::parse(kind, line, idx, ending)?));
+            } else if trimmed.starts_with(TEMPLATE_PREFIX) {
+                let parts = LineParts::split(line, true);
+                let name = &parts.head[TEMPLATE_PREFIX.len()..];
+                if name.is_empty() {
+                    return Err(LevelError::MissingName(idx));
+                }
+
+                // The body runs until the next directive. Trailing comments and blank lines
+                // usually introduce whatever follows, so they're left outside the template.
+                let start = idx;
+                while idx < lines.len() && !starts_directive(lines[idx].0) {
+                    idx += 1;
+                }
+                let mut end = idx;
+                while end > start && is_trivia(lines[end - 1].0) {
+                    end -= 1;
+                }
+
+                items.push(Item::Template(Template {
+                    name: name.into(),
+                    comment: parts.comment.map(Into::into),
+                    chunks: parse_chunks(&lines[start..end]),
+                    decor: Decor {
+                        indent: parts.indent.into(),
+                        gap: String::new(),
+                        trailing: format!("{}{}", parts.gap, parts.tail) + parts.trailing,
+                        ending,
+                    },
+                }));
+                items.extend(
+                    lines[end..idx]
+                        .iter()
+                        .map(|(line, ending)| Item::Trivia(Trivia::with_ending(line, *ending))),
+                );
+            } else {
+                items.push(Item::Trivia(Trivia::with_ending(line, ending)));
+            }
+        }
+
+        Ok(Self { items })
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), LevelError> {
+        writer.write_all(&self.to_bytes()?)?;
+        Ok(())
+    }
+
+    pub fn write_path(&self, path: impl AsRef<Path>) -> Result<(), LevelError> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, LevelError> {
+        let mut out = String::new();
+        for item in &self.items {
+            match item {
+                Item::Trivia(trivia) => trivia.write(&mut out),
+                Item::Directive(directive) => directive.write(&mut out),
+                Item::Template(template) => {
+                    out.push_str(&template.decor.indent);
+                    out.push_str(TEMPLATE_PREFIX);
+                    out.push_str(&template.name);
+                    write_comment(&mut out, &template.decor, template.comment.as_deref());
+                    for line in template.chunks.iter().flat_map(|chunk| &chunk.lines) {
+                        line.write(&mut out);
+                    }
+                }
+            }
+        }
+        encode(&out)
+    }
+
+    pub fn directives(&self, kind: DirectiveKind) -> impl Iterator<Item = &Directive> {
+        self.items.iter().filter_map(move |item| match item {
+            Item::Directive(directive) if directive.kind == kind => Some(directive),
+            _ => None,
+        })
+    }
+
+    pub fn level_settings(&self) -> impl Iterator<Item = &Directive> {
+        self.directives(DirectiveKind::LevelSetting)
+    }
+
+    pub fn tile_codes(&self) -> impl Iterator<Item = &Directive> {
+        self.directives(DirectiveKind::TileCode)
+    }
+
+    pub fn level_chances(&self) -> impl Iterator<Item = &Directive> {
+        self.directives(DirectiveKind::LevelChance)
+    }
+
+    pub fn monster_chances(&self) -> impl Iterator<Item = &Directive> {
+        self.directives(DirectiveKind::MonsterChance)
+    }
+
+    pub fn templates(&self) -> impl Iterator<Item = &Template> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Template(template) => Some(template),
+            _ => None,
+        })
+    }
+
+    pub fn directive(&self, kind: DirectiveKind, name: &str) -> Option<&Directive> {
+        self.directives(kind)
+            .find(|directive| directive.name == name)
+    }
+
+    pub fn directive_mut(&mut self, kind: DirectiveKind, name: &str) -> Option<&mut Directive> {
+        self.items.iter_mut().find_map(|item| match item {
+            Item::Directive(directive) if directive.kind == kind && directive.name == name => {
+                Some(directive)
+            }
+            _ => None,
+        })
+    }
+
+    /// Finds the tile code that a room character stands for.
+    pub fn tile_code_for(&self, code: char) -> Option<&Directive> {
+        self.tile_codes()
+            .find(|directive| directive.value.chars().eq(std::iter::once(code)))
+    }
+
+    pub fn template(&self, name: &str) -> Option<&Template> {
+        self.templates().find(|template| template.name == name)
+    }
+
+    pub fn template_mut(&mut self, name: &str) -> Option<&mut Template> {
+        self.items.iter_mut().find_map(|item| match item {
+            Item::Template(template) if template.name == name => Some(template),
+            _ => None,
+        })
+    }
+}
+
+fn write_comment(out: &mut String, decor: &Decor, comment: Option<&str>) {
+    out.push_str(&decor.trailing);
+    if let Some(comment) = comment {
+        out.push_str("//");
+        out.push_str(comment);
+    }
+    out.push_str(decor.ending.as_str());
+}
+
+fn starts_directive(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with(TEMPLATE_PREFIX) || DirectiveKind::from_line(trimmed).is_some()
+}
+
+fn is_trivia(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() || trimmed.starts_with("//")
+}
+
+/// Splits a template's body into chunks. A chunk ends at a blank line, or when a comment or
+/// setting follows its rows.
+fn parse_chunks(lines: &[(&str, LineEnding)]) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut chunk = Chunk::default();
+    for (line, ending) in lines {
+        let line = ChunkLine::parse(line, Decor::with_ending(*ending));
+        let starts_new = match &line {
+            ChunkLine::Trivia(trivia) if trivia.is_blank() => chunk.has_content(),
+            ChunkLine::Trivia(_) | ChunkLine::Setting(..) => chunk.is_room(),
+            ChunkLine::Row(_) => false,
+        };
+        if starts_new {
+            chunks.push(std::mem::take(&mut chunk));
+        }
+        chunk.lines.push(line);
+    }
+    if !chunk.lines.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DirectiveKind, Item, LevelFile};
+    use crate::template::{Row, TemplateSetting};
+
+    const SUNKEN_CITY: &[u8] = include_bytes!("../tests/data/sunkencity.lvl");
+
+    #[test]
+    fn test_round_trip() {
+        let level = LevelFile::from_bytes(SUNKEN_CITY).unwrap();
+        assert_eq!(level.to_bytes().unwrap(), SUNKEN_CITY);
+
+        let crlf = String::from_utf8_lossy(SUNKEN_CITY).replace('\n', "\r\n");
+        let level = LevelFile::from_bytes(crlf.as_bytes()).unwrap();
+        assert_eq!(level.to_bytes().unwrap(), crlf.as_bytes());
+
+        let odd = b"  \\?floor\t1  //comment\n\\.x   // y\n  \\!  flip \n11 \xe900  \n\n";
+        let level = LevelFile::from_bytes(odd).unwrap();
+        assert_eq!(level.to_bytes().unwrap(), odd);
+
+        let cp1252 = b"// \x93quoted\x94 \x80 \x81\n";
+        let level = LevelFile::from_bytes(cp1252).unwrap();
+        assert!(matches!(
+            &level.items[0],
+            Item::Trivia(trivia) if trivia.text == "// \u{201C}quoted\u{201D} \u{20AC} \u{81}"
+        ));
+        assert_eq!(level.to_bytes().unwrap(), cp1252);
+    }
+
+    #[test]
+    fn test_parse() {
+        let level = LevelFile::from_bytes(SUNKEN_CITY).unwrap();
+
+        let setting = level
+            .directive(DirectiveKind::LevelSetting, "liquid_gravity")
+            .unwrap();
+        assert_eq!(setting.value, "10.0");
+        assert_eq!(
+            setting.comment.as_deref(),
+            Some(" Liquid vertical gravity (default -10.0)")
+        );
+        assert_eq!(level.tile_codes().count(), 40);
+        assert_eq!(level.tile_code_for('$').unwrap().name, "shopkeeper");
+        assert_eq!(
+            level
+                .directive(DirectiveKind::LevelChance, "arrowtrap_chance")
+                .unwrap()
+                .chance_values()
+                .unwrap(),
+            vec![35]
+        );
+        assert_eq!(level.monster_chances().count(), 5);
+
+        let side = level.template("side").unwrap();
+        let rooms: Vec<_> = side.rooms().collect();
+        assert_eq!(rooms.len(), 3);
+        assert!(rooms[1].has_setting(&TemplateSetting::Liquid));
+        assert_eq!(rooms[1].foreground()[1], "=www=====0");
+        assert_eq!(rooms[1].foreground().len(), 8);
+        assert_eq!(rooms[1].background(), None);
+
+        let coffin = level.template("coffin_player").unwrap();
+        assert_eq!(
+            coffin.comment.as_deref(),
+            Some(" Coffin room holding a dead player (both sides must be open)")
+        );
+
+        // Banners before a template aren't part of the previous one
+        assert!(level.items.iter().any(|item| matches!(
+            item,
+            Item::Trivia(trivia) if trivia.text.starts_with("////")
+        )));
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut level = LevelFile::from_bytes(SUNKEN_CITY).unwrap();
+        level
+            .directive_mut(DirectiveKind::TileCode, "floor")
+            .unwrap()
+            .value = "F".into();
+        let template = level.template_mut("chunk_door").unwrap();
+        template.chunks.truncate(1);
+        template.chunks[0]
+            .lines
+            .push(crate::template::ChunkLine::Row(Row::new(
+                "111111",
+                Some("222222"),
+            )));
+
+        let edited = LevelFile::from_bytes(&level.to_bytes().unwrap()).unwrap();
+        assert_eq!(edited.tile_code_for('F').unwrap().name, "floor");
+        let rooms: Vec<_> = edited.template("chunk_door").unwrap().rooms().collect();
+        assert_eq!(rooms.len(), 1);
+        assert_eq!(rooms[0].foreground().len(), 4);
+        assert_eq!(rooms[0].background().unwrap()[3], "222222");
+    }
+}
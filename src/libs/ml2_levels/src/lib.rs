@@ -0,0 +1,36 @@
+mod level_file;
+mod line;
+mod lint;
+mod merge;
+mod preview;
+mod template;
+
+use thiserror::Error;
+
+pub use level_file::{Directive, DirectiveKind, Item, LevelFile, Trivia};
+pub use line::Decor;
+pub use lint::{lint, Issue, Severity};
+pub use merge::{merge, Conflict, ConflictKind, MergeTarget, MergedLevel};
+pub use preview::{tile_code_entity, Layer, Previewer};
+pub use template::{Chunk, ChunkLine, Row, Template, TemplateSetting};
+
+#[derive(Error, Debug)]
+pub enum LevelError {
+    #[error("IoError")]
+    IoError(#[from] std::io::Error),
+
+    #[error("ImageError")]
+    ImageError(#[from] image::ImageError),
+
+    #[error("Directive on line {0} is missing a name")]
+    MissingName(usize),
+
+    #[error("{0} has an invalid value: {1:?}")]
+    InvalidValue(String, String),
+
+    #[error("{0:?} can't be written in a level file")]
+    UnencodableChar(char),
+
+    #[error("{0} is too large to render at a tile size of {1}")]
+    TooLarge(String, u32),
+}
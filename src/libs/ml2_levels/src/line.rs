@@ -0,0 +1,150 @@
+use crate::LevelError;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum LineEnding {
+    #[default]
+    CrLf,
+    Lf,
+    /// Only valid for the last line of a file.
+    None,
+}
+
+impl LineEnding {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Lf => "\n",
+            LineEnding::None => "",
+        }
+    }
+}
+
+/// Whitespace around the parts of a line, kept so unedited lines are written back exactly.
+///
+/// This is opaque. New lines get single spaces between their parts and a CRLF ending.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Decor {
+    /// Before the first non-whitespace character.
+    pub(crate) indent: String,
+    /// Between the name (or foreground) and the value (or background). For room settings,
+    /// between `\!` and the name.
+    pub(crate) gap: String,
+    /// After the value, before any comment.
+    pub(crate) trailing: String,
+    pub(crate) ending: LineEnding,
+}
+
+impl Default for Decor {
+    fn default() -> Self {
+        Self {
+            indent: String::new(),
+            gap: " ".into(),
+            trailing: String::new(),
+            ending: LineEnding::default(),
+        }
+    }
+}
+
+impl Decor {
+    pub(crate) fn with_ending(ending: LineEnding) -> Self {
+        Self {
+            ending,
+            ..Default::default()
+        }
+    }
+}
+
+/// A line split into its parts. Concatenating them reproduces the line.
+#[derive(Debug)]
+pub(crate) struct LineParts<'a> {
+    pub(crate) indent: &'a str,
+    pub(crate) head: &'a str,
+    pub(crate) gap: &'a str,
+    pub(crate) tail: &'a str,
+    pub(crate) trailing: &'a str,
+    /// Everything after `//`, verbatim.
+    pub(crate) comment: Option<&'a str>,
+}
+
+impl<'a> LineParts<'a> {
+    /// Splits `line` into a leading word, the rest of its content, and a `//` comment.
+    pub(crate) fn split(line: &'a str, with_comment: bool) -> Self {
+        let (content, comment) = match line.find("//").filter(|_| with_comment) {
+            Some(idx) => (&line[..idx], Some(&line[idx + 2..])),
+            None => (line, None),
+        };
+        let rest = content.trim_start();
+        let indent = &content[..content.len() - rest.len()];
+        let body = rest.trim_end();
+        let trailing = &rest[body.len()..];
+        let head_len = body.find(char::is_whitespace).unwrap_or(body.len());
+        let head = &body[..head_len];
+        let tail = body[head_len..].trim_start();
+        let gap = &body[head_len..body.len() - tail.len()];
+
+        Self {
+            indent,
+            head,
+            gap,
+            tail,
+            trailing,
+            comment,
+        }
+    }
+}
+
+/// Splits text into lines and their endings.
+pub(crate) fn split_lines(mut text: &str) -> Vec<(&str, LineEnding)> {
+    let mut lines = Vec::new();
+    while !text.is_empty() {
+        match text.find('\n') {
+            Some(idx) => {
+                let line = &text[..idx];
+                lines.push(match line.strip_suffix('\r') {
+                    Some(line) => (line, LineEnding::CrLf),
+                    None => (line, LineEnding::Lf),
+                });
+                text = &text[idx + 1..];
+            }
+            None => {
+                lines.push((text, LineEnding::None));
+                text = "";
+            }
+        }
+    }
+    lines
+}
+
+/// What Windows-1252 maps 0x80..=0x9F to. The five bytes it leaves undefined map to the C1
+/// control with the same value, as in the WHATWG decoder, so any file still round-trips.
+const CP1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+/// Level files are Windows-1252 text, as the Python level editor reads and writes them.
+pub(crate) fn decode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            0x80..=0x9F => CP1252_HIGH[usize::from(b - 0x80)],
+            _ => char::from(b),
+        })
+        .collect()
+}
+
+pub(crate) fn encode(text: &str) -> Result<Vec<u8>, LevelError> {
+    text.chars()
+        .map(|c| {
+            if let Some(idx) = CP1252_HIGH.iter().position(|&high| high == c) {
+                return Ok(0x80 + idx as u8);
+            }
+            match u8::try_from(c) {
+                Ok(b) if !(0x80..=0x9F).contains(&b) => Ok(b),
+                _ => Err(LevelError::UnencodableChar(c)),
+            }
+        })
+        .collect()
+}
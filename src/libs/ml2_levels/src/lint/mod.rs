@@ -0,0 +1,551 @@
+//! Checks a level file for mistakes the game would silently ignore or choke on.
+
+mod names;
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use ml2_entity_data::ENTITIES;
+
+use crate::level_file::{DirectiveKind, LevelFile};
+use crate::template::{Chunk, TemplateSetting};
+
+use names::{
+    VALID_LEVEL_CHANCES, VALID_LEVEL_SETTINGS, VALID_LEVEL_TEMPLATES, VALID_MONSTER_CHANCES,
+    VALID_TILE_CODES,
+};
+
+const ENTITY_PREFIX: &str = "ENT_TYPE_";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Probably a mistake, but the game or a mod loader may accept it.
+    Warning,
+    /// The game will misbehave or refuse to load the level.
+    Error,
+}
+
+/// A problem found by [`lint`]. Rooms are numbered from 0, in the order they appear in their
+/// template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Issue {
+    UnknownTemplate {
+        template: String,
+    },
+    /// Rows of the room have different widths.
+    RaggedRoom {
+        template: String,
+        room: usize,
+    },
+    /// The back layer is missing from some rows, or doesn't match the front layer's size.
+    MismatchedLayers {
+        template: String,
+        room: usize,
+    },
+    /// The room is `\!dual` but has no back layer.
+    MissingBackLayer {
+        template: String,
+        room: usize,
+    },
+    UnexpectedRoomSize {
+        template: String,
+        room: usize,
+        width: usize,
+        height: usize,
+        expected: (usize, usize),
+    },
+    UndefinedTileCode {
+        template: String,
+        room: usize,
+        code: char,
+    },
+    /// A tile code's value isn't a single character.
+    InvalidTileCodeValue {
+        name: String,
+        value: String,
+    },
+    DuplicateTileCodeValue {
+        code: char,
+        names: (String, String),
+    },
+    /// A part of a tile code's name isn't a known tile code.
+    UnknownTileCodeName {
+        name: String,
+        part: String,
+    },
+    /// A part of a tile code's name is an `ENT_TYPE_` that doesn't exist.
+    UnknownEntity {
+        name: String,
+        entity: String,
+    },
+    /// A level setting, level chance or monster chance the game doesn't know about.
+    UnknownName {
+        kind: DirectiveKind,
+        name: String,
+    },
+    InvalidSetting {
+        name: String,
+        value: String,
+    },
+    /// A chance that isn't 1 or 4 non-negative integers.
+    InvalidChance {
+        kind: DirectiveKind,
+        name: String,
+        value: String,
+    },
+}
+
+impl Issue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::UnknownTemplate { .. }
+            | Issue::UnexpectedRoomSize { .. }
+            | Issue::UnknownTileCodeName { .. }
+            | Issue::UnknownName { .. } => Severity::Warning,
+            Issue::RaggedRoom { .. }
+            | Issue::MismatchedLayers { .. }
+            | Issue::MissingBackLayer { .. }
+            | Issue::UndefinedTileCode { .. }
+            | Issue::InvalidTileCodeValue { .. }
+            | Issue::DuplicateTileCodeValue { .. }
+            | Issue::UnknownEntity { .. }
+            | Issue::InvalidSetting { .. }
+            | Issue::InvalidChance { .. } => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::UnknownTemplate { template } => write!(f, "Unknown template {template:?}"),
+            Issue::RaggedRoom { template, room } => {
+                write!(f, "Room {room} of {template:?} has rows of different widths")
+            }
+            Issue::MismatchedLayers { template, room } => write!(
+                f,
+                "Room {room} of {template:?} has a back layer that doesn't match its front layer"
+            ),
+            Issue::MissingBackLayer { template, room } => {
+                write!(f, "Room {room} of {template:?} is dual but has no back layer")
+            }
+            Issue::UnexpectedRoomSize {
+                template,
+                room,
+                width,
+                height,
+                expected: (expected_width, expected_height),
+            } => write!(
+                f,
+                "Room {room} of {template:?} is {width}x{height}, expected {expected_width}x{expected_height}"
+            ),
+            Issue::UndefinedTileCode {
+                template,
+                room,
+                code,
+            } => write!(f, "Room {room} of {template:?} uses undefined tile code {code:?}"),
+            Issue::InvalidTileCodeValue { name, value } => write!(
+                f,
+                "Tile code {name:?} has value {value:?}, which isn't a single character"
+            ),
+            Issue::DuplicateTileCodeValue {
+                code,
+                names: (first, second),
+            } => write!(f, "Tile codes {first:?} and {second:?} both use {code:?}"),
+            Issue::UnknownTileCodeName { name, part } => {
+                write!(f, "Tile code {name:?} uses unknown tile code {part:?}")
+            }
+            Issue::UnknownEntity { name, entity } => {
+                write!(f, "Tile code {name:?} uses unknown entity {entity:?}")
+            }
+            Issue::UnknownName { kind, name } => {
+                write!(f, "Unknown directive {}{name}", kind.prefix())
+            }
+            Issue::InvalidSetting { name, value } => {
+                write!(f, "Level setting {name:?} has an invalid value: {value:?}")
+            }
+            Issue::InvalidChance { kind, name, value } => write!(
+                f,
+                "{}{name} has an invalid value {value:?}, expected 1 or 4 non-negative integers",
+                kind.prefix()
+            ),
+        }
+    }
+}
+
+/// Checks `level` for problems, with tile codes from `base` files (e.g. `generic.lvl`)
+/// counting as defined.
+pub fn lint(level: &LevelFile, base: &[&LevelFile]) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    lint_tile_codes(level, &mut issues);
+    lint_settings(level, &mut issues);
+    lint_chances(level, &mut issues);
+    lint_templates(level, base, &mut issues);
+    issues
+}
+
+fn lint_tile_codes(level: &LevelFile, issues: &mut Vec<Issue>) {
+    let mut seen: HashMap<char, &str> = HashMap::new();
+    for tile_code in level.tile_codes() {
+        let name = &tile_code.name;
+        let mut chars = tile_code.value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(code), None) => {
+                if let Some(first) = seen.insert(code, name) {
+                    issues.push(Issue::DuplicateTileCodeValue {
+                        code,
+                        names: (first.into(), name.clone()),
+                    });
+                }
+            }
+            _ => issues.push(Issue::InvalidTileCodeValue {
+                name: name.clone(),
+                value: tile_code.value.clone(),
+            }),
+        }
+
+        for part in split_percent(name) {
+            if part.to_ascii_uppercase().starts_with(ENTITY_PREFIX) {
+                if !ENTITIES.contains_key(&part.to_ascii_uppercase()) {
+                    issues.push(Issue::UnknownEntity {
+                        name: name.clone(),
+                        entity: part.into(),
+                    });
+                }
+            } else if !VALID_TILE_CODES.contains(&part) {
+                issues.push(Issue::UnknownTileCodeName {
+                    name: name.clone(),
+                    part: part.into(),
+                });
+            }
+        }
+    }
+}
+
+/// Splits a tile code name like `floor%50%spikes` on its percentages, dropping empty parts.
+fn split_percent(name: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut rest = name;
+    while let Some(idx) = rest.find('%') {
+        let after = &rest[idx + 1..];
+        let digits = after.bytes().take(2).take_while(u8::is_ascii_digit).count();
+        let offset = name.len() - rest.len();
+        if digits == 0 {
+            rest = after;
+            continue;
+        }
+        let mut end = idx + 1 + digits;
+        if rest[end..].starts_with('%') {
+            end += 1;
+        }
+        parts.push(&name[start..offset + idx]);
+        start = offset + end;
+        rest = &rest[end..];
+    }
+    parts.push(&name[start..]);
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn lint_settings(level: &LevelFile, issues: &mut Vec<Issue>) {
+    for setting in level.level_settings() {
+        let name = setting.name.as_str();
+        if !VALID_LEVEL_SETTINGS.contains(&name) {
+            issues.push(Issue::UnknownName {
+                kind: setting.kind,
+                name: name.into(),
+            });
+            continue;
+        }
+
+        let valid = match name {
+            "size" => {
+                let values: Vec<_> = setting.value.split_whitespace().collect();
+                values.len() == 2 && values.iter().all(|v| v.parse::<u32>().is_ok())
+            }
+            "liquid_gravity" => setting.value.trim().parse::<f32>().is_ok(),
+            _ => setting.value.trim().parse::<i64>().is_ok(),
+        };
+        if !valid {
+            issues.push(Issue::InvalidSetting {
+                name: name.into(),
+                value: setting.value.clone(),
+            });
+        }
+    }
+}
+
+fn lint_chances(level: &LevelFile, issues: &mut Vec<Issue>) {
+    let chances = level
+        .level_chances()
+        .map(|chance| (chance, VALID_LEVEL_CHANCES))
+        .chain(
+            level
+                .monster_chances()
+                .map(|chance| (chance, VALID_MONSTER_CHANCES)),
+        );
+    for (chance, valid_names) in chances {
+        if !valid_names.contains(&chance.name.as_str()) {
+            issues.push(Issue::UnknownName {
+                kind: chance.kind,
+                name: chance.name.clone(),
+            });
+        }
+
+        let valid = chance.chance_values().is_ok_and(|values| {
+            matches!(values.len(), 1 | 4) && values.iter().all(|value| *value >= 0)
+        });
+        if !valid {
+            issues.push(Issue::InvalidChance {
+                kind: chance.kind,
+                name: chance.name.clone(),
+                value: chance.value.clone(),
+            });
+        }
+    }
+}
+
+fn lint_templates(level: &LevelFile, base: &[&LevelFile], issues: &mut Vec<Issue>) {
+    let defined: BTreeSet<char> = std::iter::once(level)
+        .chain(base.iter().copied())
+        .flat_map(|file| file.tile_codes())
+        .filter_map(|tile_code| tile_code.value.chars().next())
+        .collect();
+
+    for template in level.templates() {
+        let name = &template.name;
+        let known = VALID_LEVEL_TEMPLATES.contains(&name.as_str()) || is_setroom(name);
+        if !known {
+            issues.push(Issue::UnknownTemplate {
+                template: name.clone(),
+            });
+        }
+        let expected = known.then(|| expected_size(name)).flatten();
+
+        for (room, chunk) in template.rooms().enumerate() {
+            lint_room_shape(name, room, chunk, expected, issues);
+
+            let mut used = BTreeSet::new();
+            let foreground = chunk.foreground();
+            let background = chunk.background().unwrap_or_default();
+            for row in foreground.iter().chain(background.iter()) {
+                used.extend(row.chars());
+            }
+            for code in used.difference(&defined) {
+                issues.push(Issue::UndefinedTileCode {
+                    template: name.clone(),
+                    room,
+                    code: *code,
+                });
+            }
+        }
+    }
+}
+
+fn lint_room_shape(
+    template: &str,
+    room: usize,
+    chunk: &Chunk,
+    expected: Option<(usize, usize)>,
+    issues: &mut Vec<Issue>,
+) {
+    let template = template.to_string();
+    let widths = |rows: &[&str]| {
+        rows.iter()
+            .map(|row| row.chars().count())
+            .collect::<Vec<_>>()
+    };
+    let foreground = widths(&chunk.foreground());
+    if foreground.windows(2).any(|pair| pair[0] != pair[1]) {
+        issues.push(Issue::RaggedRoom { template, room });
+        return;
+    }
+
+    match chunk.background() {
+        Some(background) if widths(&background) != foreground => {
+            issues.push(Issue::MismatchedLayers {
+                template: template.clone(),
+                room,
+            });
+        }
+        None if chunk.has_setting(&TemplateSetting::Dual) => {
+            issues.push(Issue::MissingBackLayer {
+                template: template.clone(),
+                room,
+            });
+        }
+        _ => {}
+    }
+
+    let (width, height) = (foreground[0], foreground.len());
+    if let Some(expected) = expected.filter(|expected| *expected != (width, height)) {
+        issues.push(Issue::UnexpectedRoomSize {
+            template,
+            room,
+            width,
+            height,
+            expected,
+        });
+    }
+}
+
+/// Matches the game's `setroom<y>-<x>` templates, whose size depends on the level.
+fn is_setroom(name: &str) -> bool {
+    let Some(rest) = name.strip_prefix("setroom") else {
+        return false;
+    };
+    let Some((y, x)) = rest.split_once('-') else {
+        return false;
+    };
+    let is_index = |s: &str| matches!(s.len(), 1 | 2) && s.bytes().all(|b| b.is_ascii_digit());
+    let x_digits = x.bytes().take_while(u8::is_ascii_digit).count();
+    is_index(y) && is_index(&x[..x_digits])
+}
+
+/// The width and height of rooms in a known template, if it has a fixed size.
+///
+/// These follow `ROOM_TYPES` in the Python level editor, which matches on prefixes.
+fn expected_size(name: &str) -> Option<(usize, usize)> {
+    if is_setroom(name) {
+        return None;
+    }
+    const SIZES: &[(&str, (usize, usize))] = &[
+        ("machine_wideroom", (20, 8)),
+        ("machine_tallroom", (10, 16)),
+        ("machine_bigroom", (20, 16)),
+        ("coffin_frog", (10, 16)),
+        ("ghistroom", (5, 5)),
+        ("feeling", (20, 16)),
+        ("chunk_ground", (5, 3)),
+        ("chunk_door", (6, 3)),
+        ("chunk_air", (5, 3)),
+        ("cache", (5, 5)),
+    ];
+    Some(
+        SIZES
+            .iter()
+            .find(|(prefix, _)| name.starts_with(prefix))
+            .map_or((10, 8), |(_, size)| *size),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint, split_percent, Issue, Severity};
+    use crate::level_file::{Directive, DirectiveKind, Item, LevelFile};
+
+    const LEVEL: &str = concat!(
+        "\\?floor 1\r\n",
+        "\\?empty 0\r\n",
+        "\\.chunk_ground\r\n",
+        "\r\n",
+        "11111\r\n",
+        "00000\r\n",
+        "11111\r\n",
+    );
+
+    fn parse(text: &str) -> LevelFile {
+        LevelFile::from_bytes(text.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_clean() {
+        assert_eq!(lint(&parse(LEVEL), &[]), vec![]);
+
+        let sunken_city =
+            LevelFile::from_bytes(include_bytes!("../../tests/data/sunkencity.lvl")).unwrap();
+        assert_eq!(lint(&sunken_city, &[]), vec![]);
+    }
+
+    #[test]
+    fn test_room_sizes() {
+        let mut text = String::from("\\?empty 0\r\n");
+        for (template, width, height) in [
+            ("cache", 5, 5),
+            ("ghistroom", 5, 5),
+            ("feeling_factory", 20, 16),
+            ("feeling_prison", 20, 16),
+            ("feeling_tomb", 20, 16),
+            ("coffin_frog", 10, 16),
+            ("machine_bigroom_path", 20, 16),
+            ("chunk_door", 6, 3),
+            ("entrance", 10, 8),
+        ] {
+            text += &format!("\\.{template}\r\n\r\n");
+            for _ in 0..height {
+                text += &"0".repeat(width);
+                text += "\r\n";
+            }
+        }
+        let issues = lint(&parse(&text), &[]);
+        assert!(issues
+            .iter()
+            .all(|issue| !matches!(issue, Issue::UnexpectedRoomSize { .. })));
+    }
+
+    #[test]
+    fn test_split_percent() {
+        assert_eq!(split_percent("floor%50"), vec!["floor"]);
+        assert_eq!(split_percent("floor%50%spikes"), vec!["floor", "spikes"]);
+        assert_eq!(split_percent("a%b%5%c"), vec!["a%b", "c"]);
+    }
+
+    #[test]
+    fn test_issues() {
+        let text = LEVEL.replace("00000", "0000X").replace(
+            "\\?empty 0",
+            "\\?empty 0\r\n\\?ENT_TYPE_ITEM_ROCKK%50%floor 2",
+        ) + "\r\n11\r\n11\r\n";
+        let mut level = parse(&text);
+        level.items.push(Item::Directive(Directive::new(
+            DirectiveKind::LevelChance,
+            "arrowtrap_chance",
+            "1, 2, 3",
+        )));
+        level.items.push(Item::Directive(Directive::new(
+            DirectiveKind::MonsterChance,
+            "snake",
+            "-5",
+        )));
+
+        let issues = lint(&level, &[]);
+        let x_undefined = Issue::UndefinedTileCode {
+            template: "chunk_ground".into(),
+            room: 0,
+            code: 'X',
+        };
+        assert!(issues.contains(&x_undefined));
+        assert!(issues.contains(&Issue::UnknownEntity {
+            name: "ENT_TYPE_ITEM_ROCKK%50%floor".into(),
+            entity: "ENT_TYPE_ITEM_ROCKK".into(),
+        }));
+        assert!(issues.contains(&Issue::UnexpectedRoomSize {
+            template: "chunk_ground".into(),
+            room: 1,
+            width: 2,
+            height: 2,
+            expected: (5, 3),
+        }));
+        let invalid_chances = issues
+            .iter()
+            .filter(|issue| matches!(issue, Issue::InvalidChance { .. }))
+            .count();
+        assert_eq!(invalid_chances, 2);
+        assert!(issues
+            .iter()
+            .all(|issue| !matches!(issue, Issue::UnknownName { .. })));
+
+        // X is defined by the base file
+        let base = parse("\\?door X\r\n");
+        let issues = lint(&level, &[&base]);
+        assert!(!issues.contains(&x_undefined));
+        assert_eq!(
+            issues
+                .iter()
+                .filter(|issue| issue.severity() == Severity::Warning)
+                .count(),
+            1
+        );
+    }
+}
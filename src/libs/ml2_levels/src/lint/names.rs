@@ -0,0 +1,676 @@
+//! Names the game accepts, copied from the Python level editor. A test keeps them in sync.
+
+pub(crate) const VALID_TILE_CODES: &[&str] = &[
+    "adjacent_floor",
+    "alien",
+    "alien_generator",
+    "alienqueen",
+    "altar",
+    "altar_duat",
+    "ammit",
+    "ankh",
+    "anubis",
+    "anubis2",
+    "apep",
+    "apep_left",
+    "apep_right",
+    "arrow_metal",
+    "arrow_metal_poison",
+    "arrow_trap",
+    "arrow_wooden",
+    "arrow_wooden_poison",
+    "assassin",
+    "autowalltorch",
+    "babylon_floor",
+    "back_hoverpack",
+    "back_jetpack",
+    "back_powerpack",
+    "back_telepack",
+    "bat",
+    "bat_flying",
+    "bee",
+    "bee_queen",
+    "beehive_floor",
+    "bigspear_trap",
+    "bodyguard",
+    "bomb",
+    "bomb_bag",
+    "bomb_box",
+    "bone_block",
+    "bone_key",
+    "boombox",
+    "boomerang",
+    "boulder",
+    "bubble_platform",
+    "bunkbed",
+    "bush_block",
+    "camera",
+    "cape",
+    "catmummy",
+    "caveman",
+    "caveman_asleep",
+    "cavemanboss",
+    "cavemanshopkeeper",
+    "chain_ceiling",
+    "chainandblocks_ceiling",
+    "chair_looking_left",
+    "chair_looking_right",
+    "challenge_waitroom",
+    "chunk_air",
+    "chunk_door",
+    "chunk_ground",
+    "climbing_gloves",
+    "climbing_pole",
+    "clonegun",
+    "clover",
+    "coarse_lava",
+    "coarse_water",
+    "cobra",
+    "cobweb",
+    "coffin",
+    "cog_door",
+    "cog_floor",
+    "compass",
+    "compass_alien",
+    "construction_sign",
+    "conveyorbelt_left",
+    "conveyorbelt_right",
+    "cooked_turkey",
+    "cookfire",
+    "cosmic_jelly",
+    "cosmic_orb",
+    "couch",
+    "crabman",
+    "crate",
+    "crate_bombs",
+    "crate_parachute",
+    "crate_ropes",
+    "critter_butterfly",
+    "critter_crab",
+    "critter_drone",
+    "critter_dungbeetle",
+    "critter_firefly",
+    "critter_fish",
+    "critter_locust",
+    "critter_penguin",
+    "critter_slime",
+    "critter_snail",
+    "crocman",
+    "crossbow",
+    "crown",
+    "crown_statue",
+    "crushing_elevator",
+    "crushtrap",
+    "crushtraplarge",
+    "cursed_pot",
+    "diamond",
+    "die",
+    "diningtable",
+    "dm_spawn_point",
+    "dog_sign",
+    "door",
+    "door2",
+    "door2_secret",
+    "door_drop_held",
+    "dresser",
+    "drill",
+    "duat_floor",
+    "dustwall",
+    "eggplant",
+    "eggplant_altar",
+    "eggplant_child",
+    "eggplant_crown",
+    "eggplant_door",
+    "eggsac",
+    "eggsac_bottom",
+    "eggsac_left",
+    "eggsac_right",
+    "eggsac_top",
+    "elevator",
+    "elixir",
+    "emerald",
+    "empress_grave",
+    "empty",
+    "empty_mech",
+    "entrance",
+    "entrance_shortcut",
+    "excalibur",
+    "excalibur_broken",
+    "excalibur_stone",
+    "excalibur_stone_empty",
+    "exit",
+    "factory_generator",
+    "falling_platform",
+    "firebug",
+    "floor",
+    "floor_hard",
+    "flying_fish",
+    "forcefield",
+    "forcefield_horizontal",
+    "forcefield_horizontal_top",
+    "forcefield_top",
+    "fountain_drain",
+    "fountain_head",
+    "frog",
+    "frog_orange",
+    "ghist_door2",
+    "ghist_present",
+    "ghist_shopkeeper",
+    "ghost",
+    "ghost_med_happy",
+    "ghost_med_sad",
+    "ghost_small_angry",
+    "ghost_small_happy",
+    "ghost_small_sad",
+    "ghost_small_surprised",
+    "giant_fly",
+    "giant_frog",
+    "giant_spider",
+    "giantclam",
+    "giantfood",
+    "gold_bar",
+    "goldbars",
+    "growable_climbing_pole",
+    "growable_vine",
+    "grub",
+    "gun_freezeray",
+    "gun_shotgun",
+    "gun_webgun",
+    "guts_floor",
+    "haunted_corpse",
+    "hedjet",
+    "hermitcrab",
+    "honey_downwards",
+    "honey_upwards",
+    "houyibow",
+    "humphead",
+    "hundun",
+    "icefloor",
+    "idol",
+    "idol_floor",
+    "idol_hold",
+    "imp",
+    "jiangshi",
+    "jumpdog",
+    "jungle_floor",
+    "jungle_spear_trap",
+    "kapala",
+    "key",
+    "kingu",
+    "ladder",
+    "ladder_plat",
+    "lamassu",
+    "lamp_hang",
+    "landmine",
+    "laser_trap",
+    "lava",
+    "lava_pot",
+    "lavamander",
+    "leaf",
+    "leprechaun",
+    "lightarrow",
+    "lion_trap",
+    "littorch",
+    "litwalltorch",
+    "lizard",
+    "locked_door",
+    "lockedchest",
+    "machete",
+    "madametusk",
+    "mantrap",
+    "mattock",
+    "merchant",
+    "minewood_floor",
+    "minewood_floor_hanging_hide",
+    "minewood_floor_noreplace",
+    "minister",
+    "moai_statue",
+    "mole",
+    "monkey",
+    "monkey_gold",
+    "mosquito",
+    "mother_statue",
+    "mothership_floor",
+    "mount_axolotl",
+    "mount_qilin",
+    "mount_rockdog",
+    "movable_spikes",
+    "mummy",
+    "mushroom_base",
+    "necromancer",
+    "nonreplaceable_babylon_floor",
+    "nonreplaceable_floor",
+    "octopus",
+    "oldhunter",
+    "olmec",
+    "olmecship",
+    "olmite",
+    "olmite_armored",
+    "olmite_helmet",
+    "olmite_naked",
+    "osiris",
+    "pagoda_floor",
+    "pagoda_platform",
+    "palace_bookcase",
+    "palace_candle",
+    "palace_chandelier",
+    "palace_entrance",
+    "palace_floor",
+    "palace_sign",
+    "palace_table",
+    "palace_table_tray",
+    "parachute",
+    "paste",
+    "pen_floor",
+    "pen_locked_door",
+    "pet_monty",
+    "pet_percy",
+    "pet_poochi",
+    "pillar",
+    "pipe",
+    "pitchers_mitt",
+    "plasma_cannon",
+    "platform",
+    "playerbag",
+    "pot",
+    "potofgold",
+    "powder_keg",
+    "present",
+    "proto_shopkeeper",
+    "punishball",
+    "punishball_attach",
+    "punishball_attach_bottom",
+    "punishball_attach_left",
+    "punishball_attach_right",
+    "punishball_attach_top",
+    "push_block",
+    "quicksand",
+    "redskeleton",
+    "regenerating_block",
+    "robot",
+    "rock",
+    "rope",
+    "rope_pile",
+    "rope_unrolled",
+    "royal_jelly",
+    "ruby",
+    "sapphire",
+    "scarab",
+    "scepter",
+    "scorpion",
+    "seeded_run_unlocker",
+    "shield_metal",
+    "shield_wooden",
+    "shoes_spike",
+    "shoes_spring",
+    "shop_door",
+    "shop_item",
+    "shop_pagodawall",
+    "shop_sign",
+    "shop_wall",
+    "shop_woodwall",
+    "shopkeeper",
+    "shopkeeper_clone",
+    "shopkeeper_vat",
+    "shortcut_station_banner",
+    "sidetable",
+    "singlebed",
+    "sister",
+    "skeleton",
+    "skull",
+    "skull_drop_trap",
+    "sleeping_hiredhand",
+    "slidingwall",
+    "slidingwall_ceiling",
+    "slidingwall_switch",
+    "snake",
+    "snap_trap",
+    "sorceress",
+    "spark_trap",
+    "specs",
+    "spider",
+    "spider_falling",
+    "spider_hanging",
+    "spikeball",
+    "spikeball_no_bounce",
+    "spikeball_trap",
+    "spikes",
+    "spring_trap",
+    "starting_exit",
+    "sticky_trap",
+    "stone_floor",
+    "storage_floor",
+    "storage_guy",
+    "styled_floor",
+    "sunken_floor",
+    "surface_floor",
+    "surface_hidden_floor",
+    "tablet",
+    "tadpole",
+    "teleporter",
+    "telescope",
+    "temple_floor",
+    "thief",
+    "thinice",
+    "thorn_vine",
+    "tiamat",
+    "tikiman",
+    "timed_forcefield",
+    "timed_powder_keg",
+    "tomb_floor",
+    "totem_trap",
+    "treasure",
+    "treasure_chest",
+    "treasure_vaultchest",
+    "tree_base",
+    "true_crown",
+    "turkey",
+    "tutorial_menu_sign",
+    "tutorial_speedrun_sign",
+    "tv",
+    "udjat_eye",
+    "udjat_key",
+    "udjat_socket",
+    "udjat_target",
+    "ufo",
+    "upsidedown_spikes",
+    "ushabti",
+    "vampire",
+    "vampire_flying",
+    "vault_wall",
+    "venom",
+    "vine",
+    "vlad",
+    "vlad_floor",
+    "vlad_flying",
+    "vlads_cape",
+    "walltorch",
+    "wanted_poster",
+    "water",
+    "witchdoctor",
+    "woodenlog_trap",
+    "woodenlog_trap_ceiling",
+    "yama",
+    "yang",
+    "yeti",
+    "yeti_king",
+    "yeti_queen",
+    "zoo_exhibit",
+];
+
+pub(crate) const VALID_LEVEL_SETTINGS: &[&str] = &[
+    "altar_room_chance",
+    "back_room_chance",
+    "back_room_hidden_door_cache_chance",
+    "back_room_hidden_door_chance",
+    "back_room_interconnection_chance",
+    "background_chance",
+    "flagged_liquid_rooms",
+    "floor_bottom_spread_chance",
+    "floor_side_spread_chance",
+    "ground_background_chance",
+    "idol_room_chance",
+    "liquid_gravity",
+    "machine_bigroom_chance",
+    "machine_rewardroom_chance",
+    "machine_tallroom_chance",
+    "machine_wideroom_chance",
+    "max_liquid_particles",
+    "mount_chance",
+    "size",
+];
+
+pub(crate) const VALID_LEVEL_CHANCES: &[&str] = &[
+    "arrowtrap_chance",
+    "beehive_chance",
+    "bigspeartrap_chance",
+    "chain_blocks_chance",
+    "crusher_trap_chance",
+    "eggsac_chance",
+    "jungle_spear_trap_chance",
+    "lasertrap_chance",
+    "leprechaun_chance",
+    "liontrap_chance",
+    "minister_chance",
+    "pushblock_chance",
+    "skulldrop_chance",
+    "snap_trap_chance",
+    "sparktrap_chance",
+    "spike_ball_chance",
+    "stickytrap_chance",
+    "totemtrap_chance",
+];
+
+pub(crate) const VALID_MONSTER_CHANCES: &[&str] = &[
+    "bat",
+    "bee",
+    "cat",
+    "caveman",
+    "cobra",
+    "crabman",
+    "critteranchovy",
+    "critterbutterfly",
+    "crittercrab",
+    "critterdrone",
+    "critterdungbeetle",
+    "critterfirefly",
+    "critterfish",
+    "critterlocust",
+    "critterpenguin",
+    "critterslime",
+    "crittersnail",
+    "crocman",
+    "female_jiangshi",
+    "firebug",
+    "firefrog",
+    "fish",
+    "frog",
+    "giantfly",
+    "giantspider",
+    "hangspider",
+    "hermitcrab",
+    "hornedlizard",
+    "imp",
+    "jiangshi",
+    "landmine",
+    "lavamander",
+    "leprechaun",
+    "mantrap",
+    "mole",
+    "monkey",
+    "mosquito",
+    "necromancer",
+    "octopus",
+    "olmite",
+    "robot",
+    "snake",
+    "sorceress",
+    "spider",
+    "springtrap",
+    "tadpole",
+    "tikiman",
+    "ufo",
+    "vampire",
+    "witchdoctor",
+    "yeti",
+];
+
+pub(crate) const VALID_LEVEL_TEMPLATES: &[&str] = &[
+    "abzu_backdoor",
+    "altar",
+    "anubis_room",
+    "apep",
+    "beehive",
+    "beehive_entrance",
+    "blackmarket_coffin",
+    "blackmarket_entrance",
+    "blackmarket_exit",
+    "cache",
+    "cavemanshop",
+    "challenge_0-0",
+    "challenge_0-1",
+    "challenge_0-2",
+    "challenge_0-3",
+    "challenge_1-0",
+    "challenge_1-1",
+    "challenge_1-2",
+    "challenge_1-3",
+    "challenge_bottom",
+    "challenge_entrance",
+    "challenge_special",
+    "chunk_air",
+    "chunk_door",
+    "chunk_ground",
+    "coffin_player",
+    "coffin_player_vertical",
+    "coffin_unlockable",
+    "cog_altar_top",
+    "crashedship_entrance",
+    "crashedship_entrance_notop",
+    "curioshop",
+    "diceshop",
+    "empress_grave",
+    "entrance",
+    "entrance_drop",
+    "exit",
+    "exit_notop",
+    "feeling_factory",
+    "feeling_prison",
+    "feeling_tomb",
+    "ghistroom",
+    "ghistshop",
+    "idol",
+    "idol_top",
+    "lake_exit",
+    "lake_normal",
+    "lake_notop",
+    "lakeoffire_back_entrance",
+    "lakeoffire_back_exit",
+    "machine_bigroom_path",
+    "machine_bigroom_side",
+    "machine_keyroom",
+    "machine_rewardroom",
+    "machine_tallroom_path",
+    "machine_tallroom_side",
+    "machine_wideroom_path",
+    "machine_wideroom_side",
+    "moai",
+    "mothership_coffin",
+    "mothership_entrance",
+    "mothership_exit",
+    "motherstatue_room",
+    "oldhunter_cursedroom",
+    "oldhunter_keyroom",
+    "oldhunter_rewardroom",
+    "olmecship_room",
+    "palaceofpleasure_0-0",
+    "palaceofpleasure_0-1",
+    "palaceofpleasure_0-2",
+    "palaceofpleasure_1-0",
+    "palaceofpleasure_1-1",
+    "palaceofpleasure_1-2",
+    "palaceofpleasure_2-0",
+    "palaceofpleasure_2-1",
+    "palaceofpleasure_2-2",
+    "palaceofpleasure_3-0",
+    "palaceofpleasure_3-1",
+    "palaceofpleasure_3-2",
+    "passage_horz",
+    "passage_turn",
+    "passage_vert",
+    "path_drop",
+    "path_drop_notop",
+    "path_normal",
+    "path_notop",
+    "pen_room",
+    "posse",
+    "quest_thief1",
+    "quest_thief2",
+    "room2",
+    "shop",
+    "shop_attic",
+    "shop_basement",
+    "shop_entrance_down",
+    "shop_entrance_up",
+    "side",
+    "sisters_room",
+    "storage_room",
+    "tuskdiceshop",
+    "tuskfrontdiceshop",
+    "udjatentrance",
+    "udjattop",
+    "ushabti_entrance",
+    "ushabti_room",
+    "vault",
+    "vlad_bottom_exit",
+    "vlad_bottom_tunnel",
+    "vlad_drill",
+    "vlad_entrance",
+    "vlad_tunnel",
+];
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{
+        VALID_LEVEL_CHANCES, VALID_LEVEL_SETTINGS, VALID_LEVEL_TEMPLATES, VALID_MONSTER_CHANCES,
+        VALID_TILE_CODES,
+    };
+
+    /// The quoted strings in `name = set([...])`.
+    fn python_set<'a>(source: &'a str, name: &str) -> BTreeSet<&'a str> {
+        let start = source
+            .find(&format!("{name} = set("))
+            .unwrap_or_else(|| panic!("{name} not found"));
+        let list = &source[start..];
+        let list = &list[list.find('[').unwrap()..list.find(']').unwrap()];
+        list.lines()
+            .map(|line| line.split('#').next().unwrap())
+            .flat_map(|line| line.split('"').skip(1).step_by(2))
+            .collect()
+    }
+
+    fn check(source: &str, name: &str, names: &[&str]) {
+        let expected = python_set(source, name);
+        let found: BTreeSet<&str> = names.iter().copied().collect();
+        assert_eq!(found.len(), names.len(), "{name} has duplicates");
+        assert_eq!(
+            found.difference(&expected).collect::<Vec<_>>(),
+            Vec::<&&str>::new(),
+            "{name} has names the Python editor doesn't"
+        );
+        assert_eq!(
+            expected.difference(&found).collect::<Vec<_>>(),
+            Vec::<&&str>::new(),
+            "{name} is missing names from the Python editor"
+        );
+    }
+
+    #[test]
+    fn test_matches_python() {
+        let levels = concat!(env!("CARGO_MANIFEST_DIR"), "/../../modlunky2/levels");
+        let read = |file: &str| std::fs::read_to_string(format!("{levels}/{file}")).unwrap();
+        check(&read("tile_codes.py"), "VALID_TILE_CODES", VALID_TILE_CODES);
+        check(
+            &read("level_settings.py"),
+            "VALID_LEVEL_SETTINGS",
+            VALID_LEVEL_SETTINGS,
+        );
+        check(
+            &read("level_chances.py"),
+            "VALID_LEVEL_CHANCES",
+            VALID_LEVEL_CHANCES,
+        );
+        check(
+            &read("monster_chances.py"),
+            "VALID_MONSTER_CHANCES",
+            VALID_MONSTER_CHANCES,
+        );
+        check(
+            &read("level_templates.py"),
+            "VALID_LEVEL_TEMPLATES",
+            VALID_LEVEL_TEMPLATES,
+        );
+    }
+}
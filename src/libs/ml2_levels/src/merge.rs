@@ -0,0 +1,443 @@
+//! Merges level files from several packs against the vanilla version they were made from.
+//!
+//! Each room template and directive is merged on its own, so packs that edit different rooms of
+//! the same file can be combined.
+
+use serde::{Deserialize, Serialize};
+
+use crate::level_file::{DirectiveKind, Item, LevelFile};
+use crate::template::{Template, TemplateSetting};
+
+/// Something that's merged as a whole.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MergeTarget {
+    Directive { kind: DirectiveKind, name: String },
+    Template { name: String },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictKind {
+    /// Both packs changed it, differently.
+    Changed,
+    /// Both packs added it, differently.
+    Added,
+    /// One pack changed it, and the other removed it.
+    Removed,
+    /// A pack's tile code uses the same glyph as a different tile code from an earlier pack, or
+    /// a pack's room uses such a tile code.
+    Glyph,
+}
+
+/// A change that couldn't be merged. The earlier pack's version is kept.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conflict {
+    pub target: MergeTarget,
+    pub kind: ConflictKind,
+    /// Index of the pack whose version was kept, or `None` for vanilla's.
+    pub kept: Option<usize>,
+    /// Index of the pack whose change was dropped.
+    pub dropped: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MergedLevel {
+    pub level: LevelFile,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Merges `packs`' versions of a level file, in order, against `vanilla`.
+///
+/// Changes are compared by content, so differences in whitespace or comments don't conflict.
+/// Layout follows the first pack that touched each part of the file.
+pub fn merge(vanilla: &LevelFile, packs: &[&LevelFile]) -> MergedLevel {
+    let mut level = vanilla.clone();
+    let mut owners: Vec<(MergeTarget, usize)> = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for (idx, pack) in packs.iter().enumerate() {
+        let level_before = level.clone();
+        let owners_before = owners.clone();
+        let mut applied = Vec::new();
+        for target in targets(&[vanilla, &level, *pack]) {
+            let base = find(vanilla, &target);
+            let ours = find(&level, &target);
+            let theirs = find(pack, &target);
+            if same(ours, theirs) || same(base, theirs) {
+                continue;
+            }
+
+            let owner = owners.iter_mut().find(|(owned, _)| *owned == target);
+            if same(base, ours) {
+                match owner {
+                    Some((_, owner)) => *owner = idx,
+                    None => owners.push((target.clone(), idx)),
+                }
+                apply(&mut level, &target, theirs.cloned());
+                applied.push(target);
+                continue;
+            }
+
+            let kind = match (base, ours, theirs) {
+                (None, _, _) => ConflictKind::Added,
+                (Some(_), Some(_), Some(_)) => ConflictKind::Changed,
+                _ => ConflictKind::Removed,
+            };
+            conflicts.push(Conflict {
+                target,
+                kind,
+                // Ours only differs from vanilla if an earlier pack changed it
+                kept: owner.map(|(_, owner)| *owner),
+                dropped: idx,
+            });
+        }
+
+        // Checked once the whole pack is in, since it may have moved the other tile code
+        let mut glyphs = Vec::new();
+        for (target, glyph, other) in glyph_clashes(&level, &applied) {
+            glyphs.push(glyph);
+            let mut revert = Revert {
+                level: &mut level,
+                owners: &mut owners,
+                level_before: &level_before,
+                owners_before: &owners_before,
+            };
+            revert.apply(&target);
+            let kept = owners.iter().find(|(owned, _)| *owned == other);
+            conflicts.push(Conflict {
+                target,
+                kind: ConflictKind::Glyph,
+                kept: kept.map(|(_, owner)| *owner),
+                dropped: idx,
+            });
+        }
+
+        // The pack's rooms meant its own tile codes by those glyphs, not the kept ones
+        for target in &applied {
+            let uses_glyph = match find(&level, target) {
+                Some(Item::Template(template)) => uses_any(template, &glyphs),
+                _ => false,
+            };
+            if !uses_glyph {
+                continue;
+            }
+            let mut revert = Revert {
+                level: &mut level,
+                owners: &mut owners,
+                level_before: &level_before,
+                owners_before: &owners_before,
+            };
+            let kept = revert.apply(target);
+            conflicts.push(Conflict {
+                target: target.clone(),
+                kind: ConflictKind::Glyph,
+                kept,
+                dropped: idx,
+            });
+        }
+    }
+
+    MergedLevel { level, conflicts }
+}
+
+/// Puts targets back to how they were before a pack was merged.
+struct Revert<'a> {
+    level: &'a mut LevelFile,
+    owners: &'a mut Vec<(MergeTarget, usize)>,
+    level_before: &'a LevelFile,
+    owners_before: &'a [(MergeTarget, usize)],
+}
+
+impl Revert<'_> {
+    /// Reverts `target`, returning the pack that now owns it, or `None` for vanilla.
+    fn apply(&mut self, target: &MergeTarget) -> Option<usize> {
+        let previous = find(self.level_before, target).cloned();
+        apply(self.level, target, previous);
+        self.owners.retain(|(owned, _)| owned != target);
+        let owner = self
+            .owners_before
+            .iter()
+            .find(|(owned, _)| owned == target)
+            .cloned();
+        let kept = owner.as_ref().map(|(_, owner)| *owner);
+        self.owners.extend(owner);
+        kept
+    }
+}
+
+/// Whether any room of `template` uses one of `glyphs`.
+fn uses_any(template: &Template, glyphs: &[String]) -> bool {
+    template.rooms().any(|chunk| {
+        chunk
+            .foreground()
+            .into_iter()
+            .chain(chunk.background().into_iter().flatten())
+            .any(|line| glyphs.iter().any(|glyph| line.contains(glyph.as_str())))
+    })
+}
+
+/// Tile codes in `applied` whose glyph is also used by a tile code that wasn't, with the glyph
+/// and that tile code.
+fn glyph_clashes(
+    level: &LevelFile,
+    applied: &[MergeTarget],
+) -> Vec<(MergeTarget, String, MergeTarget)> {
+    let tile_codes: Vec<(MergeTarget, &str)> = level
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Directive(directive) if directive.kind == DirectiveKind::TileCode => {
+                Some((target_of(item)?, directive.value.trim()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut clashes = Vec::new();
+    for (target, glyph) in tile_codes.iter().filter(|(t, _)| applied.contains(t)) {
+        let other = tile_codes
+            .iter()
+            .find(|(other, other_glyph)| other_glyph == glyph && !applied.contains(other));
+        if let Some((other, _)) = other {
+            clashes.push((target.clone(), glyph.to_string(), other.clone()));
+        }
+    }
+    clashes
+}
+
+fn target_of(item: &Item) -> Option<MergeTarget> {
+    match item {
+        Item::Trivia(_) => None,
+        Item::Directive(directive) => Some(MergeTarget::Directive {
+            kind: directive.kind,
+            name: directive.name.clone(),
+        }),
+        Item::Template(template) => Some(MergeTarget::Template {
+            name: template.name.clone(),
+        }),
+    }
+}
+
+/// Every target in `files`, in the order they first appear.
+fn targets(files: &[&LevelFile]) -> Vec<MergeTarget> {
+    let mut targets = Vec::new();
+    for target in files
+        .iter()
+        .flat_map(|file| &file.items)
+        .filter_map(target_of)
+    {
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    targets
+}
+
+fn position(level: &LevelFile, target: &MergeTarget) -> Option<usize> {
+    level
+        .items
+        .iter()
+        .position(|item| target_of(item).as_ref() == Some(target))
+}
+
+fn find<'a>(level: &'a LevelFile, target: &MergeTarget) -> Option<&'a Item> {
+    position(level, target).map(|idx| &level.items[idx])
+}
+
+/// Compares items by what the game sees.
+fn same(a: Option<&Item>, b: Option<&Item>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(Item::Directive(a)), Some(Item::Directive(b))) => {
+            a.value.split_whitespace().eq(b.value.split_whitespace())
+        }
+        (Some(Item::Template(a)), Some(Item::Template(b))) => rooms(a) == rooms(b),
+        _ => false,
+    }
+}
+
+type Room<'a> = (Vec<&'a TemplateSetting>, Vec<&'a str>, Option<Vec<&'a str>>);
+
+fn rooms(template: &Template) -> Vec<Room<'_>> {
+    template
+        .rooms()
+        .map(|chunk| {
+            (
+                chunk.settings().collect(),
+                chunk.foreground(),
+                chunk.background(),
+            )
+        })
+        .collect()
+}
+
+/// Replaces, removes or adds `target` in `level`.
+fn apply(level: &mut LevelFile, target: &MergeTarget, item: Option<Item>) {
+    match (position(level, target), item) {
+        (Some(idx), Some(item)) => level.items[idx] = item,
+        (Some(idx), None) => {
+            level.items.remove(idx);
+        }
+        (None, Some(item)) => {
+            // New directives go after others of their kind, new templates at the end
+            let after = match target {
+                MergeTarget::Directive { kind, .. } => level.items.iter().rposition(
+                    |item| matches!(item, Item::Directive(directive) if directive.kind == *kind),
+                ),
+                MergeTarget::Template { .. } => None,
+            };
+            match after {
+                Some(idx) => level.items.insert(idx + 1, item),
+                None => level.items.push(item),
+            }
+        }
+        (None, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge, Conflict, ConflictKind, MergeTarget};
+    use crate::level_file::{DirectiveKind, LevelFile};
+
+    const VANILLA: &str = concat!(
+        "\\?floor 1\r\n",
+        "\\?empty 0\r\n",
+        "\r\n",
+        "\\.side\r\n",
+        "1111111111\r\n",
+        "\r\n",
+        "\\.path_normal\r\n",
+        "0000000000\r\n",
+    );
+
+    fn parse(text: &str) -> LevelFile {
+        LevelFile::from_bytes(text.as_bytes()).unwrap()
+    }
+
+    fn to_string(level: &LevelFile) -> String {
+        String::from_utf8(level.to_bytes().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_disjoint() {
+        let vanilla = parse(VANILLA);
+        let first = parse(
+            &VANILLA
+                .replace("1111111111", "1100000011")
+                .replace("\\?empty 0", "\\?empty  0  // spaced out"),
+        );
+        let second = parse(
+            &VANILLA
+                .replace("0000000000", "0000110000")
+                .replace("\\?empty 0\r\n", "\\?empty 0\r\n\\?spikes ^\r\n"),
+        );
+
+        let merged = merge(&vanilla, &[&first, &second]);
+        assert_eq!(merged.conflicts, vec![]);
+        assert_eq!(
+            to_string(&merged.level),
+            VANILLA
+                .replace("1111111111", "1100000011")
+                .replace("0000000000", "0000110000")
+                .replace("\\?empty 0\r\n", "\\?empty 0\r\n\\?spikes ^\r\n")
+        );
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let vanilla = parse(VANILLA);
+        let first = parse(&VANILLA.replace("1111111111", "1100000011"));
+        let second = parse(&VANILLA.replace("1111111111", "0000000000"));
+        let third = parse(&VANILLA.replace("\\?floor 1\r\n", ""));
+        let fourth = parse(&VANILLA.replace("1111111111", "1100000011"));
+
+        let merged = merge(&vanilla, &[&first, &second, &third, &fourth]);
+        assert_eq!(
+            merged.conflicts,
+            vec![Conflict {
+                target: MergeTarget::Template {
+                    name: "side".into()
+                },
+                kind: ConflictKind::Changed,
+                kept: Some(0),
+                dropped: 1,
+            }]
+        );
+        assert!(to_string(&merged.level).contains("1100000011"));
+        assert!(merged
+            .level
+            .directive(DirectiveKind::TileCode, "floor")
+            .is_none());
+
+        let fifth = parse(&VANILLA.replace("\\?floor 1", "\\?floor 2"));
+        let merged = merge(&vanilla, &[&third, &fifth]);
+        assert_eq!(
+            merged.conflicts,
+            vec![Conflict {
+                target: MergeTarget::Directive {
+                    kind: DirectiveKind::TileCode,
+                    name: "floor".into()
+                },
+                kind: ConflictKind::Removed,
+                kept: Some(0),
+                dropped: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_glyph_clash() {
+        let vanilla = parse(VANILLA);
+        let first = parse(&VANILLA.replace("\\?empty 0\r\n", "\\?empty 0\r\n\\?foo x\r\n"));
+        // Its room uses x for bar, which would now be foo
+        let second = parse(
+            &VANILLA
+                .replace("\\?empty 0\r\n", "\\?empty 0\r\n\\?bar x\r\n")
+                .replace("1111111111", "11xx111111"),
+        );
+        // Moving floor off its glyph frees it up
+        let third = parse(&VANILLA.replace("\\?floor 1\r\n", "\\?floor 2\r\n\\?baz 1\r\n"));
+        let fourth = parse(&VANILLA.replace("\\?empty 0\r\n", "\\?empty 0\r\n\\?qux 0\r\n"));
+
+        let merged = merge(&vanilla, &[&first, &second, &third, &fourth]);
+        assert_eq!(
+            merged.conflicts,
+            vec![
+                Conflict {
+                    target: MergeTarget::Directive {
+                        kind: DirectiveKind::TileCode,
+                        name: "bar".into()
+                    },
+                    kind: ConflictKind::Glyph,
+                    kept: Some(0),
+                    dropped: 1,
+                },
+                Conflict {
+                    target: MergeTarget::Template {
+                        name: "side".into()
+                    },
+                    kind: ConflictKind::Glyph,
+                    kept: None,
+                    dropped: 1,
+                },
+                Conflict {
+                    target: MergeTarget::Directive {
+                        kind: DirectiveKind::TileCode,
+                        name: "qux".into()
+                    },
+                    kind: ConflictKind::Glyph,
+                    // Vanilla's empty has the glyph
+                    kept: None,
+                    dropped: 3,
+                },
+            ]
+        );
+        let text = to_string(&merged.level);
+        assert!(text.contains("\\?foo x"));
+        assert!(!text.contains("bar"));
+        assert!(text.contains("1111111111"));
+        assert!(text.contains("\\?floor 2"));
+        assert!(text.contains("\\?baz 1"));
+        assert!(!text.contains("qux"));
+    }
+}
@@ -0,0 +1,457 @@
+//! Renders rooms and tile codes to images using extracted texture sheets.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use ml2_entity_data::{ENTITIES, TEXTURES};
+use ml2_types::Entity;
+
+use crate::level_file::LevelFile;
+use crate::template::{Chunk, Template};
+use crate::LevelError;
+
+/// Tile codes whose entity doesn't follow from their name.
+const TILE_CODE_ENTITIES: &[(&str, &str)] = &[
+    ("adjacent_floor", "ENT_TYPE_FLOOR_GENERIC"),
+    ("cog_door", "ENT_TYPE_FLOOR_DOOR_COG"),
+    ("crushtrap", "ENT_TYPE_ACTIVEFLOOR_CRUSH_TRAP"),
+    ("crushtraplarge", "ENT_TYPE_ACTIVEFLOOR_CRUSH_TRAP_LARGE"),
+    ("door2", "ENT_TYPE_FLOOR_DOOR_LAYER"),
+    ("door_drop_held", "ENT_TYPE_FLOOR_DOOR_LAYER_DROP_HELD"),
+    ("eggplant_door", "ENT_TYPE_FLOOR_DOOR_EGGPLANT_WORLD"),
+    ("entrance", "ENT_TYPE_FLOOR_DOOR_ENTRANCE"),
+    ("entrance_shortcut", "ENT_TYPE_FLOOR_DOOR_ENTRANCE"),
+    ("exit", "ENT_TYPE_FLOOR_DOOR_EXIT"),
+    ("floor", "ENT_TYPE_FLOOR_GENERIC"),
+    ("floor_hard", "ENT_TYPE_FLOOR_GENERIC"),
+    ("locked_door", "ENT_TYPE_FLOOR_DOOR_LOCKED"),
+    ("nonreplaceable_floor", "ENT_TYPE_FLOOR_GENERIC"),
+    ("pen_locked_door", "ENT_TYPE_FLOOR_DOOR_LOCKED_PEN"),
+    ("starting_exit", "ENT_TYPE_FLOOR_DOOR_STARTING_EXIT"),
+    ("styled_floor", "ENT_TYPE_FLOORSTYLED_STONE"),
+];
+
+/// Entity name prefixes tried, in order, when a tile code isn't in [`TILE_CODE_ENTITIES`].
+const ENTITY_PREFIXES: &[&str] = &[
+    "ENT_TYPE_",
+    "ENT_TYPE_FLOOR_",
+    "ENT_TYPE_FLOORSTYLED_",
+    "ENT_TYPE_ACTIVEFLOOR_",
+    "ENT_TYPE_MONS_",
+    "ENT_TYPE_MONS_CRITTER",
+    "ENT_TYPE_ITEM_",
+    "ENT_TYPE_ITEM_PICKUP_",
+    "ENT_TYPE_ITEM_POWERUP_",
+    "ENT_TYPE_LIQUID_",
+    "ENT_TYPE_DECORATION_",
+];
+
+/// Tile size of most game textures.
+const GAME_TILE_SIZE: u32 = 128;
+
+/// Largest image, in bytes, drawn for a preview.
+const MAX_IMAGE_BYTES: u64 = 256 << 20;
+
+/// Whether an RGBA image of this size is small enough to draw.
+fn fits(width: u32, height: u32) -> bool {
+    u64::from(width)
+        .checked_mul(u64::from(height))
+        .and_then(|pixels| pixels.checked_mul(4))
+        .is_some_and(|bytes| bytes <= MAX_IMAGE_BYTES)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer {
+    Front,
+    /// Rooms without a back layer render as empty.
+    Back,
+}
+
+/// Finds the entity a tile code places, e.g. `ENT_TYPE_FLOOR_GENERIC` for `floor`.
+///
+/// Only the first part of names like `floor%50%spikes` is used.
+pub fn tile_code_entity(name: &str) -> Option<(&'static str, &'static Entity)> {
+    let name = name.split('%').next().unwrap_or_default();
+    if let Some((_, entity)) = TILE_CODE_ENTITIES.iter().find(|(code, _)| *code == name) {
+        return ENTITIES
+            .get_key_value(*entity)
+            .map(|(name, entity)| (name.as_str(), entity));
+    }
+
+    // e.g. `snake`, `critter_snail` and `babylon_floor`
+    let name = name.to_ascii_uppercase();
+    let mut stems = vec![name.clone(), name.replace('_', "")];
+    stems.extend(name.strip_suffix("_FLOOR").map(Into::into));
+    stems.iter().find_map(|stem| {
+        ENTITY_PREFIXES.iter().find_map(|prefix| {
+            let full = if stem.starts_with(prefix) {
+                stem.clone()
+            } else {
+                format!("{prefix}{stem}")
+            };
+            ENTITIES
+                .get_key_value(&full)
+                .map(|(name, entity)| (name.as_str(), entity))
+        })
+    })
+}
+
+/// Renders rooms with sprites from a directory of extracted assets.
+///
+/// Tile codes without a known entity or sheet are drawn as a solid colour derived from their
+/// name, so previews are still readable without extracted assets.
+pub struct Previewer {
+    /// Contains `Data/Textures`.
+    extract_dir: PathBuf,
+    tile_size: u32,
+    texture_paths: HashMap<i64, String>,
+    sheets: HashMap<String, Option<RgbaImage>>,
+}
+
+impl Previewer {
+    pub fn new(extract_dir: impl AsRef<Path>) -> Self {
+        Self {
+            extract_dir: extract_dir.as_ref().into(),
+            tile_size: 32,
+            texture_paths: HashMap::new(),
+            sheets: HashMap::new(),
+        }
+    }
+
+    /// Sets the size, in pixels, of one tile in rendered images. It can't be 0.
+    pub fn with_tile_size(mut self, tile_size: u32) -> Result<Self, LevelError> {
+        if tile_size == 0 {
+            return Err(LevelError::InvalidValue(
+                "Tile size".into(),
+                tile_size.to_string(),
+            ));
+        }
+        self.tile_size = tile_size;
+        Ok(self)
+    }
+
+    /// Sets the sheet used for a texture id, e.g. `Data/Textures/floor_cave.DDS`.
+    ///
+    /// Floors use negative ids whose sheet depends on the level's theme, so they're drawn as
+    /// placeholders unless set here.
+    pub fn with_texture(mut self, id: i64, path: &str) -> Self {
+        self.texture_paths.insert(id, path.into());
+        self
+    }
+
+    /// Renders one tile of the entity placed by a tile code.
+    pub fn tile_code(&mut self, name: &str) -> Result<RgbaImage, LevelError> {
+        if !fits(self.tile_size, self.tile_size) {
+            return Err(LevelError::TooLarge(name.into(), self.tile_size));
+        }
+        let mut tile = RgbaImage::new(self.tile_size, self.tile_size);
+        self.draw_tile_code(&mut tile, name, 0, 0)?;
+        Ok(tile)
+    }
+
+    /// Renders a room, looking up its tile codes in `levels` in order, e.g. the room's own file
+    /// then `generic.lvl`.
+    pub fn room(
+        &mut self,
+        levels: &[&LevelFile],
+        chunk: &Chunk,
+        layer: Layer,
+    ) -> Result<RgbaImage, LevelError> {
+        let foreground = chunk.foreground();
+        let rows = match layer {
+            Layer::Front => foreground.clone(),
+            Layer::Back => chunk.background().unwrap_or_default(),
+        };
+        // A missing back layer is as big as the front one
+        let sized_by = if rows.is_empty() { &foreground } else { &rows };
+        let width = sized_by
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or_default();
+        let too_large = || LevelError::TooLarge("Room".into(), self.tile_size);
+        let scale = |tiles: usize| {
+            u32::try_from(tiles)
+                .ok()
+                .and_then(|tiles| tiles.checked_mul(self.tile_size))
+                .ok_or_else(too_large)
+        };
+        let (width, height) = (scale(width)?, scale(sized_by.len())?);
+        if !fits(width, height) {
+            return Err(too_large());
+        }
+        let mut image = RgbaImage::new(width, height);
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, code) in row.chars().enumerate() {
+                let x = x as u32 * self.tile_size;
+                let y = y as u32 * self.tile_size;
+                match levels.iter().find_map(|level| level.tile_code_for(code)) {
+                    Some(tile_code) => self.draw_tile_code(&mut image, &tile_code.name, x, y)?,
+                    None => self.draw_placeholder(&mut image, &code.to_string(), x, y),
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    /// Renders the front layer of each room in a template.
+    pub fn template(
+        &mut self,
+        levels: &[&LevelFile],
+        template: &Template,
+    ) -> Result<Vec<RgbaImage>, LevelError> {
+        template
+            .rooms()
+            .map(|chunk| self.room(levels, chunk, Layer::Front))
+            .collect()
+    }
+
+    fn draw_tile_code(
+        &mut self,
+        image: &mut RgbaImage,
+        name: &str,
+        x: u32,
+        y: u32,
+    ) -> Result<(), LevelError> {
+        if name == "empty" {
+            return Ok(());
+        }
+        let sprite = match tile_code_entity(name) {
+            Some((_, entity)) => self.sprite(entity)?,
+            None => None,
+        };
+        let Some(sprite) = sprite else {
+            self.draw_placeholder(image, name, x, y);
+            return Ok(());
+        };
+
+        // Sprites larger than a tile keep their size relative to it, standing on the tile's
+        // bottom edge
+        let scale = |size: u32| {
+            size.checked_mul(self.tile_size)
+                .map(|size| size / GAME_TILE_SIZE)
+                .ok_or_else(|| LevelError::TooLarge(name.into(), self.tile_size))
+        };
+        let (width, height) = (scale(sprite.width())?, scale(sprite.height())?);
+        if !fits(width, height) {
+            return Err(LevelError::TooLarge(name.into(), self.tile_size));
+        }
+        let sprite = imageops::resize(&sprite, width.max(1), height.max(1), FilterType::Triangle);
+        let left = i64::from(x) - (i64::from(width) - i64::from(self.tile_size)) / 2;
+        let top = i64::from(y) + i64::from(self.tile_size) - i64::from(height);
+        imageops::overlay(image, &sprite, left, top);
+        Ok(())
+    }
+
+    fn draw_placeholder(&self, image: &mut RgbaImage, name: &str, x: u32, y: u32) {
+        let [r, g, b] = placeholder_color(name);
+        let fill = Rgba([r, g, b, 255]);
+        let border = Rgba([r / 2, g / 2, b / 2, 255]);
+        let last = self.tile_size - 1;
+        // Tiles hanging off the image are clipped
+        let columns = self.tile_size.min(image.width().saturating_sub(x));
+        let rows = self.tile_size.min(image.height().saturating_sub(y));
+        for dy in 0..rows {
+            for dx in 0..columns {
+                let edge = dx == 0 || dy == 0 || dx == last || dy == last;
+                image.put_pixel(x + dx, y + dy, if edge { border } else { fill });
+            }
+        }
+    }
+
+    /// Crops the first frame of an entity from its sheet, if the sheet is available.
+    fn sprite(&mut self, entity: &Entity) -> Result<Option<RgbaImage>, LevelError> {
+        let texture = TEXTURES.get(&entity.texture.to_string());
+        let path = match (self.texture_paths.get(&entity.texture), texture) {
+            (Some(path), _) => path.clone(),
+            (None, Some(texture)) => texture.path.clone(),
+            (None, None) => return Ok(None),
+        };
+        let Some(sheet) = self.sheet(&path)? else {
+            return Ok(None);
+        };
+
+        // Sheets set with `with_texture` may not be in TEXTURES, so they're assumed to be a grid
+        // of game-sized tiles
+        let tile = i64::from(GAME_TILE_SIZE);
+        let (columns, tile_width, tile_height, offset_x, offset_y) = match texture {
+            Some(texture) => (
+                texture.num_tiles.width.max(1),
+                texture.tile_width,
+                texture.tile_height,
+                texture.offset.width,
+                texture.offset.height,
+            ),
+            None => ((i64::from(sheet.width()) / tile).max(1), tile, tile, 0, 0),
+        };
+        let index = entity.idle_tile(columns);
+        let left = offset_x + (index % columns) * tile_width;
+        let top = offset_y + (index / columns) * tile_height;
+        let in_bounds = left >= 0
+            && top >= 0
+            && left + tile_width <= i64::from(sheet.width())
+            && top + tile_height <= i64::from(sheet.height());
+        if !in_bounds {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            imageops::crop_imm(
+                sheet,
+                left as u32,
+                top as u32,
+                tile_width as u32,
+                tile_height as u32,
+            )
+            .to_image(),
+        ))
+    }
+
+    /// Loads an extracted sheet, returning `None` if it wasn't extracted.
+    fn sheet(&mut self, path: &str) -> Result<Option<&RgbaImage>, LevelError> {
+        if !self.sheets.contains_key(path) {
+            let file = self.extract_dir.join(Path::new(path).with_extension("png"));
+            let sheet = if file.is_file() {
+                Some(image::open(file)?.into_rgba8())
+            } else {
+                None
+            };
+            self.sheets.insert(path.into(), sheet);
+        }
+        Ok(self.sheets[path].as_ref())
+    }
+}
+
+/// A stable colour for a name, using FNV-1a.
+fn placeholder_color(name: &str) -> [u8; 3] {
+    let hash = name.bytes().fold(0x811c9dc5u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x01000193)
+    });
+    let [r, g, b, _] = hash.to_le_bytes();
+    [r, g, b]
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::{placeholder_color, tile_code_entity, Layer, Previewer, TILE_CODE_ENTITIES};
+    use crate::level_file::LevelFile;
+    use crate::LevelError;
+
+    const LEVEL: &str = "\\?empty 0\r\n\\?rock r\r\n\\.side\r\n0r\r\n?0\r\n";
+
+    #[test]
+    fn test_tile_code_entity() {
+        let (name, _) = tile_code_entity("rock").unwrap();
+        assert_eq!(name, "ENT_TYPE_ITEM_ROCK");
+        let (name, _) = tile_code_entity("floor%50%spikes").unwrap();
+        assert_eq!(name, "ENT_TYPE_FLOOR_GENERIC");
+        let (name, _) = tile_code_entity("critter_snail").unwrap();
+        assert_eq!(name, "ENT_TYPE_MONS_CRITTERSNAIL");
+        let (name, _) = tile_code_entity("babylon_floor").unwrap();
+        assert_eq!(name, "ENT_TYPE_FLOORSTYLED_BABYLON");
+        assert!(tile_code_entity("not_a_thing").is_none());
+
+        for (code, _) in TILE_CODE_ENTITIES {
+            assert!(tile_code_entity(code).is_some(), "{code}");
+        }
+    }
+
+    #[test]
+    fn test_room() {
+        // Rocks are tile (0, 1) of the 16x16 tile items sheet
+        let dir = tempfile::tempdir().unwrap();
+        let textures = dir.path().join("Data/Textures");
+        std::fs::create_dir_all(&textures).unwrap();
+        let mut sheet = RgbaImage::new(2048, 2048);
+        for y in 128..256 {
+            for x in 0..128 {
+                sheet.put_pixel(x, y, Rgba([10, 20, 30, 255]));
+            }
+        }
+        sheet.save(textures.join("items.png")).unwrap();
+
+        let level = LevelFile::from_bytes(LEVEL.as_bytes()).unwrap();
+        let room = level.template("side").unwrap().rooms().next().unwrap();
+        let mut previewer = Previewer::new(dir.path()).with_tile_size(16).unwrap();
+        let image = previewer.room(&[&level], room, Layer::Front).unwrap();
+
+        assert_eq!(image.dimensions(), (32, 32));
+        assert_eq!(image.get_pixel(4, 4), &Rgba([0, 0, 0, 0]));
+        assert_eq!(image.get_pixel(20, 4), &Rgba([10, 20, 30, 255]));
+        let [r, g, b] = placeholder_color("?");
+        assert_eq!(image.get_pixel(4, 20), &Rgba([r, g, b, 255]));
+
+        let back = previewer.room(&[&level], room, Layer::Back).unwrap();
+        assert!(back.pixels().all(|pixel| pixel[3] == 0));
+    }
+
+    #[test]
+    fn test_malformed_room() {
+        // The back layer is wider than the front one
+        let level = LevelFile::from_bytes(
+            "\\?rock r\r\n\\.side\r\n\\!dual\r\nr 000\r\n0 000\r\n".as_bytes(),
+        )
+        .unwrap();
+        let room = level.template("side").unwrap().rooms().next().unwrap();
+        let mut previewer = Previewer::new("does-not-exist").with_tile_size(4).unwrap();
+
+        let front = previewer.room(&[&level], room, Layer::Front).unwrap();
+        assert_eq!(front.dimensions(), (4, 8));
+        let back = previewer.room(&[&level], room, Layer::Back).unwrap();
+        assert_eq!(back.dimensions(), (12, 8));
+
+        // The rock has no sheet and 0 no tile code, so both are placeholders, on every column of
+        // the back layer
+        let [r, g, b] = placeholder_color("rock");
+        assert_eq!(front.get_pixel(1, 1), &Rgba([r, g, b, 255]));
+        assert_eq!(front.get_pixel(0, 0), &Rgba([r / 2, g / 2, b / 2, 255]));
+        let [r, g, b] = placeholder_color("0");
+        assert_eq!(front.get_pixel(1, 5), &Rgba([r, g, b, 255]));
+        assert_eq!(back.get_pixel(9, 5), &Rgba([r, g, b, 255]));
+
+        // Placeholders hanging off the image are clipped
+        let mut small = RgbaImage::new(6, 6);
+        previewer.draw_placeholder(&mut small, "r", 4, 4);
+        let [r, g, b] = placeholder_color("r");
+        assert_eq!(small.get_pixel(4, 4), &Rgba([r / 2, g / 2, b / 2, 255]));
+        assert_eq!(small.get_pixel(5, 5), &Rgba([r, g, b, 255]));
+        assert_eq!(small.get_pixel(3, 3), &Rgba([0, 0, 0, 0]));
+
+        // 3 tiles of 2^31 pixels don't fit in a u32
+        let mut huge = Previewer::new("does-not-exist")
+            .with_tile_size(1 << 31)
+            .unwrap();
+        assert!(matches!(
+            huge.room(&[&level], room, Layer::Back),
+            Err(LevelError::TooLarge(..))
+        ));
+        // Fits in a u32, but is far too large to allocate
+        let mut large = Previewer::new("does-not-exist")
+            .with_tile_size(1 << 14)
+            .unwrap();
+        assert!(matches!(
+            large.room(&[&level], room, Layer::Front),
+            Err(LevelError::TooLarge(..))
+        ));
+        // 2^31 pixels square fits in a u32, but its size in bytes doesn't fit in a u64
+        let single = LevelFile::from_bytes("\\.side\r\n0\r\n".as_bytes()).unwrap();
+        let single_room = single.template("side").unwrap().rooms().next().unwrap();
+        assert!(matches!(
+            huge.room(&[&single], single_room, Layer::Front),
+            Err(LevelError::TooLarge(..))
+        ));
+        assert!(matches!(
+            huge.tile_code("rock"),
+            Err(LevelError::TooLarge(..))
+        ));
+        assert!(matches!(
+            Previewer::new("does-not-exist").with_tile_size(0),
+            Err(LevelError::InvalidValue(..))
+        ));
+    }
+}
@@ -0,0 +1,231 @@
+use std::fmt;
+
+use crate::level_file::Trivia;
+use crate::line::{Decor, LineParts};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TemplateSetting {
+    /// The room is skipped. Useful while building rooms.
+    Ignore,
+    /// The room is also used mirrored horizontally.
+    Flip,
+    /// Only the mirrored room is used.
+    OnlyFlip,
+    /// The room has both a front and a back layer.
+    Dual,
+    /// The room is only used 5% of the time.
+    Rare,
+    /// The room only appears in the later levels of an area.
+    Hard,
+    /// The room contains liquid.
+    Liquid,
+    Purge,
+    /// A setting this crate doesn't know about, kept as written.
+    Other(String),
+}
+
+impl From<&str> for TemplateSetting {
+    fn from(s: &str) -> Self {
+        match s {
+            "ignore" => TemplateSetting::Ignore,
+            "flip" => TemplateSetting::Flip,
+            "onlyflip" => TemplateSetting::OnlyFlip,
+            "dual" => TemplateSetting::Dual,
+            "rare" => TemplateSetting::Rare,
+            "hard" => TemplateSetting::Hard,
+            "liquid" => TemplateSetting::Liquid,
+            "purge" => TemplateSetting::Purge,
+            other => TemplateSetting::Other(other.into()),
+        }
+    }
+}
+
+impl fmt::Display for TemplateSetting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TemplateSetting::Ignore => "ignore",
+            TemplateSetting::Flip => "flip",
+            TemplateSetting::OnlyFlip => "onlyflip",
+            TemplateSetting::Dual => "dual",
+            TemplateSetting::Rare => "rare",
+            TemplateSetting::Hard => "hard",
+            TemplateSetting::Liquid => "liquid",
+            TemplateSetting::Purge => "purge",
+            TemplateSetting::Other(name) => name,
+        };
+        f.write_str(name)
+    }
+}
+
+/// One row of tile codes. Dual rooms have a back layer after the front one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Row {
+    pub foreground: String,
+    pub background: Option<String>,
+    pub(crate) decor: Decor,
+}
+
+impl Row {
+    pub fn new(foreground: &str, background: Option<&str>) -> Self {
+        Self {
+            foreground: foreground.into(),
+            background: background.map(Into::into),
+            decor: Decor::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChunkLine {
+    Trivia(Trivia),
+    Setting(TemplateSetting, Decor),
+    Row(Row),
+}
+
+/// A single room within a template. Consecutive rooms are separated by blank lines.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Chunk {
+    pub lines: Vec<ChunkLine>,
+}
+
+impl Chunk {
+    pub fn settings(&self) -> impl Iterator<Item = &TemplateSetting> {
+        self.lines.iter().filter_map(|line| match line {
+            ChunkLine::Setting(setting, _) => Some(setting),
+            _ => None,
+        })
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &Row> {
+        self.lines.iter().filter_map(|line| match line {
+            ChunkLine::Row(row) => Some(row),
+            _ => None,
+        })
+    }
+
+    pub fn has_setting(&self, setting: &TemplateSetting) -> bool {
+        self.settings().any(|s| s == setting)
+    }
+
+    pub fn foreground(&self) -> Vec<&str> {
+        self.rows().map(|row| row.foreground.as_str()).collect()
+    }
+
+    /// Returns the back layer, or `None` if no row has one.
+    pub fn background(&self) -> Option<Vec<&str>> {
+        if self.rows().all(|row| row.background.is_none()) {
+            return None;
+        }
+        Some(
+            self.rows()
+                .map(|row| row.background.as_deref().unwrap_or_default())
+                .collect(),
+        )
+    }
+
+    /// Whether the chunk holds a room, rather than only comments or blank lines.
+    pub fn is_room(&self) -> bool {
+        self.rows().next().is_some()
+    }
+
+    pub(crate) fn has_content(&self) -> bool {
+        self.lines
+            .iter()
+            .any(|line| !matches!(line, ChunkLine::Trivia(_)))
+    }
+}
+
+/// A named set of rooms, started by a `\.` line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    pub name: String,
+    /// Everything after `//` on the name line, verbatim.
+    pub comment: Option<String>,
+    pub chunks: Vec<Chunk>,
+    pub(crate) decor: Decor,
+}
+
+impl Template {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            comment: None,
+            chunks: Vec::new(),
+            decor: Decor {
+                gap: String::new(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Chunks that hold rooms.
+    pub fn rooms(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.iter().filter(|chunk| chunk.is_room())
+    }
+}
+
+impl ChunkLine {
+    pub(crate) fn parse(line: &str, decor: Decor) -> Self {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            return ChunkLine::Trivia(Trivia::with_ending(line, decor.ending));
+        }
+
+        if let Some(setting) = trimmed.strip_prefix(r"\!") {
+            let parts = LineParts::split(line, false);
+            let name = setting.trim_start();
+            let gap = &setting[..setting.len() - name.len()];
+            return ChunkLine::Setting(
+                name.trim_end().into(),
+                Decor {
+                    indent: parts.indent.into(),
+                    gap: gap.into(),
+                    trailing: parts.trailing.into(),
+                    ending: decor.ending,
+                },
+            );
+        }
+
+        let parts = LineParts::split(line, false);
+        ChunkLine::Row(Row {
+            foreground: parts.head.into(),
+            background: Some(parts.tail)
+                .filter(|tail| !tail.is_empty())
+                .map(Into::into),
+            decor: Decor {
+                indent: parts.indent.into(),
+                gap: parts.gap.into(),
+                trailing: parts.trailing.into(),
+                ending: decor.ending,
+            },
+        })
+    }
+
+    pub(crate) fn write(&self, out: &mut String) {
+        match self {
+            ChunkLine::Trivia(trivia) => trivia.write(out),
+            ChunkLine::Setting(setting, decor) => {
+                out.push_str(&decor.indent);
+                out.push_str(r"\!");
+                out.push_str(&decor.gap);
+                out.push_str(&setting.to_string());
+                out.push_str(&decor.trailing);
+                out.push_str(decor.ending.as_str());
+            }
+            ChunkLine::Row(row) => {
+                out.push_str(&row.decor.indent);
+                out.push_str(&row.foreground);
+                if let Some(background) = &row.background {
+                    out.push_str(if row.decor.gap.is_empty() {
+                        " "
+                    } else {
+                        &row.decor.gap
+                    });
+                    out.push_str(background);
+                }
+                out.push_str(&row.decor.trailing);
+                out.push_str(row.decor.ending.as_str());
+            }
+        }
+    }
+}
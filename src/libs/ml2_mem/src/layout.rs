@@ -0,0 +1,319 @@
+//! Reading values and structs from a [`MemorySource`].
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{MemError, MemorySource};
+
+/// A value stored inline, at a fixed size.
+pub trait MemType: Sized {
+    const SIZE: usize;
+
+    fn read<S: MemorySource + ?Sized>(src: &S, addr: u64) -> Result<Self, MemError>;
+}
+
+/// A struct only read through a [`Pointer`], so its size doesn't matter.
+pub trait MemStruct: Sized {
+    fn read<S: MemorySource + ?Sized>(src: &S, addr: u64) -> Result<Self, MemError>;
+}
+
+macro_rules! mem_primitive {
+    ($($ty:ty),*) => {
+        $(
+            impl MemType for $ty {
+                const SIZE: usize = std::mem::size_of::<$ty>();
+
+                fn read<S: MemorySource + ?Sized>(src: &S, addr: u64) -> Result<Self, MemError> {
+                    let mut buf = [0; std::mem::size_of::<$ty>()];
+                    src.read_bytes(addr, &mut buf)?;
+                    Ok(<$ty>::from_le_bytes(buf))
+                }
+            }
+        )*
+    };
+}
+
+mem_primitive!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+impl MemType for bool {
+    const SIZE: usize = 1;
+
+    fn read<S: MemorySource + ?Sized>(src: &S, addr: u64) -> Result<Self, MemError> {
+        match u8::read(src, addr)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(MemError::InvalidValue {
+                type_name: "bool",
+                value: value.into(),
+            }),
+        }
+    }
+}
+
+impl<T: MemType + Copy + Default, const N: usize> MemType for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    fn read<S: MemorySource + ?Sized>(src: &S, addr: u64) -> Result<Self, MemError> {
+        let mut values = [T::default(); N];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = T::read(src, field_addr(addr, (i * T::SIZE) as u64, T::SIZE)?)?;
+        }
+        Ok(values)
+    }
+}
+
+/// `addr + offset`, or unreadable if that's past the end of the address space, e.g. when
+/// following a garbage pointer.
+pub(crate) fn field_addr(addr: u64, offset: u64, len: usize) -> Result<u64, MemError> {
+    addr.checked_add(offset)
+        .ok_or(MemError::Unreadable { addr, len })
+}
+
+/// A 64-bit pointer to a `T`, which may be null.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Pointer<T> {
+    pub addr: u64,
+    #[serde(skip)]
+    target: PhantomData<T>,
+}
+
+impl<T> Pointer<T> {
+    pub fn new(addr: u64) -> Self {
+        Self {
+            addr,
+            target: PhantomData,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.addr == 0
+    }
+}
+
+impl<T: MemStruct> Pointer<T> {
+    /// Reads the target, or `None` if the pointer is null.
+    pub fn read<S: MemorySource + ?Sized>(&self, src: &S) -> Result<Option<T>, MemError> {
+        if self.is_null() {
+            return Ok(None);
+        }
+        T::read(src, self.addr).map(Some)
+    }
+}
+
+// Derives would require T to implement these too
+impl<T> Clone for Pointer<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Pointer<T> {}
+
+impl<T> Default for Pointer<T> {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl<T> PartialEq for Pointer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.addr == other.addr
+    }
+}
+
+impl<T> Eq for Pointer<T> {}
+
+impl<T> fmt::Debug for Pointer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pointer({:#x})", self.addr)
+    }
+}
+
+impl<T> MemType for Pointer<T> {
+    const SIZE: usize = 8;
+
+    fn read<S: MemorySource + ?Sized>(src: &S, addr: u64) -> Result<Self, MemError> {
+        u64::read(src, addr).map(Self::new)
+    }
+}
+
+/// The game's vectors, which point to `len` consecutive `T`s. Unlike fixed-size fields, the
+/// elements are only read when needed.
+#[derive(Serialize, Deserialize)]
+pub struct Vector<T> {
+    pub addr: u64,
+    pub len: u32,
+    #[serde(skip)]
+    elements: PhantomData<T>,
+}
+
+impl<T> Vector<T> {
+    pub fn new(addr: u64, len: u32) -> Self {
+        Self {
+            addr,
+            len,
+            elements: PhantomData,
+        }
+    }
+}
+
+impl<T: MemType> Vector<T> {
+    /// Reads the elements, or none if the vector was never allocated.
+    pub fn read<S: MemorySource + ?Sized>(&self, src: &S) -> Result<Vec<T>, MemError> {
+        if self.addr == 0 {
+            return Ok(Vec::new());
+        }
+        (0..u64::from(self.len))
+            .map(|i| {
+                let offset = i.checked_mul(T::SIZE as u64).ok_or(MemError::Unreadable {
+                    addr: self.addr,
+                    len: T::SIZE,
+                })?;
+                T::read(src, field_addr(self.addr, offset, T::SIZE)?)
+            })
+            .collect()
+    }
+}
+
+impl<T> Clone for Vector<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Vector<T> {}
+
+impl<T> Default for Vector<T> {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+impl<T> PartialEq for Vector<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.addr, self.len) == (other.addr, other.len)
+    }
+}
+
+impl<T> Eq for Vector<T> {}
+
+impl<T> fmt::Debug for Vector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Vector({:#x}, {})", self.addr, self.len)
+    }
+}
+
+impl<T> MemType for Vector<T> {
+    const SIZE: usize = 0x18;
+
+    fn read<S: MemorySource + ?Sized>(src: &S, addr: u64) -> Result<Self, MemError> {
+        let elements = u64::read(src, field_addr(addr, 0x08, 8)?)?;
+        let len = u32::read(src, field_addr(addr, 0x14, 4)?)?;
+        Ok(Self::new(elements, len))
+    }
+}
+
+/// `N` inline `T`s, for arrays too long for serde's `[T; N]` impls.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Array<T, const N: usize>(pub Vec<T>);
+
+impl<T: MemType, const N: usize> MemType for Array<T, N> {
+    const SIZE: usize = T::SIZE * N;
+
+    fn read<S: MemorySource + ?Sized>(src: &S, addr: u64) -> Result<Self, MemError> {
+        (0..N)
+            .map(|i| T::read(src, field_addr(addr, (i * T::SIZE) as u64, T::SIZE)?))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Declares a struct whose fields are read at the given offsets.
+macro_rules! mem_struct {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                pub $field:ident: $ty:ty = $offset:literal,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+        pub struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl $name {
+            /// Each field's name, type and offset, for checking against other layouts.
+            #[cfg(test)]
+            pub(crate) const FIELDS: &'static [(&'static str, &'static str, u64)] =
+                &[$((stringify!($field), stringify!($ty), $offset),)*];
+        }
+
+        impl $crate::MemStruct for $name {
+            fn read<S: $crate::MemorySource + ?Sized>(
+                src: &S,
+                addr: u64,
+            ) -> Result<Self, $crate::MemError> {
+                Ok(Self {
+                    $($field: <$ty as $crate::MemType>::read(
+                        src,
+                        $crate::layout::field_addr(addr, $offset, <$ty as $crate::MemType>::SIZE)?,
+                    )?,)*
+                })
+            }
+        }
+    };
+}
+
+/// Declares an enum stored as `$repr`. Other values are [`MemError::InvalidValue`]s.
+macro_rules! mem_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident: $repr:ty {
+            $($variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant = $value,)*
+        }
+
+        impl $name {
+            pub fn from_raw(value: i64) -> Option<Self> {
+                match value {
+                    $($value => Some(Self::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl $crate::MemType for $name {
+            const SIZE: usize = std::mem::size_of::<$repr>();
+
+            fn read<S: $crate::MemorySource + ?Sized>(
+                src: &S,
+                addr: u64,
+            ) -> Result<Self, $crate::MemError> {
+                let value = i64::from(<$repr as $crate::MemType>::read(src, addr)?);
+                Self::from_raw(value).ok_or($crate::MemError::InvalidValue {
+                    type_name: stringify!($name),
+                    value,
+                })
+            }
+        }
+    };
+}
+
+pub(crate) use mem_enum;
+pub(crate) use mem_struct;
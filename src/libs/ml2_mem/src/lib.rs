@@ -0,0 +1,38 @@
+//! Decodes Spelunky 2's game state from memory.
+//!
+//! Structs are read through a [`MemorySource`], so the same layouts work on a live process or on
+//! [`Snapshot`]s of memory captured earlier. Only the fields trackers need are decoded, at the
+//! offsets of the game's structs.
+
+mod layout;
+mod recording;
+mod source;
+mod state;
+
+use thiserror::Error;
+
+pub use layout::{Array, MemStruct, MemType, Pointer, Vector};
+pub use recording::{RecordedFrame, RecordingReader, RecordingWriter};
+pub use source::{MemoryDump, MemorySource, Snapshot};
+pub use state::{
+    CharState, Entity, EntityDb, Inventory, Items, LoadingState, Mount, Player, Screen, State,
+    Theme, UidEntityMap, WinState,
+};
+
+#[derive(Error, Debug)]
+pub enum MemError {
+    #[error("IoError")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Can't read {len} bytes at {addr:#x}")]
+    Unreadable { addr: u64, len: usize },
+
+    #[error("{type_name} has an invalid value: {value}")]
+    InvalidValue { type_name: &'static str, value: i64 },
+
+    #[error("Invalid recording: {0}")]
+    InvalidRecording(String),
+
+    #[error("Invalid snapshot name {0:?}")]
+    InvalidSnapshotName(String),
+}
@@ -0,0 +1,299 @@
+//! A compact format for recording memory over a run, and replaying it.
+//!
+//! A recording is a header followed by frames. Each frame has a timestamp and every region of a
+//! [`MemoryDump`], either in full or as the byte ranges that changed since the previous frame:
+//!
+//! ```text
+//! header: b"ML2REC" version:u16
+//! frame:  time_ms:u64 region_count:u32 region*
+//! region: base:u64 0:u8 len:u32 bytes
+//!       | base:u64 1:u8 run_count:u32 (offset:u32 len:u32 bytes)*
+//! ```
+//!
+//! Integers are little-endian.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use crate::{MemError, MemoryDump, Snapshot};
+
+const MAGIC: &[u8; 6] = b"ML2REC";
+const VERSION: u16 = 1;
+
+const FULL: u8 = 0;
+const DELTA: u8 = 1;
+
+/// Unchanged gaps shorter than this are stored rather than starting a new run, since a run costs
+/// 8 bytes.
+const MERGE_GAP: usize = 8;
+
+/// A dump captured at a time, relative to the start of the recording.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedFrame {
+    pub time_ms: u64,
+    pub dump: MemoryDump,
+}
+
+pub struct RecordingWriter<W: Write> {
+    writer: W,
+    previous: HashMap<u64, Vec<u8>>,
+}
+
+impl<W: Write> RecordingWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self, MemError> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        Ok(Self {
+            writer,
+            previous: HashMap::new(),
+        })
+    }
+
+    pub fn write_frame(&mut self, time_ms: u64, dump: &MemoryDump) -> Result<(), MemError> {
+        let w = &mut self.writer;
+        w.write_all(&time_ms.to_le_bytes())?;
+        w.write_all(&len_u32(dump.regions.len())?.to_le_bytes())?;
+        let mut current = HashMap::with_capacity(dump.regions.len());
+        for region in &dump.regions {
+            w.write_all(&region.base.to_le_bytes())?;
+            match self.previous.get(&region.base) {
+                Some(old) if old.len() == region.bytes.len() => {
+                    let runs = changed_runs(old, &region.bytes);
+                    w.write_all(&[DELTA])?;
+                    w.write_all(&len_u32(runs.len())?.to_le_bytes())?;
+                    for (start, end) in runs {
+                        w.write_all(&len_u32(start)?.to_le_bytes())?;
+                        w.write_all(&len_u32(end - start)?.to_le_bytes())?;
+                        w.write_all(&region.bytes[start..end])?;
+                    }
+                }
+                _ => {
+                    w.write_all(&[FULL])?;
+                    w.write_all(&len_u32(region.bytes.len())?.to_le_bytes())?;
+                    w.write_all(&region.bytes)?;
+                }
+            }
+            current.insert(region.base, region.bytes.clone());
+        }
+        self.previous = current;
+        Ok(())
+    }
+
+    /// Flushes and returns the writer.
+    pub fn finish(mut self) -> Result<W, MemError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Replays a recording as full dumps, which can be read like the game's memory.
+pub struct RecordingReader<R: Read> {
+    reader: R,
+    current: HashMap<u64, Vec<u8>>,
+}
+
+impl<R: Read> RecordingReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, MemError> {
+        let mut magic = [0; 6];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(MemError::InvalidRecording("not a recording".into()));
+        }
+        let version = u16::from_le_bytes(read_array(&mut reader)?);
+        if version != VERSION {
+            return Err(MemError::InvalidRecording(format!(
+                "unsupported version {version}"
+            )));
+        }
+        Ok(Self {
+            reader,
+            current: HashMap::new(),
+        })
+    }
+
+    /// Reads the next frame, or `None` at the end of the recording.
+    pub fn read_frame(&mut self) -> Result<Option<RecordedFrame>, MemError> {
+        let mut time = [0; 8];
+        // The recording only ends cleanly between frames, so part of a timestamp is truncation
+        match read_up_to(&mut self.reader, &mut time)? {
+            0 => return Ok(None),
+            8 => {}
+            _ => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+        }
+        let time_ms = u64::from_le_bytes(time);
+
+        let r = &mut self.reader;
+        let count = u32::from_le_bytes(read_array(r)?);
+        // Not preallocated from `count`, which a corrupt recording could make huge
+        let mut current = HashMap::new();
+        let mut regions = Vec::new();
+        for _ in 0..count {
+            let base = u64::from_le_bytes(read_array(r)?);
+            let [kind] = read_array(r)?;
+            let bytes = match kind {
+                FULL => {
+                    let len = u32::from_le_bytes(read_array(r)?);
+                    read_vec(r, len)?
+                }
+                DELTA => {
+                    let mut bytes = self.current.remove(&base).ok_or_else(|| {
+                        MemError::InvalidRecording(format!("delta for unknown region {base:#x}"))
+                    })?;
+                    for _ in 0..u32::from_le_bytes(read_array(r)?) {
+                        let start = u32::from_le_bytes(read_array(r)?) as usize;
+                        let len = u32::from_le_bytes(read_array(r)?);
+                        let run = bytes.get_mut(start..start + len as usize).ok_or_else(|| {
+                            MemError::InvalidRecording(format!("delta outside region {base:#x}"))
+                        })?;
+                        r.read_exact(run)?;
+                    }
+                    bytes
+                }
+                kind => {
+                    return Err(MemError::InvalidRecording(format!(
+                        "unknown region kind {kind}"
+                    )))
+                }
+            };
+            current.insert(base, bytes.clone());
+            regions.push(Snapshot::new(base, bytes));
+        }
+        self.current = current;
+        Ok(Some(RecordedFrame {
+            time_ms,
+            dump: MemoryDump { regions },
+        }))
+    }
+}
+
+impl<R: Read> Iterator for RecordingReader<R> {
+    type Item = Result<RecordedFrame, MemError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+/// Ranges of bytes that differ, with short unchanged gaps merged in.
+fn changed_runs(old: &[u8], new: &[u8]) -> Vec<(usize, usize)> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (i, (a, b)) in old.iter().zip(new).enumerate() {
+        if a == b {
+            continue;
+        }
+        match runs.last_mut() {
+            Some((_, end)) if i - *end < MERGE_GAP => *end = i + 1,
+            _ => runs.push((i, i + 1)),
+        }
+    }
+    runs
+}
+
+fn len_u32(len: usize) -> Result<u32, MemError> {
+    u32::try_from(len).map_err(|_| MemError::InvalidRecording(format!("{len} is too long")))
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], MemError> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// Reads until `buf` is full or the reader ends, returning how many bytes were read.
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize, MemError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+fn read_vec(reader: &mut impl Read, len: u32) -> Result<Vec<u8>, MemError> {
+    let mut buf = Vec::new();
+    reader.take(len.into()).read_to_end(&mut buf)?;
+    if buf.len() != len as usize {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecordedFrame, RecordingReader, RecordingWriter};
+    use crate::{MemError, MemType, MemoryDump, Snapshot};
+
+    fn dump(counter: u32, extra: Option<Vec<u8>>) -> MemoryDump {
+        let mut state = vec![0; 0x100];
+        state[0x40..0x44].copy_from_slice(&counter.to_le_bytes());
+        let mut regions = vec![Snapshot::new(0x1000, state)];
+        regions.extend(extra.map(|bytes| Snapshot::new(0x2000, bytes)));
+        MemoryDump { regions }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let frames = vec![
+            RecordedFrame {
+                time_ms: 0,
+                dump: dump(1, None),
+            },
+            RecordedFrame {
+                time_ms: 16,
+                dump: dump(2, Some(vec![1, 2, 3])),
+            },
+            RecordedFrame {
+                time_ms: 33,
+                dump: dump(300, Some(vec![1, 2, 3])),
+            },
+            RecordedFrame {
+                time_ms: 50,
+                dump: dump(300, Some(vec![4, 5])),
+            },
+        ];
+
+        let mut writer = RecordingWriter::new(Vec::new()).unwrap();
+        for frame in &frames {
+            writer.write_frame(frame.time_ms, &frame.dump).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+        // The unchanged state is only stored once
+        assert!(bytes.len() < 2 * 0x100);
+
+        let replayed = RecordingReader::new(bytes.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(replayed, frames);
+        assert_eq!(u32::read(&replayed[2].dump, 0x1040).unwrap(), 300);
+
+        let truncated = RecordingReader::new(&bytes[..bytes.len() - 1])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>();
+        assert!(matches!(truncated, Err(MemError::IoError(_))));
+
+        // A frame cut off inside its timestamp
+        let mut partial = bytes.clone();
+        partial.extend_from_slice(&[1, 2, 3]);
+        let partial = RecordingReader::new(partial.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>();
+        assert!(matches!(partial, Err(MemError::IoError(_))));
+
+        // A huge region count fails on the missing regions rather than allocating for them
+        let mut huge = bytes[..8].to_vec();
+        huge.extend_from_slice(&0u64.to_le_bytes());
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        let mut reader = RecordingReader::new(huge.as_slice()).unwrap();
+        assert!(matches!(reader.read_frame(), Err(MemError::IoError(_))));
+
+        assert!(matches!(
+            RecordingReader::new(&b"NOTREC\x01\x00"[..]),
+            Err(MemError::InvalidRecording(_))
+        ));
+    }
+}
@@ -0,0 +1,149 @@
+use std::fs;
+use std::path::Path;
+
+use crate::MemError;
+
+/// Somewhere to read the game's memory from.
+pub trait MemorySource {
+    /// Fills `buf` with the bytes starting at `addr`.
+    fn read_bytes(&self, addr: u64, buf: &mut [u8]) -> Result<(), MemError>;
+}
+
+impl<S: MemorySource + ?Sized> MemorySource for &S {
+    fn read_bytes(&self, addr: u64, buf: &mut [u8]) -> Result<(), MemError> {
+        (**self).read_bytes(addr, buf)
+    }
+}
+
+/// A copy of a contiguous region of memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub base: u64,
+    pub bytes: Vec<u8>,
+}
+
+impl Snapshot {
+    pub fn new(base: u64, bytes: Vec<u8>) -> Self {
+        Self { base, bytes }
+    }
+
+    /// Copies `len` bytes at `base` from another source, e.g. the game while it's running.
+    pub fn capture<S: MemorySource + ?Sized>(
+        src: &S,
+        base: u64,
+        len: usize,
+    ) -> Result<Self, MemError> {
+        let mut bytes = vec![0; len];
+        src.read_bytes(base, &mut bytes)?;
+        Ok(Self::new(base, bytes))
+    }
+
+    pub fn load(base: u64, path: &Path) -> Result<Self, MemError> {
+        Ok(Self::new(base, fs::read(path)?))
+    }
+
+    fn contains(&self, addr: u64, len: usize) -> bool {
+        addr >= self.base
+            && addr
+                .checked_add(len as u64)
+                .is_some_and(|end| end <= self.base + self.bytes.len() as u64)
+    }
+}
+
+impl MemorySource for Snapshot {
+    fn read_bytes(&self, addr: u64, buf: &mut [u8]) -> Result<(), MemError> {
+        if !self.contains(addr, buf.len()) {
+            return Err(MemError::Unreadable {
+                addr,
+                len: buf.len(),
+            });
+        }
+        let start = (addr - self.base) as usize;
+        buf.copy_from_slice(&self.bytes[start..start + buf.len()]);
+        Ok(())
+    }
+}
+
+/// Several snapshots, e.g. the state and the heap it points into.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryDump {
+    pub regions: Vec<Snapshot>,
+}
+
+impl MemoryDump {
+    /// Loads every `<hex address>.bin` file in `dir` as a snapshot at that address.
+    pub fn load_dir(dir: &Path) -> Result<Self, MemError> {
+        let mut regions = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().map_or(true, |ext| ext != "bin") {
+                continue;
+            }
+            let stem = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let base = u64::from_str_radix(stem.trim_start_matches("0x"), 16)
+                .map_err(|_| MemError::InvalidSnapshotName(stem.clone()))?;
+            regions.push(Snapshot::load(base, &path)?);
+        }
+        regions.sort_by_key(|r| r.base);
+        Ok(Self { regions })
+    }
+
+    /// Writes each snapshot to `dir` in the form [`MemoryDump::load_dir`] reads.
+    pub fn save_dir(&self, dir: &Path) -> Result<(), MemError> {
+        fs::create_dir_all(dir)?;
+        for region in &self.regions {
+            fs::write(dir.join(format!("{:x}.bin", region.base)), &region.bytes)?;
+        }
+        Ok(())
+    }
+}
+
+impl MemorySource for MemoryDump {
+    fn read_bytes(&self, addr: u64, buf: &mut [u8]) -> Result<(), MemError> {
+        match self.regions.iter().find(|r| r.contains(addr, buf.len())) {
+            Some(region) => region.read_bytes(addr, buf),
+            None => Err(MemError::Unreadable {
+                addr,
+                len: buf.len(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryDump, MemorySource, Snapshot};
+    use crate::MemError;
+
+    #[test]
+    fn test_dump() {
+        let dump = MemoryDump {
+            regions: vec![
+                Snapshot::new(0x1000, vec![1, 2, 3, 4]),
+                Snapshot::new(0x2000, vec![5, 6]),
+            ],
+        };
+        let mut buf = [0; 2];
+        dump.read_bytes(0x1002, &mut buf).unwrap();
+        assert_eq!(buf, [3, 4]);
+        dump.read_bytes(0x2000, &mut buf).unwrap();
+        assert_eq!(buf, [5, 6]);
+
+        let err = dump.read_bytes(0x1003, &mut buf).unwrap_err();
+        assert!(matches!(
+            err,
+            MemError::Unreadable {
+                addr: 0x1003,
+                len: 2
+            }
+        ));
+        assert!(dump.read_bytes(u64::MAX, &mut buf).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        dump.save_dir(dir.path()).unwrap();
+        assert_eq!(MemoryDump::load_dir(dir.path()).unwrap(), dump);
+    }
+}
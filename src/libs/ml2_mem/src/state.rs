@@ -0,0 +1,738 @@
+//! The game's State and the structs reachable from it.
+//!
+//! Offsets follow the community-documented `StateMemory`, `Items` and `Player` structs, and
+//! match the layouts in `modlunky2/mem` that the Python trackers read.
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{field_addr, mem_enum, mem_struct};
+use crate::{Array, MemError, MemStruct, MemType, MemorySource, Pointer, Vector};
+
+mem_enum! {
+    pub enum Screen: i32 {
+        Logo = 0,
+        Intro = 1,
+        Prologue = 2,
+        Title = 3,
+        Menu = 4,
+        Options = 5,
+        PlayerProfile = 6,
+        Leaderboard = 7,
+        SeedInput = 8,
+        CharacterSelect = 9,
+        TeamSelect = 10,
+        Camp = 11,
+        Level = 12,
+        LevelTransition = 13,
+        Death = 14,
+        Spaceship = 15,
+        Win = 16,
+        Credits = 17,
+        Scores = 18,
+        Constellation = 19,
+        Recap = 20,
+        ArenaMenu = 21,
+        ArenaStages = 22,
+        ArenaItems = 23,
+        ArenaSelect = 24,
+        ArenaIntro = 25,
+        ArenaLevel = 26,
+        ArenaScore = 27,
+        OnlineLoading = 28,
+        OnlineLobby = 29,
+    }
+}
+
+mem_enum! {
+    pub enum Theme: u8 {
+        Dwelling = 1,
+        Jungle = 2,
+        Volcana = 3,
+        Olmec = 4,
+        TidePool = 5,
+        Temple = 6,
+        IceCaves = 7,
+        NeoBabylon = 8,
+        SunkenCity = 9,
+        CosmicOcean = 10,
+        CityOfGold = 11,
+        Duat = 12,
+        Abzu = 13,
+        Tiamat = 14,
+        EggplantWorld = 15,
+        Hundun = 16,
+        BaseCamp = 17,
+        Arena = 18,
+    }
+}
+
+mem_enum! {
+    /// Which ending the run reached, if any.
+    pub enum WinState: u8 {
+        NoWin = 0,
+        Tiamat = 1,
+        Hundun = 2,
+        CosmicOcean = 3,
+    }
+}
+
+mem_enum! {
+    /// Whether the game is between screens.
+    pub enum LoadingState: i32 {
+        NotLoading = 0,
+        Start = 1,
+        Loading = 2,
+        End = 3,
+    }
+}
+
+mem_enum! {
+    /// What a player or monster is doing.
+    pub enum CharState: u8 {
+        Flailing = 0,
+        Standing = 1,
+        Sitting = 2,
+        Unknown1 = 3,
+        Hanging = 4,
+        Ducking = 5,
+        Climbing = 6,
+        Pushing = 7,
+        Jumping = 8,
+        Falling = 9,
+        Dropping = 10,
+        Unknown2 = 11,
+        Attacking = 12,
+        Unknown3 = 13,
+        Unknown4 = 14,
+        Unknown5 = 15,
+        Unknown6 = 16,
+        Throwing = 17,
+        Stunned = 18,
+        Entering = 19,
+        Loading = 20,
+        Exiting = 21,
+        Dying = 22,
+        Unknown7 = 23,
+        Unknown8 = 24,
+        Unknown9 = 25,
+        Unknown10 = 26,
+        Unknown11 = 27,
+        Unknown12 = 28,
+        Unknown13 = 29,
+    }
+}
+
+mem_struct! {
+    pub struct State {
+        pub screen_last: Screen = 0x08,
+        pub screen: Screen = 0x0C,
+        pub screen_next: Screen = 0x10,
+        pub loading: LoadingState = 0x14,
+        pub ingame: bool = 0x30,
+        pub playing: bool = 0x31,
+        /// Bits for each reason the game is paused.
+        pub pause: u8 = 0x32,
+        pub quest_flags: u32 = 0x38,
+        pub world_start: u8 = 0x5C,
+        pub level_start: u8 = 0x5D,
+        pub theme_start: u8 = 0x5E,
+        pub seed: u32 = 0x60,
+        /// Frames since the run started.
+        pub time_total: u32 = 0x64,
+        pub world: u8 = 0x68,
+        pub world_next: u8 = 0x69,
+        pub level: u8 = 0x6A,
+        pub level_next: u8 = 0x6B,
+        /// Zero outside of levels, so see [`State::theme`].
+        pub theme: u8 = 0x74,
+        pub theme_next: u8 = 0x75,
+        pub win_state: WinState = 0x76,
+        /// Types of the items stored with Waddler, 0 for empty slots.
+        pub waddler_storage: Array<u32, 99> = 0x8C,
+        /// [`State::RECAP_PACIFIST`] and the other run recap bits still earned.
+        pub run_recap_flags: u32 = 0xA34,
+        /// Frames since the level started.
+        pub time_level: u32 = 0xA44,
+        pub hud_flags: u32 = 0xA50,
+        pub presence_flags: u32 = 0xA54,
+        /// The uid the next entity spawned will get.
+        pub next_entity_uid: u32 = 0x12E0,
+        pub items: Pointer<Items> = 0x12F0,
+        pub instance_id_to_pointer: UidEntityMap = 0x1348,
+    }
+}
+
+impl State {
+    /// [`State::run_recap_flags`] bit for not having killed anything.
+    pub const RECAP_PACIFIST: u32 = 1 << 0;
+    /// [`State::run_recap_flags`] bit for not having collected any gold.
+    pub const RECAP_NO_GOLD: u32 = 1 << 10;
+    /// [`State::hud_flags`] bit for holding a four-leaf clover.
+    pub const HUD_HAVE_CLOVER: u32 = 1 << 22;
+    /// [`State::presence_flags`] bit for being in the moon challenge.
+    pub const PRESENCE_MOON_CHALLENGE: u32 = 1 << 8;
+    /// [`State::presence_flags`] bit for being in the sun challenge.
+    pub const PRESENCE_SUN_CHALLENGE: u32 = 1 << 10;
+
+    /// The current theme, or `None` outside of levels.
+    pub fn theme(&self) -> Option<Theme> {
+        Theme::from_raw(self.theme.into())
+    }
+
+    /// The players in the run, in slot order.
+    pub fn players<S: MemorySource + ?Sized>(&self, src: &S) -> Result<Vec<Player>, MemError> {
+        match self.items.read(src)? {
+            Some(items) => items.players(src),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Each of the four player slots, `None` where the slot is empty.
+    pub fn player_slots<S: MemorySource + ?Sized>(
+        &self,
+        src: &S,
+    ) -> Result<Vec<Option<Player>>, MemError> {
+        match self.items.read(src)? {
+            Some(items) => items.player_slots(src),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+mem_struct! {
+    pub struct Items {
+        pub players: [Pointer<Player>; 4] = 0x08,
+    }
+}
+
+impl Items {
+    /// The players in occupied slots.
+    pub fn players<S: MemorySource + ?Sized>(&self, src: &S) -> Result<Vec<Player>, MemError> {
+        Ok(self.player_slots(src)?.into_iter().flatten().collect())
+    }
+
+    /// Each of the four player slots, `None` where the slot is empty.
+    pub fn player_slots<S: MemorySource + ?Sized>(
+        &self,
+        src: &S,
+    ) -> Result<Vec<Option<Player>>, MemError> {
+        self.players
+            .iter()
+            .map(|pointer| pointer.read(src))
+            .collect()
+    }
+}
+
+mem_struct! {
+    /// The type of an entity.
+    pub struct EntityDb {
+        pub id: u32 = 0x14,
+    }
+}
+
+mem_struct! {
+    /// The fields every entity has.
+    pub struct Entity {
+        pub entity_type: Pointer<EntityDb> = 0x08,
+        /// What the entity is standing on or riding.
+        pub overlay: Pointer<Entity> = 0x10,
+        /// The uids of the entities it holds, wears or is affected by.
+        pub items: Vector<u32> = 0x18,
+        pub uid: u32 = 0x38,
+        pub layer: u8 = 0xA0,
+    }
+}
+
+impl Entity {
+    /// The id of the entity's type, if it has one.
+    pub fn type_id<S: MemorySource + ?Sized>(&self, src: &S) -> Result<Option<u32>, MemError> {
+        Ok(self
+            .entity_type
+            .read(src)?
+            .map(|entity_type| entity_type.id))
+    }
+}
+
+mem_struct! {
+    pub struct Mount {
+        pub entity_type: Pointer<EntityDb> = 0x08,
+        pub uid: u32 = 0x38,
+        pub is_tamed: bool = 0x151,
+    }
+}
+
+mem_struct! {
+    pub struct Player {
+        pub entity_type: Pointer<EntityDb> = 0x08,
+        /// What the player is standing on or riding.
+        pub overlay: Pointer<Entity> = 0x10,
+        /// The uids of the entities the player holds, wears or is affected by.
+        pub items: Vector<u32> = 0x18,
+        pub uid: u32 = 0x38,
+        pub x: f32 = 0x40,
+        pub y: f32 = 0x44,
+        /// 0 for the front layer, 1 for the back.
+        pub layer: u8 = 0xA0,
+        pub state: CharState = 0x114,
+        pub last_state: CharState = 0x115,
+        pub health: i8 = 0x117,
+        pub inventory: Pointer<Inventory> = 0x140,
+        /// The uid of the first hired hand following the player, or 0.
+        pub linked_companion_child: i32 = 0x150,
+    }
+}
+
+mem_struct! {
+    pub struct Inventory {
+        pub money: u32 = 0x00,
+        pub bombs: u8 = 0x04,
+        pub ropes: u8 = 0x05,
+        pub kills_level: u32 = 0x1424,
+        pub kills_total: u32 = 0x1428,
+        pub collected_money_total: u32 = 0x1520,
+    }
+}
+
+/// The game's table of entities by uid, a Robin Hood hash table of 16-byte entries.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UidEntityMap {
+    pub mask: u64,
+    pub table: u64,
+}
+
+impl MemType for UidEntityMap {
+    const SIZE: usize = 16;
+
+    fn read<S: MemorySource + ?Sized>(src: &S, addr: u64) -> Result<Self, MemError> {
+        Ok(Self {
+            mask: u64::read(src, addr)?,
+            table: u64::read(src, field_addr(addr, 8, 8)?)?,
+        })
+    }
+}
+
+impl UidEntityMap {
+    const ENTRY_SIZE: usize = 16;
+
+    /// The entity with `uid`, or `None` if it doesn't exist.
+    pub fn get<S: MemorySource + ?Sized>(
+        &self,
+        src: &S,
+        uid: u32,
+    ) -> Result<Option<Entity>, MemError> {
+        let Some(addr) = self.entity_addr(src, uid)? else {
+            return Ok(None);
+        };
+        let entity = Entity::read(src, addr)?;
+        Ok((entity.uid == uid).then_some(entity))
+    }
+
+    /// Where the entity with `uid` is, if it's in the table.
+    pub fn entity_addr<S: MemorySource + ?Sized>(
+        &self,
+        src: &S,
+        uid: u32,
+    ) -> Result<Option<u64>, MemError> {
+        // u32::MAX is the game's "no entity" uid
+        if self.table == 0 || self.mask == 0 || uid == u32::MAX {
+            return Ok(None);
+        }
+        let key = lowbias32(uid.wrapping_add(1));
+        let mut index = u64::from(key) & self.mask;
+        // No probe is longer than the table
+        for _ in 0..=self.mask {
+            let offset =
+                index
+                    .checked_mul(Self::ENTRY_SIZE as u64)
+                    .ok_or(MemError::Unreadable {
+                        addr: self.table,
+                        len: Self::ENTRY_SIZE,
+                    })?;
+            let entry = field_addr(self.table, offset, Self::ENTRY_SIZE)?;
+            let hashed_key = u32::read(src, entry)?;
+            if hashed_key == key {
+                return u64::read(src, field_addr(entry, 8, 8)?).map(Some);
+            }
+            if hashed_key == 0 {
+                return Ok(None);
+            }
+            // Entries are ordered by how far they are from where they hash to, so passing one
+            // closer to its place than the uid would be means the uid isn't in the table.
+            let distance = index.wrapping_sub(key.into()) & self.mask;
+            let entry_distance = index.wrapping_sub(hashed_key.into()) & self.mask;
+            if distance > entry_distance {
+                return Ok(None);
+            }
+            index = (index + 1) & self.mask;
+        }
+        Ok(None)
+    }
+}
+
+/// The game's hash for uids, from <https://github.com/skeeto/hash-prospector>.
+fn lowbias32(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7FEB_352D);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846C_A68B);
+    x ^= x >> 16;
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        lowbias32, CharState, Entity, EntityDb, Inventory, Items, LoadingState, Mount, Player,
+        Screen, State, Theme, WinState,
+    };
+    use crate::{MemError, MemStruct, MemType, MemoryDump, Pointer, Snapshot};
+
+    const STATE: u64 = 0x1_0000;
+    const ITEMS: u64 = 0x2_0000;
+    const PLAYER: u64 = 0x3_0000;
+    const INVENTORY: u64 = 0x4_0000;
+    const ITEM_UIDS: u64 = 0x6_0000;
+    const UID_TABLE: u64 = 0x7_0000;
+    const ENTITIES: u64 = 0x8_0000;
+    const ENTITY_DB: u64 = 0x9_0000;
+
+    fn put(bytes: &mut [u8], offset: usize, value: &[u8]) {
+        bytes[offset..offset + value.len()].copy_from_slice(value);
+    }
+
+    fn sample() -> MemoryDump {
+        let mut state = vec![0; 0x1358];
+        put(&mut state, 0x08, &11i32.to_le_bytes());
+        put(&mut state, 0x0C, &12i32.to_le_bytes());
+        put(&mut state, 0x10, &13i32.to_le_bytes());
+        put(&mut state, 0x30, &[1, 1]);
+        put(&mut state, 0x64, &1234u32.to_le_bytes());
+        put(&mut state, 0x68, &[2, 2, 3, 4]);
+        put(&mut state, 0x74, &[2, 3, 0]);
+        put(&mut state, 0x8C + 4, &591u32.to_le_bytes());
+        put(&mut state, 0xA34, &0x401u32.to_le_bytes());
+        put(&mut state, 0xA44, &600u32.to_le_bytes());
+        put(&mut state, 0x12F0, &ITEMS.to_le_bytes());
+        put(&mut state, 0x1348, &0xFFu64.to_le_bytes());
+        put(&mut state, 0x1350, &UID_TABLE.to_le_bytes());
+
+        let mut items = vec![0; 0x28];
+        put(&mut items, 0x10, &PLAYER.to_le_bytes());
+
+        let mut player = vec![0; 0x154];
+        put(&mut player, 0x10, &ENTITIES.to_le_bytes());
+        put(&mut player, 0x20, &ITEM_UIDS.to_le_bytes());
+        put(&mut player, 0x2C, &2u32.to_le_bytes());
+        put(&mut player, 0x38, &42u32.to_le_bytes());
+        put(&mut player, 0x40, &1.5f32.to_le_bytes());
+        put(&mut player, 0x114, &[12, 1, 0, 4]);
+        put(&mut player, 0x140, &INVENTORY.to_le_bytes());
+        put(&mut player, 0x150, &(-1i32).to_le_bytes());
+
+        let mut item_uids = vec![0; 8];
+        put(&mut item_uids, 0, &43u32.to_le_bytes());
+        put(&mut item_uids, 4, &44u32.to_le_bytes());
+
+        // Each entry is where the uid's hash, e.g. 0xcc678805 for 43, puts it in the table
+        let mut uid_table = vec![0; 0x100 * 16];
+        for (index, key, entity) in [
+            (0x05, 0xcc67_8805u32, 0),
+            (0xF7, 0x85a4_43f7, 0x100),
+            // Points at 43's entity, as if 45 was reused
+            (0x9F, 0xda24_bc9f, 0),
+        ] {
+            put(&mut uid_table, index * 16, &key.to_le_bytes());
+            put(
+                &mut uid_table,
+                index * 16 + 8,
+                &(ENTITIES + entity).to_le_bytes(),
+            );
+        }
+
+        // A tamed mount the player is riding, and an item without a type
+        let mut entities = vec![0; 0x200];
+        put(&mut entities, 0x08, &ENTITY_DB.to_le_bytes());
+        put(&mut entities, 0x38, &43u32.to_le_bytes());
+        put(&mut entities, 0x151, &[1]);
+        put(&mut entities, 0x138, &44u32.to_le_bytes());
+
+        let mut entity_db = vec![0; 0x18];
+        put(&mut entity_db, 0x14, &900u32.to_le_bytes());
+
+        let mut inventory = vec![0; 0x1524];
+        put(&mut inventory, 0, &2500u32.to_le_bytes());
+        put(&mut inventory, 4, &[3, 5]);
+        put(&mut inventory, 0x1428, &7u32.to_le_bytes());
+        put(&mut inventory, 0x1520, &3000u32.to_le_bytes());
+
+        MemoryDump {
+            regions: vec![
+                Snapshot::new(STATE, state),
+                Snapshot::new(ITEMS, items),
+                Snapshot::new(PLAYER, player),
+                Snapshot::new(INVENTORY, inventory),
+                Snapshot::new(ITEM_UIDS, item_uids),
+                Snapshot::new(UID_TABLE, uid_table),
+                Snapshot::new(ENTITIES, entities),
+                Snapshot::new(ENTITY_DB, entity_db),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_state() {
+        let dump = sample();
+        let state = State::read(&dump, STATE).unwrap();
+        assert_eq!(state.screen_last, Screen::Camp);
+        assert_eq!(state.screen, Screen::Level);
+        assert_eq!(state.screen_next, Screen::LevelTransition);
+        assert!(state.ingame && state.playing);
+        assert_eq!(state.time_total, 1234);
+        assert_eq!((state.world, state.level), (2, 3));
+        assert_eq!(state.theme(), Some(Theme::Jungle));
+        assert_eq!(state.win_state, WinState::NoWin);
+        assert_eq!(state.loading, LoadingState::NotLoading);
+        assert_eq!(state.waddler_storage.0.len(), 99);
+        assert_eq!(state.waddler_storage.0[..3], [0, 591, 0]);
+        assert_eq!(
+            state.run_recap_flags,
+            State::RECAP_PACIFIST | State::RECAP_NO_GOLD
+        );
+        assert_eq!(state.time_level, 600);
+
+        let slots = state.player_slots(&dump).unwrap();
+        assert_eq!(
+            slots.iter().map(Option::is_some).collect::<Vec<_>>(),
+            [false, true, false, false]
+        );
+
+        let players = state.players(&dump).unwrap();
+        assert_eq!(players.len(), 1);
+        let player: &Player = &players[0];
+        assert_eq!((player.uid, player.x, player.health), (42, 1.5, 4));
+        assert!(player.entity_type.is_null());
+        assert_eq!(
+            (player.state, player.last_state),
+            (CharState::Attacking, CharState::Standing)
+        );
+        assert_eq!(player.linked_companion_child, -1);
+        assert_eq!(
+            player.inventory.read(&dump).unwrap(),
+            Some(Inventory {
+                money: 2500,
+                bombs: 3,
+                ropes: 5,
+                collected_money_total: 3000,
+                kills_level: 0,
+                kills_total: 7,
+            })
+        );
+    }
+
+    #[test]
+    fn test_lowbias32() {
+        // Values from the Python tracker's `_lowbias32`
+        assert_eq!(lowbias32(1), 0x6889_90c0);
+        assert_eq!(lowbias32(100), 0x4891_52b6);
+    }
+
+    #[test]
+    fn test_entities() {
+        let dump = sample();
+        let state = State::read(&dump, STATE).unwrap();
+        let player = state.players(&dump).unwrap().remove(0);
+        let uids = state.instance_id_to_pointer;
+
+        assert_eq!(player.items.read(&dump).unwrap(), [43, 44]);
+        let mount: Entity = player.overlay.read(&dump).unwrap().unwrap();
+        assert_eq!(mount, uids.get(&dump, 43).unwrap().unwrap());
+        assert_eq!(mount.type_id(&dump).unwrap(), Some(900));
+        assert!(Mount::read(&dump, player.overlay.addr).unwrap().is_tamed);
+
+        let item = uids.get(&dump, 44).unwrap().unwrap();
+        assert_eq!(item.uid, 44);
+        assert_eq!(item.type_id(&dump).unwrap(), None);
+
+        // 45's entry points at an entity with another uid
+        assert_eq!(uids.get(&dump, 45).unwrap(), None);
+        // Nothing hashes to 99's entry
+        assert_eq!(uids.get(&dump, 99).unwrap(), None);
+        assert_eq!(uids.get(&dump, u32::MAX).unwrap(), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut dump = sample();
+        dump.regions[0].bytes[0x0C] = 99;
+        let err = State::read(&dump, STATE).unwrap_err();
+        assert!(matches!(
+            err,
+            MemError::InvalidValue {
+                type_name: "Screen",
+                value: 99
+            }
+        ));
+
+        let dangling = Pointer::<Inventory>::new(0x5_0000);
+        assert!(matches!(
+            dangling.read(&dump),
+            Err(MemError::Unreadable { addr: 0x5_0000, .. })
+        ));
+
+        // Field offsets past the end of the address space are unreadable rather than overflowing
+        let garbage = Pointer::<Inventory>::new(u64::MAX - 2);
+        assert!(matches!(
+            garbage.read(&dump),
+            Err(MemError::Unreadable { .. })
+        ));
+        assert!(matches!(
+            <[u32; 4]>::read(&dump, u64::MAX - 8),
+            Err(MemError::Unreadable { .. })
+        ));
+    }
+
+    /// Fields only decoded here, which the Python layouts don't have.
+    const NOT_IN_PYTHON: &[&str] = &["State.ingame", "State.playing", "State.pause", "State.seed"];
+
+    /// Python field names to their offset and type, e.g. `sc_uint8`.
+    type PythonFields = HashMap<String, (u64, String)>;
+
+    /// A class's `struct_field`s in a Python layout, with those of its base classes.
+    fn python_fields(source: &str, class: &str) -> PythonFields {
+        let mut classes: HashMap<&str, (Option<&str>, PythonFields)> = HashMap::new();
+        let mut current = None;
+        for (index, line) in source.lines().enumerate() {
+            if let Some(header) = line.strip_prefix("class ") {
+                let header = header.trim_end_matches(':');
+                let (name, base) = match header.split_once('(') {
+                    Some((name, base)) => (name, Some(base.trim_end_matches(')'))),
+                    None => (header, None),
+                };
+                classes.insert(name, (base, HashMap::new()));
+                current = Some(name);
+                continue;
+            }
+            let (Some(class), Some((field, _))) = (current, line.split_once(':')) else {
+                continue;
+            };
+            if !line.contains("= struct_field(") {
+                continue;
+            }
+            // The arguments may continue on the next lines
+            let rest: String = source
+                .lines()
+                .skip(index)
+                .take(3)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let args = &rest[rest.find("struct_field(").unwrap() + "struct_field(".len()..];
+            let mut args = args.split(',').map(str::trim);
+            let offset = args.next().unwrap();
+            let offset = u64::from_str_radix(offset.trim_start_matches("0x"), 16).unwrap();
+            let ty = args.next().unwrap();
+            let ty = ty
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .next()
+                .unwrap()
+                .to_string();
+            let fields = &mut classes.get_mut(class).unwrap().1;
+            fields.insert(field.trim().to_string(), (offset, ty));
+        }
+
+        let mut fields = HashMap::new();
+        let mut class = Some(class);
+        while let Some(name) = class {
+            let Some((base, own)) = classes.get(name) else {
+                break;
+            };
+            for (field, value) in own {
+                fields.entry(field.clone()).or_insert_with(|| value.clone());
+            }
+            class = *base;
+        }
+        fields
+    }
+
+    fn check(
+        struct_name: &str,
+        fields: &[(&str, &str, u64)],
+        python: &PythonFields,
+        renames: &[(&str, &str)],
+    ) {
+        assert!(!python.is_empty(), "{struct_name} not found");
+        for (field, ty, offset) in fields {
+            if NOT_IN_PYTHON.contains(&format!("{struct_name}.{field}").as_str()) {
+                continue;
+            }
+            let python_name = renames
+                .iter()
+                .find(|(rust, _)| rust == field)
+                .map_or(*field, |(_, python)| python);
+            let (python_offset, python_ty) = python
+                .get(python_name)
+                .unwrap_or_else(|| panic!("{struct_name}.{field} isn't in the Python layout"));
+            assert_eq!(
+                offset, python_offset,
+                "{struct_name}.{field} is at {offset:#x} but {python_offset:#x} in Python"
+            );
+            let scalar = match *ty {
+                "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "bool" => format!("sc_{ty}")
+                    .replace("sc_u", "sc_uint")
+                    .replace("sc_i", "sc_int"),
+                "f32" => "sc_float".to_string(),
+                _ => continue,
+            };
+            assert_eq!(
+                &scalar, python_ty,
+                "{struct_name}.{field} is {ty} but {python_ty} in Python"
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_python() {
+        let mem = concat!(env!("CARGO_MANIFEST_DIR"), "/../../modlunky2/mem");
+        let read = |file: &str| std::fs::read_to_string(format!("{mem}/{file}")).unwrap();
+        let state = read("state.py");
+        let entities = read("entities.py");
+
+        check("State", State::FIELDS, &python_fields(&state, "State"), &[]);
+        check("Items", Items::FIELDS, &python_fields(&state, "Items"), &[]);
+        check(
+            "Entity",
+            Entity::FIELDS,
+            &python_fields(&entities, "Entity"),
+            &[("entity_type", "type")],
+        );
+        check(
+            "Mount",
+            Mount::FIELDS,
+            &python_fields(&entities, "Mount"),
+            &[("entity_type", "type")],
+        );
+        check(
+            "Player",
+            Player::FIELDS,
+            &python_fields(&entities, "Player"),
+            &[
+                ("entity_type", "type"),
+                ("x", "position_x"),
+                ("y", "position_y"),
+            ],
+        );
+        check(
+            "Inventory",
+            Inventory::FIELDS,
+            &python_fields(&entities, "Inventory"),
+            &[],
+        );
+        check(
+            "EntityDb",
+            EntityDb::FIELDS,
+            &python_fields(&entities, "EntityDBEntry"),
+            &[],
+        );
+    }
+}
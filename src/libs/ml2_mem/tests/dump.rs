@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use ml2_mem::{EntityDb, Inventory, MemStruct, MemoryDump, Screen, State, Theme, WinState};
+
+const STATE: u64 = 0x1_4a3c_0000;
+
+fn load_fixture() -> MemoryDump {
+    let dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "data", "ocean_dump"]
+        .iter()
+        .collect();
+    MemoryDump::load_dir(&dir).unwrap()
+}
+
+#[test]
+fn test_load_dir() {
+    let dump = load_fixture();
+    // README.md is skipped
+    assert_eq!(dump.regions.len(), 7);
+    assert!(dump.regions.windows(2).all(|w| w[0].base < w[1].base));
+    assert_eq!(dump.regions[1].base, STATE);
+}
+
+#[test]
+fn test_state() {
+    let dump = load_fixture();
+    let state = State::read(&dump, STATE).unwrap();
+    assert_eq!(state.screen_last, Screen::LevelTransition);
+    assert_eq!(state.screen, Screen::Level);
+    assert_eq!(state.screen_next, Screen::LevelTransition);
+    assert!(state.ingame && state.playing);
+    assert_eq!(state.pause, 0);
+    assert_eq!(state.seed, 0x5f3e_91c2);
+    assert_eq!(state.time_total, 649_234);
+    assert_eq!((state.world_start, state.level_start), (1, 1));
+    assert_eq!((state.world, state.level), (7, 5));
+    assert_eq!(state.theme(), Some(Theme::CosmicOcean));
+    assert_eq!(state.win_state, WinState::NoWin);
+
+    let items = state.items.read(&dump).unwrap().unwrap();
+    assert_eq!(
+        items.players.map(|p| p.addr),
+        [0x2_92b0_8c40, 0, 0x2_92b0_9e80, 0]
+    );
+}
+
+#[test]
+fn test_players() {
+    let dump = load_fixture();
+    let state = State::read(&dump, STATE).unwrap();
+    let players = state.players(&dump).unwrap();
+    assert_eq!(players.len(), 2);
+
+    let ana = &players[0];
+    assert_eq!((ana.uid, ana.x, ana.y), (117, 21.5, 86.0));
+    assert_eq!((ana.layer, ana.health), (0, 3));
+    let entity: EntityDb = ana.entity_type.read(&dump).unwrap().unwrap();
+    assert_eq!(entity.id, 194);
+    assert_eq!(
+        ana.inventory.read(&dump).unwrap(),
+        Some(Inventory {
+            money: 48200,
+            bombs: 7,
+            ropes: 2,
+            collected_money_total: 91500,
+            kills_level: 4,
+            kills_total: 131,
+        })
+    );
+
+    let second = &players[1];
+    assert_eq!((second.uid, second.health), (118, 1));
+    assert!(second.entity_type.is_null());
+    let inventory = second.inventory.read(&dump).unwrap().unwrap();
+    assert_eq!(
+        (inventory.money, inventory.bombs, inventory.ropes),
+        (150, 0, 1)
+    );
+    assert_eq!(inventory.kills_total, 12);
+}
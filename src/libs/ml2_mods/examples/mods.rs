@@ -62,6 +62,10 @@ enum Commands {
     InstallRemote { code: String },
     UpdateLocal { source: String, id: String },
     UpdateRemote { code: String },
+    Enable { id: String },
+    Disable { id: String },
+    LoadOrder {},
+    SetLoadOrder { ids: Vec<String> },
     Run {},
 }
 
@@ -178,6 +182,18 @@ async fn run(
             let package = ModSource::Remote { code };
             println!("{:#?}", manager.update(&package).await?);
         }
+        Commands::Enable { id } => {
+            println!("{:#?}", manager.enable(&id).await?);
+        }
+        Commands::Disable { id } => {
+            println!("{:#?}", manager.disable(&id).await?);
+        }
+        Commands::LoadOrder {} => {
+            println!("{:#?}", manager.load_order().await?);
+        }
+        Commands::SetLoadOrder { ids } => {
+            println!("{:#?}", manager.set_load_order(&ids).await?);
+        }
         Commands::Run {} => loop {
             select! {
                 _ = subsystem.on_shutdown_requested() => break,
@@ -6,6 +6,11 @@ use crate::manager::Error;
 pub struct Mod {
     pub id: String,
     pub manifest: Option<Manifest>,
+    /// Whether Playlunky loads the mod. Disabled mods are moved out of the packs directory, or
+    /// marked with `--` in `load_order.txt` by Playlunky itself.
+    pub enabled: bool,
+    /// Where the mod is in Playlunky's load order, starting from 0. `None` for disabled mods.
+    pub load_order: Option<usize>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -59,6 +64,29 @@ pub enum Change {
     Remove { id: String },
     Update { progress: ModProgress },
     NewVersion { id: String },
+    SetEnabled { id: String, enabled: bool },
+    LoadOrder { ids: Vec<String> },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupReason {
+    Install,
+    Update,
+    Remove,
+    Restore,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveBackup {
+    pub id: String,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+    pub reason: BackupReason,
+    /// The mod whose operation triggered the backup.
+    pub mod_id: String,
+    /// Paths relative to the install directory and separated by `/`, e.g.
+    /// `Mods/Packs/foo/save.dat`.
+    pub files: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, thiserror::Error)]
@@ -70,6 +98,8 @@ pub enum ManagerError {
     #[error("{0}")]
     ModNonDirectoryError(String),
     #[error("{0}")]
+    BackupNotFoundError(String),
+    #[error("{0}")]
     ManifestParseError(String),
     #[error("{0}")]
     SourceError(String),
@@ -89,6 +119,9 @@ impl From<Error> for ManagerError {
             Error::ModNonDirectoryError(_) => {
                 ManagerError::ModNonDirectoryError(format!("{original}"))
             }
+            Error::BackupNotFoundError(_) => {
+                ManagerError::BackupNotFoundError(format!("{original}"))
+            }
             Error::ManifestParseError(_) => ManagerError::ManifestParseError(format!("{original}")),
             Error::SourceError(_) => ManagerError::SourceError(format!("{original}")),
             Error::DestinationError(_) => ManagerError::DestinationError(format!("{original}")),
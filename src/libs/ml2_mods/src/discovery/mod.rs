@@ -0,0 +1,211 @@
+//! Finds Spelunky 2 installs from Steam's library metadata.
+//!
+//! Each Steam root lists its libraries in `steamapps/libraryfolders.vdf`, and each library has
+//! an `appmanifest_<app id>.acf` for every game installed in it. On Linux the game runs under
+//! Proton, whose Wine prefix is in the library's `compatdata`.
+
+pub mod vdf;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use vdf::{Vdf, VdfError};
+
+pub const SPELUNKY2_APP_ID: &str = "418530";
+
+const STEAMAPPS_SUBPATH: &str = "steamapps";
+const LIBRARY_FOLDERS_FILENAME: &str = "libraryfolders.vdf";
+const COMMON_SUBPATH: &str = "common";
+const COMPATDATA_SUBPATH: &str = "compatdata";
+/// The app manifest's `StateFlags` bit for a complete install.
+const STATE_FULLY_INSTALLED: u64 = 4;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("I/O error")]
+    IoError(#[from] io::Error),
+    #[error("Problem parsing {path:?}")]
+    VdfError {
+        path: PathBuf,
+        #[source]
+        source: VdfError,
+    },
+}
+
+pub type Result<R> = std::result::Result<R, Error>;
+
+/// A game install found in a Steam library.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Install {
+    /// The game's directory, to use as the install directory.
+    pub path: PathBuf,
+    /// The Steam library it's in.
+    pub library: PathBuf,
+    pub build_id: Option<u64>,
+    /// Seconds since the Unix epoch.
+    pub last_updated: Option<u64>,
+    /// False while Steam is downloading or updating the game.
+    pub fully_installed: bool,
+    /// The Proton Wine prefix, if the game has been run under Proton.
+    pub proton_prefix: Option<PathBuf>,
+}
+
+/// Where Steam usually lives on this platform.
+pub fn default_steam_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    if cfg!(windows) {
+        for var in ["ProgramFiles(x86)", "ProgramFiles"] {
+            if let Some(dir) = env::var_os(var) {
+                roots.push(PathBuf::from(dir).join("Steam"));
+            }
+        }
+    } else if let Some(home) = env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        roots.push(home.join(".steam").join("steam"));
+        roots.push(home.join(".local").join("share").join("Steam"));
+        // Flatpak
+        roots.push(
+            home.join(".var")
+                .join("app")
+                .join("com.valvesoftware.Steam")
+                .join(".local")
+                .join("share")
+                .join("Steam"),
+        );
+    }
+    roots
+}
+
+/// Finds installs in the libraries of every Steam root, skipping any that can't be read.
+///
+/// Roots are often symlinks to each other, so installs are only listed once.
+pub fn find_installs(steam_roots: &[PathBuf]) -> Vec<Install> {
+    let mut installs: Vec<Install> = Vec::new();
+    for root in steam_roots {
+        let libraries = match library_folders(root) {
+            Ok(libraries) => libraries,
+            Err(e) => {
+                warn!("Skipping Steam root {:?}: {:?}", root, e);
+                continue;
+            }
+        };
+        for library in libraries {
+            match find_install(&library) {
+                Ok(Some(install)) => {
+                    let path = canonical(&install.path);
+                    if !installs.iter().any(|i| canonical(&i.path) == path) {
+                        installs.push(install);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Skipping Steam library {:?}: {:?}", library, e),
+            }
+        }
+    }
+    installs
+}
+
+/// The install to use without asking, if there's exactly one that's fully installed.
+///
+/// With several, or only ones Steam is still downloading, the user should pick.
+pub fn auto_select(installs: &[Install]) -> Option<&Install> {
+    let mut complete = installs.iter().filter(|install| install.fully_installed);
+    match (complete.next(), complete.next()) {
+        (Some(install), None) => Some(install),
+        _ => None,
+    }
+}
+
+/// Libraries listed by a Steam root, starting with the root itself.
+pub fn library_folders(steam_root: &Path) -> Result<Vec<PathBuf>> {
+    let path = steam_root
+        .join(STEAMAPPS_SUBPATH)
+        .join(LIBRARY_FOLDERS_FILENAME);
+    let mut libraries = Vec::new();
+    if fs::metadata(steam_root.join(STEAMAPPS_SUBPATH)).is_ok() {
+        libraries.push(steam_root.to_path_buf());
+    }
+    let Some(vdf) = read_vdf(&path)? else {
+        return Ok(libraries);
+    };
+    let Some(folders) = vdf.get("libraryfolders") else {
+        return Ok(libraries);
+    };
+    for (key, value) in folders.entries() {
+        // Other keys are settings, like TimeNextStatsReport
+        if key.parse::<u32>().is_err() {
+            continue;
+        }
+        // Older versions had the path as the value instead of a table
+        let library = match value {
+            Vdf::String(path) => Some(path.as_str()),
+            Vdf::Table(_) => value.get_str("path"),
+        };
+        if let Some(library) = library {
+            let library = PathBuf::from(library);
+            if !libraries.contains(&library) {
+                libraries.push(library);
+            }
+        }
+    }
+    Ok(libraries)
+}
+
+/// The install in a library, if it has an app manifest for the game.
+pub fn find_install(library: &Path) -> Result<Option<Install>> {
+    let steamapps = library.join(STEAMAPPS_SUBPATH);
+    let manifest_path = steamapps.join(format!("appmanifest_{SPELUNKY2_APP_ID}.acf"));
+    let Some(vdf) = read_vdf(&manifest_path)? else {
+        return Ok(None);
+    };
+    let Some(state) = vdf.get("AppState") else {
+        debug!("No AppState in {:?}", manifest_path);
+        return Ok(None);
+    };
+    let install_dir = state.get_str("installdir").unwrap_or("Spelunky 2");
+    let path = steamapps.join(COMMON_SUBPATH).join(install_dir);
+    if fs::metadata(&path).is_err() {
+        debug!("Install {:?} doesn't exist", path);
+        return Ok(None);
+    }
+
+    let number = |key| state.get_str(key).and_then(|v| v.parse::<u64>().ok());
+    let proton_prefix = steamapps
+        .join(COMPATDATA_SUBPATH)
+        .join(SPELUNKY2_APP_ID)
+        .join("pfx");
+    Ok(Some(Install {
+        path,
+        library: library.to_path_buf(),
+        build_id: number("buildid"),
+        last_updated: number("LastUpdated"),
+        fully_installed: number("StateFlags").is_some_and(|f| f & STATE_FULLY_INSTALLED != 0),
+        proton_prefix: fs::metadata(&proton_prefix)
+            .is_ok()
+            .then_some(proton_prefix),
+    }))
+}
+
+fn read_vdf(path: &Path) -> Result<Option<Vdf>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Vdf::parse(&text)
+        .map(Some)
+        .map_err(|source| Error::VdfError {
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
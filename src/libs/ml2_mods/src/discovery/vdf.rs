@@ -0,0 +1,208 @@
+//! A parser for Valve's text KeyValues format, used by Steam's `.vdf` and `.acf` files.
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+#[error("Syntax error on line {line}: {reason}")]
+pub struct VdfError {
+    pub line: usize,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Vdf {
+    String(String),
+    Table(Vec<(String, Vdf)>),
+}
+
+impl Vdf {
+    /// Parses a document, which is a table of its top-level keys.
+    pub fn parse(text: &str) -> Result<Self, VdfError> {
+        let mut parser = Parser {
+            chars: text.chars().peekable(),
+            line: 1,
+        };
+        let table = parser.table()?;
+        match parser.token()? {
+            None => Ok(Vdf::Table(table)),
+            Some(_) => Err(parser.error("unexpected '}'")),
+        }
+    }
+
+    /// Looks a key up in a table. Steam isn't consistent about case, so it's ignored.
+    pub fn get(&self, key: &str) -> Option<&Vdf> {
+        self.entries()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Vdf::String(s) => Some(s),
+            Vdf::Table(_) => None,
+        }
+    }
+
+    /// A table's entries, or nothing for a string.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &Vdf)> {
+        let entries: &[(String, Vdf)] = match self {
+            Vdf::String(_) => &[],
+            Vdf::Table(entries) => entries,
+        };
+        entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+}
+
+enum Token {
+    String(String),
+    Open,
+    Close,
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &str) -> VdfError {
+        VdfError {
+            line: self.line,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Entries up to a `}` or the end of the input, which the caller checks for.
+    fn table(&mut self) -> Result<Vec<(String, Vdf)>, VdfError> {
+        let mut entries = Vec::new();
+        loop {
+            self.skip_space();
+            if matches!(self.chars.peek(), None | Some('}')) {
+                return Ok(entries);
+            }
+            let key = match self.token()? {
+                Some(Token::String(key)) => key,
+                _ => return Err(self.error("expected a key")),
+            };
+            let value = match self.token()? {
+                Some(Token::String(value)) => Vdf::String(value),
+                Some(Token::Open) => {
+                    let table = self.table()?;
+                    match self.token()? {
+                        Some(Token::Close) => Vdf::Table(table),
+                        _ => return Err(self.error("expected '}'")),
+                    }
+                }
+                _ => return Err(self.error(&format!("expected a value for {key:?}"))),
+            };
+            entries.push((key, value));
+        }
+    }
+
+    fn token(&mut self) -> Result<Option<Token>, VdfError> {
+        self.skip_space();
+        let Some(c) = self.chars.next() else {
+            return Ok(None);
+        };
+        let token = match c {
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match self.chars.next() {
+                        None => return Err(self.error("unterminated string")),
+                        Some('"') => break,
+                        Some('\\') => match self.chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(c) => s.push(c),
+                            None => return Err(self.error("unterminated string")),
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                self.line += 1;
+                            }
+                            s.push(c);
+                        }
+                    }
+                }
+                Token::String(s)
+            }
+            c => {
+                // Unquoted strings end at whitespace or a brace
+                let mut s = c.to_string();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_whitespace() || c == '{' || c == '}' || c == '"' {
+                        break;
+                    }
+                    s.push(c);
+                    self.chars.next();
+                }
+                Token::String(s)
+            }
+        };
+        Ok(Some(token))
+    }
+
+    /// Skips whitespace and `//` comments.
+    fn skip_space(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '\n' {
+                self.line += 1;
+            }
+            if c.is_whitespace() {
+                self.chars.next();
+            } else if c == '/' {
+                let mut ahead = self.chars.clone();
+                ahead.next();
+                if ahead.peek() != Some(&'/') {
+                    return;
+                }
+                while self.chars.next_if(|&c| c != '\n').is_some() {}
+            } else {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Vdf;
+
+    #[test]
+    fn test_parse() {
+        let vdf = Vdf::parse(
+            r#"
+            // A comment
+            "libraryfolders"
+            {
+                "0"
+                {
+                    "path"      "C:\\Program Files (x86)\\Steam"
+                    "label"     ""
+                    "apps" { "418530" "1234" }
+                }
+                unquoted value
+            }
+            "#,
+        )
+        .unwrap();
+        let folders = vdf.get("LibraryFolders").unwrap();
+        let first = folders.get("0").unwrap();
+        assert_eq!(first.get_str("path"), Some(r"C:\Program Files (x86)\Steam"));
+        assert_eq!(first.get_str("label"), Some(""));
+        assert_eq!(first.get("apps").unwrap().get_str("418530"), Some("1234"));
+        assert_eq!(folders.get_str("unquoted"), Some("value"));
+
+        let err = Vdf::parse("\"a\"\n{\n\"b\" \"c\"\n").unwrap_err();
+        assert_eq!(err.line, 4);
+        assert!(Vdf::parse("\"a\" }").is_err());
+        assert!(Vdf::parse("\"a\" \"b").is_err());
+    }
+}
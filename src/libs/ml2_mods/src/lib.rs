@@ -1,4 +1,5 @@
 pub mod data;
+pub mod discovery;
 pub mod local;
 pub mod manager;
 pub mod spelunkyfyi;
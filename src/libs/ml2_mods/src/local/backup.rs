@@ -0,0 +1,294 @@
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use tokio::fs;
+use tracing::{debug, instrument};
+
+use super::constants::{
+    BACKUP_METADATA_FILENAME, DISABLED_MODS_SUBPATH, GAME_SAVE_FILENAME, MODS_SUBPATH,
+    PACK_SAVE_FILENAME, SAVE_BACKUPS_SUBPATH,
+};
+use super::paths::{exists, locate_pack, resolve, split_stored, subpath};
+use super::{Error, Result};
+use crate::data::{BackupReason, SaveBackup};
+
+pub const DEFAULT_MAX_BACKUPS: usize = 20;
+
+/// Rotating snapshots of the game's save and each pack's save.dat.
+#[derive(Clone, Debug)]
+pub struct SaveBackups {
+    install_path: PathBuf,
+    max_backups: usize,
+}
+
+impl SaveBackups {
+    /// Keeps the newest `max_backups` snapshots. Zero disables backups.
+    pub fn new(install_path: impl Into<PathBuf>, max_backups: usize) -> Self {
+        Self {
+            install_path: install_path.into(),
+            max_backups,
+        }
+    }
+
+    async fn backups_dir(&self) -> Result<PathBuf> {
+        Ok(resolve(&self.install_path, SAVE_BACKUPS_SUBPATH).await?)
+    }
+
+    /// Where a file listed in a backup is in the install directory.
+    async fn install_file(&self, file: &str) -> Result<PathBuf> {
+        Ok(resolve(&self.install_path, &split_stored(file)).await?)
+    }
+
+    /// Save files that currently exist, relative to the install directory and separated by `/`
+    /// so that backups work on any platform.
+    async fn save_files(&self) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        if exists(&self.install_file(GAME_SAVE_FILENAME).await?).await? {
+            files.push(GAME_SAVE_FILENAME.to_string());
+        }
+        let mut pack_files = Vec::new();
+        for packs_subpath in [MODS_SUBPATH, DISABLED_MODS_SUBPATH] {
+            pack_files.extend(self.pack_save_files(packs_subpath).await?);
+        }
+        pack_files.sort();
+        files.extend(pack_files);
+        Ok(files)
+    }
+
+    /// Saves of the packs in one directory, e.g. the enabled or the disabled ones.
+    async fn pack_save_files(&self, packs_subpath: &[&str]) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        let packs_dir = resolve(&self.install_path, packs_subpath).await?;
+        let mut dir = match fs::read_dir(packs_dir).await {
+            Ok(d) => d,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(files),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = dir.next_entry().await? {
+            // Skip files like Playlunky's load order
+            if !fs::metadata(entry.path()).await?.is_dir() {
+                continue;
+            }
+            let Some(pack) = entry.file_name().to_str().map(str::to_string) else {
+                debug!("Skipping non-unicode pack {:?}", entry.path());
+                continue;
+            };
+            let file = [packs_subpath, &[&pack, PACK_SAVE_FILENAME]]
+                .concat()
+                .join("/");
+            if exists(&self.install_file(&file).await?).await? {
+                files.push(file);
+            }
+        }
+        Ok(files)
+    }
+
+    /// Where a file listed in a backup should be restored to. Pack saves go to wherever the
+    /// pack is now, enabled or not, and are `None` if it's gone.
+    async fn restore_dest(&self, file: &str) -> Result<Option<PathBuf>> {
+        let Some(pack) = stored_pack(file) else {
+            return Ok(Some(self.install_file(file).await?));
+        };
+        Ok(locate_pack(&self.install_path, pack)
+            .await?
+            .map(|(dir, _)| dir.join(PACK_SAVE_FILENAME)))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn backup(&self, reason: BackupReason, mod_id: &str) -> Result<Option<SaveBackup>> {
+        if self.max_backups == 0 {
+            return Ok(None);
+        }
+        let files = self.save_files().await?;
+        if files.is_empty() {
+            debug!("No saves to back up");
+            return Ok(None);
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| Error::UnknownError(e.into()))?;
+        // Ids start with a zero-padded millisecond timestamp so they sort by age, bumped past the
+        // newest backup's so that ones made in the same millisecond stay in order
+        let newest = self
+            .list()
+            .await?
+            .first()
+            .and_then(|b| b.id.split('-').next()?.parse::<u128>().ok());
+        let millis = match newest {
+            Some(newest) => now.as_millis().max(newest + 1),
+            None => now.as_millis(),
+        };
+        let reason_name = format!("{reason:?}").to_ascii_lowercase();
+        let id = format!("{millis:013}-{reason_name}");
+
+        let backup_dir = self.backups_dir().await?.join(&id);
+        debug!("Backing up {} saves to {:?}", files.len(), backup_dir);
+        for file in &files {
+            let dest = backup_dir.join(subpath(&split_stored(file)));
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            fs::copy(self.install_file(file).await?, dest).await?;
+        }
+
+        let backup = SaveBackup {
+            id,
+            created_at: now.as_secs(),
+            reason,
+            mod_id: mod_id.to_string(),
+            files,
+        };
+        let json = serde_json::to_string(&backup)?;
+        fs::write(backup_dir.join(BACKUP_METADATA_FILENAME), json).await?;
+
+        self.prune().await?;
+        Ok(Some(backup))
+    }
+
+    /// Backups, newest first.
+    #[instrument(skip(self))]
+    pub async fn list(&self) -> Result<Vec<SaveBackup>> {
+        let mut backups = Vec::new();
+        let mut dir = match fs::read_dir(self.backups_dir().await?).await {
+            Ok(d) => d,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(backups),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = dir.next_entry().await? {
+            if !fs::metadata(entry.path()).await?.is_dir() {
+                debug!("Skipping {:?}, which isn't a backup", entry.path());
+                continue;
+            }
+            let path = entry.path().join(BACKUP_METADATA_FILENAME);
+            match fs::read(&path).await {
+                Ok(json) => backups.push(serde_json::from_slice::<SaveBackup>(&json)?),
+                // Probably a backup that was interrupted
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    debug!("Skipping {:?} without metadata", entry.path())
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        backups.sort_by(|a, b| b.id.cmp(&a.id));
+        Ok(backups)
+    }
+
+    /// Copies a backup's files over the current saves, after backing those up.
+    ///
+    /// Saves of packs that have since been removed are skipped, and ones of packs that have been
+    /// enabled or disabled go to where the pack is now.
+    #[instrument(skip(self))]
+    pub async fn restore(&self, id: &str) -> Result<SaveBackup> {
+        let backup = self
+            .list()
+            .await?
+            .into_iter()
+            .find(|b| b.id == id)
+            .ok_or_else(|| Error::BackupNotFound(id.to_string()))?;
+
+        // Read everything first, since the backup below may rotate this one out
+        let backup_dir = self.backups_dir().await?.join(id);
+        let mut contents = Vec::with_capacity(backup.files.len());
+        for file in &backup.files {
+            if !is_relative_stored(file) {
+                return Err(Error::UnknownError(anyhow!(
+                    "Backup {id} has an invalid path {file:?}"
+                )));
+            }
+            let path = backup_dir.join(subpath(&split_stored(file)));
+            contents.push((file, fs::read(path).await?));
+        }
+
+        self.backup(BackupReason::Restore, &backup.mod_id).await?;
+        for (file, bytes) in contents {
+            let Some(dest) = self.restore_dest(file).await? else {
+                debug!("Skipping {:?}, whose pack is gone", file);
+                continue;
+            };
+            debug!("Restoring {:?}", file);
+            fs::write(dest, bytes).await?;
+        }
+        Ok(backup)
+    }
+
+    async fn prune(&self) -> Result<()> {
+        let backups_dir = self.backups_dir().await?;
+        for old in self.list().await?.iter().skip(self.max_backups) {
+            debug!("Removing old backup {}", old.id);
+            fs::remove_dir_all(backups_dir.join(&old.id)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// The pack whose save a path from a backup's metadata is, if it's one.
+fn stored_pack(file: &str) -> Option<&str> {
+    let components = split_stored(file);
+    let (save, rest) = components.split_last()?;
+    let (pack, dir) = rest.split_last()?;
+    let in_packs_dir = [MODS_SUBPATH, DISABLED_MODS_SUBPATH]
+        .iter()
+        .any(|packs_subpath| {
+            dir.len() == packs_subpath.len()
+                && dir
+                    .iter()
+                    .zip(*packs_subpath)
+                    .all(|(a, b)| a.eq_ignore_ascii_case(b))
+        });
+    (in_packs_dir && save.eq_ignore_ascii_case(PACK_SAVE_FILENAME)).then_some(*pack)
+}
+
+/// Whether a path from a backup's metadata stays inside the backup, i.e. has no `..`, drive
+/// prefix like `C:` or leading separator.
+fn is_relative_stored(file: &str) -> bool {
+    let components = split_stored(file);
+    // Empty components are dropped, so any missing came from a leading, trailing or doubled
+    // separator
+    components.len() == file.split(['/', '\\']).count()
+        && components
+            .iter()
+            .all(|c| !matches!(*c, "." | "..") && !c.contains(':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_relative_stored, stored_pack};
+
+    #[test]
+    fn test_stored_pack() {
+        assert_eq!(stored_pack("Mods/Packs/foo/save.dat"), Some("foo"));
+        assert_eq!(stored_pack("Mods\\Packs\\foo\\save.dat"), Some("foo"));
+        assert_eq!(
+            stored_pack("Mods/.ml/disabled-packs/foo.bar/save.dat"),
+            Some("foo.bar")
+        );
+        assert_eq!(stored_pack("savegame.sav"), None);
+        assert_eq!(stored_pack("Mods/Packs/save.dat"), None);
+        assert_eq!(stored_pack("Mods/Packs/foo/bar/save.dat"), None);
+        assert_eq!(stored_pack("Mods/Other/foo/save.dat"), None);
+    }
+
+    #[test]
+    fn test_is_relative_stored() {
+        assert!(is_relative_stored("savegame.sav"));
+        assert!(is_relative_stored("Mods/Packs/foo/save.dat"));
+        assert!(is_relative_stored("Mods\\Packs\\foo.bar\\save.dat"));
+        for file in [
+            "",
+            "../savegame.sav",
+            "Mods/../../savegame.sav",
+            "Mods/./save.dat",
+            "/etc/passwd",
+            "\\Windows\\win.ini",
+            "Mods//save.dat",
+            "Mods/",
+            "C:savegame.sav",
+            "C:\\savegame.sav",
+        ] {
+            assert!(!is_relative_stored(file), "{file:?}");
+        }
+    }
+}
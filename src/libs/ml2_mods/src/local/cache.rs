@@ -16,9 +16,9 @@ use tokio::{
 use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
 use tracing::{debug, instrument, trace, warn};
 
-use super::{LocalMods, ModLogo, Result};
+use super::{load_order, LocalMods, ModLogo, Result};
 use crate::{
-    data::Mod,
+    data::{BackupReason, Mod, SaveBackup},
     local::Error,
     spelunkyfyi::http::{DownloadedMod, Mod as ApiMod, RemoteMods},
 };
@@ -217,6 +217,30 @@ where
         }
     }
 
+    #[instrument(skip(self))]
+    async fn mod_toggled(&self, new: &Mod) {
+        let old = self.cache.lock().await.insert(new.id.clone(), new.clone());
+        if old.is_none() {
+            warn!("When toggling mod {:?}, it wasn't in cache", new.id);
+        }
+    }
+
+    /// Updates cached positions after an operation that can change the load order.
+    #[instrument(skip(self))]
+    async fn load_order_changed(&self) {
+        let ids = match self.local_mods.load_order().await {
+            Ok(ids) => ids,
+            Err(e) => {
+                warn!("Error reading load order: {:?}", e);
+                return;
+            }
+        };
+        for m in self.cache.lock().await.values_mut() {
+            m.load_order = load_order::position(ids.iter().map(String::as_str), &m.id);
+            m.enabled = m.load_order.is_some();
+        }
+    }
+
     #[instrument(skip(self))]
     async fn send_change(&self, change: DetectedChange) {
         trace!("Sending change {:?}", change);
@@ -279,6 +303,7 @@ where
     #[instrument(skip(self))]
     async fn remove(&self, id: &str) -> Result<()> {
         self.local_mods.remove(id).await?;
+        self.load_order_changed().await;
         Ok(())
     }
 
@@ -286,6 +311,7 @@ where
     async fn install_local(&self, source: &str, dest_id: &str) -> Result<Mod> {
         let new = self.local_mods.install_local(source, dest_id).await?;
         self.mod_installed(&new).await;
+        self.load_order_changed().await;
         Ok(new)
     }
 
@@ -293,6 +319,7 @@ where
     async fn install_remote(&self, downloaded: &DownloadedMod) -> Result<Mod> {
         let new = self.local_mods.install_remote(downloaded).await?;
         self.mod_installed(&new).await;
+        self.load_order_changed().await;
         Ok(new)
     }
 
@@ -324,6 +351,49 @@ where
     async fn get_mod_logo(&self, id: &str) -> Result<ModLogo> {
         self.local_mods.get_mod_logo(id).await
     }
+
+    #[instrument(skip(self))]
+    async fn enable(&self, id: &str) -> Result<Mod> {
+        let new = self.local_mods.enable(id).await?;
+        self.mod_toggled(&new).await;
+        self.load_order_changed().await;
+        Ok(new)
+    }
+
+    #[instrument(skip(self))]
+    async fn disable(&self, id: &str) -> Result<Mod> {
+        let new = self.local_mods.disable(id).await?;
+        self.mod_toggled(&new).await;
+        self.load_order_changed().await;
+        Ok(new)
+    }
+
+    #[instrument(skip(self))]
+    async fn load_order(&self) -> Result<Vec<String>> {
+        self.local_mods.load_order().await
+    }
+
+    #[instrument(skip(self))]
+    async fn set_load_order(&self, ids: &[String]) -> Result<Vec<String>> {
+        let new = self.local_mods.set_load_order(ids).await?;
+        self.load_order_changed().await;
+        Ok(new)
+    }
+
+    #[instrument(skip(self))]
+    async fn backup_saves(&self, reason: BackupReason, mod_id: &str) -> Result<Option<SaveBackup>> {
+        self.local_mods.backup_saves(reason, mod_id).await
+    }
+
+    #[instrument(skip(self))]
+    async fn list_backups(&self) -> Result<Vec<SaveBackup>> {
+        self.local_mods.list_backups().await
+    }
+
+    #[instrument(skip(self))]
+    async fn restore_backup(&self, id: &str) -> Result<SaveBackup> {
+        self.local_mods.restore_backup(id).await
+    }
 }
 
 impl ModCacheHandle {
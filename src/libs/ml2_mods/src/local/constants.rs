@@ -1,4 +1,11 @@
-pub const MODS_SUBPATH: &str = r"Mods\Packs";
-pub const MOD_METADATA_SUBPATH: &str = r"Mods\.ml\pack-metadata";
+// Subpaths are lists of components, so they can be joined portably with `paths::subpath`
+pub const MODS_SUBPATH: &[&str] = &["Mods", "Packs"];
+pub const MOD_METADATA_SUBPATH: &[&str] = &["Mods", ".ml", "pack-metadata"];
+pub const DISABLED_MODS_SUBPATH: &[&str] = &["Mods", ".ml", "disabled-packs"];
+pub const LOAD_ORDER_FILENAME: &str = r"load_order.txt";
 pub const MANIFEST_FILENAME: &str = r"manifest.json";
 pub const LATEST_FILENAME: &str = r"latest.json";
+pub const SAVE_BACKUPS_SUBPATH: &[&str] = &["Mods", ".ml", "save-backups"];
+pub const BACKUP_METADATA_FILENAME: &str = r"backup.json";
+pub const GAME_SAVE_FILENAME: &str = r"savegame.sav";
+pub const PACK_SAVE_FILENAME: &str = r"save.dat";
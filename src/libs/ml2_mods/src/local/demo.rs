@@ -4,7 +4,7 @@ use tracing::{info, instrument};
 
 use super::{Error, LocalMods, ModLogo, Result};
 use crate::{
-    data::Mod,
+    data::{BackupReason, Mod, SaveBackup},
     spelunkyfyi::http::{DownloadedMod, Mod as ApiMod},
 };
 
@@ -66,4 +66,50 @@ impl LocalMods for LoggingLocalMods {
         info!("get_logo_bytes");
         Err(Error::UnknownError(anyhow!("Not implemented")))
     }
+
+    #[instrument]
+    async fn enable(&self, _id: &str) -> Result<Mod> {
+        info!("enable");
+        Err(Error::UnknownError(anyhow!("Not implemented")))
+    }
+
+    #[instrument]
+    async fn disable(&self, _id: &str) -> Result<Mod> {
+        info!("disable");
+        Err(Error::UnknownError(anyhow!("Not implemented")))
+    }
+
+    #[instrument]
+    async fn load_order(&self) -> Result<Vec<String>> {
+        info!("load_order");
+        Err(Error::UnknownError(anyhow!("Not implemented")))
+    }
+
+    #[instrument]
+    async fn set_load_order(&self, _ids: &[String]) -> Result<Vec<String>> {
+        info!("set_load_order");
+        Err(Error::UnknownError(anyhow!("Not implemented")))
+    }
+
+    #[instrument]
+    async fn backup_saves(
+        &self,
+        _reason: BackupReason,
+        _mod_id: &str,
+    ) -> Result<Option<SaveBackup>> {
+        info!("backup_saves");
+        Err(Error::UnknownError(anyhow!("Not implemented")))
+    }
+
+    #[instrument]
+    async fn list_backups(&self) -> Result<Vec<SaveBackup>> {
+        info!("list_backups");
+        Err(Error::UnknownError(anyhow!("Not implemented")))
+    }
+
+    #[instrument]
+    async fn restore_backup(&self, _id: &str) -> Result<SaveBackup> {
+        info!("restore_backup");
+        Err(Error::UnknownError(anyhow!("Not implemented")))
+    }
 }
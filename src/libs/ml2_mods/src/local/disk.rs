@@ -11,12 +11,18 @@ use tracing::{debug, instrument};
 use zip::result::ZipError;
 use zip::ZipArchive;
 
+use super::backup::{SaveBackups, DEFAULT_MAX_BACKUPS};
+use super::load_order::{self, LoadOrderEntry};
+use super::paths::{locate_pack, name_on_disk, resolve};
 use super::ModLogo;
 use super::{
-    constants::{LATEST_FILENAME, MANIFEST_FILENAME, MODS_SUBPATH, MOD_METADATA_SUBPATH},
+    constants::{
+        DISABLED_MODS_SUBPATH, LATEST_FILENAME, LOAD_ORDER_FILENAME, MANIFEST_FILENAME,
+        MODS_SUBPATH, MOD_METADATA_SUBPATH, PACK_SAVE_FILENAME,
+    },
     Error, LocalMods, Result,
 };
-use crate::data::{Manifest, ManifestModFile, Mod};
+use crate::data::{BackupReason, Manifest, ManifestModFile, Mod, SaveBackup};
 use crate::spelunkyfyi::http::{DownloadedLogo, DownloadedMod, Mod as ApiMod};
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -27,26 +33,181 @@ struct LatestFile {
 #[derive(Clone, Debug)]
 pub struct DiskMods {
     install_path: PathBuf,
+    backups: SaveBackups,
 }
 
 impl DiskMods {
     pub fn new(install_path: &str) -> Self {
         Self {
             install_path: install_path.into(),
+            backups: SaveBackups::new(install_path, DEFAULT_MAX_BACKUPS),
         }
     }
 
-    fn mods_dir_path(&self, id: &str) -> PathBuf {
-        self.install_path.join(MODS_SUBPATH).join(id)
+    /// Sets how many save backups are kept. Zero disables backups.
+    pub fn with_max_backups(mut self, max_backups: usize) -> Self {
+        self.backups = SaveBackups::new(self.install_path.clone(), max_backups);
+        self
     }
 
-    fn manifest_dir_path(&self, id: &str) -> PathBuf {
-        self.install_path.join(MOD_METADATA_SUBPATH).join(id)
+    async fn mods_dir_path(&self, id: &str) -> Result<PathBuf> {
+        Ok(resolve(&self.install_path, &[MODS_SUBPATH, &[id]].concat()).await?)
+    }
+
+    async fn disabled_dir_path(&self, id: &str) -> Result<PathBuf> {
+        Ok(resolve(&self.install_path, &[DISABLED_MODS_SUBPATH, &[id]].concat()).await?)
+    }
+
+    async fn pack_dir_path(&self, id: &str, enabled: bool) -> Result<PathBuf> {
+        if enabled {
+            self.mods_dir_path(id).await
+        } else {
+            self.disabled_dir_path(id).await
+        }
+    }
+
+    /// Where a mod is, and whether it's enabled.
+    async fn locate(&self, id: &str) -> Result<Option<(PathBuf, bool)>> {
+        Ok(locate_pack(&self.install_path, id).await?)
+    }
+
+    /// Names of the entries in a packs directory.
+    async fn list_ids(&self, subpath: &[&str]) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        let mod_path = resolve(&self.install_path, subpath).await?;
+        let mut dir = match fs::read_dir(mod_path).await {
+            Ok(d) => d,
+            Err(e) => match e.kind() {
+                io::ErrorKind::NotFound => return Ok(ids),
+                _ => return Err(Error::UnknownError(e.into())),
+            },
+        };
+
+        while let Some(entry) = dir
+            .next_entry()
+            .await
+            .map_err(|e| Error::UnknownError(e.into()))?
+        {
+            if entry.file_name() == ".db" {
+                continue;
+            }
+            let id = entry
+                .file_name()
+                .into_string()
+                .map_err(|e| Error::UnknownError(anyhow!("Couldn't convert to unicode: {e:?}")))?;
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+
+    /// Ids of the mods in the packs directory, sorted.
+    async fn enabled_ids(&self) -> Result<Vec<String>> {
+        let mut ids = Vec::new();
+        for id in self.list_ids(MODS_SUBPATH).await? {
+            let metadata = path_metadata(self.mods_dir_path(&id).await?).await?;
+            if metadata.is_some_and(|m| m.is_dir()) {
+                ids.push(id);
+            }
+        }
+        ids.sort();
+        Ok(ids)
+    }
+
+    async fn load_order_path(&self) -> Result<PathBuf> {
+        let subpath = [MODS_SUBPATH, &[LOAD_ORDER_FILENAME]].concat();
+        Ok(resolve(&self.install_path, &subpath).await?)
+    }
+
+    /// The load order file, matched against the enabled mods.
+    #[instrument(skip(self))]
+    async fn read_load_order(&self) -> Result<Vec<LoadOrderEntry>> {
+        let ids = self.enabled_ids().await?;
+        let entries = load_order::read(&self.load_order_path().await?).await?;
+        Ok(load_order::reconcile(&entries, &ids))
+    }
+
+    /// Rewrites the load order file to match the enabled mods, e.g. after one is installed.
+    #[instrument(skip(self))]
+    async fn sync_load_order(&self) -> Result<Vec<LoadOrderEntry>> {
+        let entries = self.read_load_order().await?;
+        debug!("Writing load order of {} mods", entries.len());
+        load_order::write(&self.load_order_path().await?, &entries).await?;
+        Ok(entries)
+    }
+
+    #[instrument(skip(self))]
+    async fn set_enabled(&self, id: &str, enabled: bool) -> Result<Mod> {
+        let (path, was_enabled) = self
+            .locate(id)
+            .await?
+            .ok_or_else(|| Error::NotFound(id.to_string()))?;
+        // Moved under the name it has, not however the caller spelled it
+        let id = name_on_disk(&path).await?.unwrap_or_else(|| id.to_string());
+        if was_enabled != enabled {
+            let dest = self.pack_dir_path(&id, enabled).await?;
+            if path_metadata(&dest).await?.is_some() {
+                return Err(Error::AlreadyExists(id));
+            }
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            debug!("Moving {:?} to {:?}", path, dest);
+            fs::rename(&path, &dest).await?;
+        }
+        let mut load_order = self.sync_load_order().await?;
+        if enabled {
+            // Playlunky would still skip it
+            let lower = id.to_lowercase();
+            let skipped = load_order
+                .iter_mut()
+                .find(|e| e.id.to_lowercase() == lower && !e.selected);
+            if let Some(entry) = skipped {
+                debug!("Unmarking {:?} in the load order", entry.id);
+                entry.selected = true;
+                load_order::write(&self.load_order_path().await?, &load_order).await?;
+            }
+        }
+        self.get(&id).await
+    }
+
+    async fn load_mod(&self, id: &str, load_order: &[LoadOrderEntry]) -> Result<Mod> {
+        // First, check that the mod exists
+        let (mod_path, enabled) = match self.locate(id).await? {
+            Some(found) => found,
+            None => return Err(Error::NotFound(id.to_string())),
+        };
+        // The id may have been given in a different case
+        let id = name_on_disk(&mod_path)
+            .await?
+            .unwrap_or_else(|| id.to_string());
+        if let Some(m) = path_metadata(&mod_path).await? {
+            if !m.is_dir() {
+                return Err(Error::NonDirectory(id));
+            }
+        }
+
+        let manifest = self.load_mod_manifest(&id).await?;
+        // Packs marked with `--` are installed but skipped, just like disabled ones
+        let load_order = if enabled {
+            position(load_order, &id)
+        } else {
+            None
+        };
+        Ok(Mod {
+            id,
+            manifest,
+            enabled: load_order.is_some(),
+            load_order,
+        })
+    }
+
+    async fn manifest_dir_path(&self, id: &str) -> Result<PathBuf> {
+        Ok(resolve(&self.install_path, &[MOD_METADATA_SUBPATH, &[id]].concat()).await?)
     }
 
     #[instrument(skip(self))]
     async fn create_manifest_dir(&self, id: &str) -> Result<PathBuf> {
-        let manifest_dir = self.manifest_dir_path(id);
+        let manifest_dir = self.manifest_dir_path(id).await?;
         debug!("Creating manifest dir {:?}", manifest_dir);
         fs::create_dir_all(&manifest_dir).await?;
         Ok(manifest_dir)
@@ -54,7 +215,7 @@ impl DiskMods {
 
     #[instrument(skip(self))]
     async fn load_latest(&self, id: &str) -> Result<Option<String>> {
-        let path = self.manifest_dir_path(id).join(LATEST_FILENAME);
+        let path = self.manifest_dir_path(id).await?.join(LATEST_FILENAME);
         let json = if let Some(content) = try_read(path).await? {
             content
         } else {
@@ -66,7 +227,7 @@ impl DiskMods {
 
     #[instrument(skip(self))]
     async fn load_mod_manifest(&self, id: &str) -> Result<Option<Manifest>> {
-        let path = self.manifest_dir_path(id).join(MANIFEST_FILENAME);
+        let path = self.manifest_dir_path(id).await?.join(MANIFEST_FILENAME);
         let json = if let Some(content) = try_read(path).await? {
             content
         } else {
@@ -78,8 +239,7 @@ impl DiskMods {
     #[instrument(skip(self))]
     async fn write_mod_manifest(&self, id: &str, manifest: &Manifest) -> Result<()> {
         debug!("Writing manifest");
-        self.create_manifest_dir(id).await?;
-        let manifest_path = self.manifest_dir_path(id).join(MANIFEST_FILENAME);
+        let manifest_path = self.create_manifest_dir(id).await?.join(MANIFEST_FILENAME);
         let json = serde_json::to_string(manifest).map_err(|e| Error::UnknownError(e.into()))?;
         debug!("Writing manifest JSON to {:?}", manifest_path);
         fs::write(manifest_path, json).await?;
@@ -87,11 +247,10 @@ impl DiskMods {
     }
 
     async fn make_dest_dir(&self, dest_id: &str) -> Result<PathBuf> {
-        let dest_dir_path = self.mods_dir_path(dest_id);
-
-        if path_metadata(&dest_dir_path).await?.is_some() {
+        if self.locate(dest_id).await?.is_some() {
             return Err(Error::AlreadyExists(dest_id.to_string()));
         }
+        let dest_dir_path = self.mods_dir_path(dest_id).await?;
         fs::create_dir_all(&dest_dir_path)
             .await
             .map_err(|e| Error::DestinationError(e.into()))?;
@@ -131,52 +290,55 @@ impl DiskMods {
             ))),
         }?;
 
-        self.create_manifest_dir(dest_id).await?;
+        let manifest_dir = self.create_manifest_dir(dest_id).await?;
 
         let mut dest_name = OsString::from("mod_logo.");
         dest_name.push(extension);
-        let dest_path = self.manifest_dir_path(dest_id).join(&dest_name);
+        let dest_path = manifest_dir.join(&dest_name);
         debug!("Installing logo to path {:?}", dest_path);
         fs::copy(&source.file, dest_path).await?;
 
         Ok(dest_name)
     }
 
-    async fn prep_for_update(&self, dest_id: &str) -> Result<TempDir> {
+    /// Stashes the old version of a mod. Returns whether it was enabled.
+    async fn prep_for_update(&self, dest_id: &str) -> Result<(TempDir, bool)> {
         let temp_dir = tempdir()?;
         let temp_mod_path = temp_dir.path().join(dest_id);
-        let old_mod_path = self.mods_dir_path(dest_id);
-        if path_metadata(&old_mod_path).await?.is_none() {
-            return Err(Error::NotFound(dest_id.to_string()));
-        }
+        let (old_mod_path, enabled) = self
+            .locate(dest_id)
+            .await?
+            .ok_or_else(|| Error::NotFound(dest_id.to_string()))?;
         fs::rename(&old_mod_path, &temp_mod_path).await?;
         debug!("Stashed old mod version in {:?}", temp_mod_path);
-        Ok(temp_dir)
+        Ok((temp_dir, enabled))
     }
 
     async fn finish_update(
         &self,
         dest_id: &str,
         temp_dir: TempDir,
+        enabled: bool,
         install_result: Result<Mod>,
     ) -> Result<Mod> {
         let temp_mod_path = temp_dir.path().join(dest_id);
-        let r#mod = match install_result {
+        let mut r#mod = match install_result {
             Err(e) => {
-                fs::rename(&temp_mod_path, self.mods_dir_path(dest_id)).await?;
+                let old_mod_path = self.pack_dir_path(dest_id, enabled).await?;
+                fs::rename(&temp_mod_path, &old_mod_path).await?;
                 return Err(e);
             }
             Ok(m) => m,
         };
 
-        let temp_save_path = temp_mod_path.join("save.dat");
+        let mod_path = self.mods_dir_path(dest_id).await?;
+        let temp_save_path = temp_mod_path.join(PACK_SAVE_FILENAME);
         if path_metadata(&temp_save_path).await?.is_some() {
             debug!("Restoring save.dat");
-            fs::copy(
-                &temp_save_path,
-                self.mods_dir_path(dest_id).join("save.dat"),
-            )
-            .await?;
+            fs::copy(&temp_save_path, mod_path.join(PACK_SAVE_FILENAME)).await?;
+        }
+        if !enabled {
+            r#mod = self.set_enabled(dest_id, false).await?;
         }
         Ok(r#mod)
     }
@@ -186,47 +348,24 @@ impl DiskMods {
 impl LocalMods for DiskMods {
     #[instrument(skip(self))]
     async fn get(&self, id: &str) -> Result<Mod> {
-        let id = id.to_string();
-        // First, check that the mod exists
-        let mod_path = self.mods_dir_path(&id);
-        match path_metadata(&mod_path).await? {
-            Some(m) => {
-                if !m.is_dir() {
-                    return Err(Error::NonDirectory(id));
-                }
-            }
-            None => return Err(Error::NotFound(id)),
-        }
-
-        let manifest = self.load_mod_manifest(&id).await?;
-        Ok(Mod { id, manifest })
+        let load_order = self.read_load_order().await?;
+        self.load_mod(id, &load_order).await
     }
 
     #[instrument(skip(self))]
     async fn list(&self) -> Result<Vec<Mod>> {
-        let mut mods = Vec::new();
-        let mod_path = self.install_path.join(MODS_SUBPATH);
-        let mut dir = match fs::read_dir(mod_path).await {
-            Ok(d) => d,
-            Err(e) => match e.kind() {
-                io::ErrorKind::NotFound => return Ok(mods),
-                _ => return Err(Error::UnknownError(e.into())),
-            },
-        };
-
-        while let Some(entry) = dir
-            .next_entry()
-            .await
-            .map_err(|e| Error::UnknownError(e.into()))?
-        {
-            if entry.file_name() == ".db" {
+        let mut mods: Vec<Mod> = Vec::new();
+        let load_order = self.read_load_order().await?;
+        let mut ids = self.list_ids(MODS_SUBPATH).await?;
+        ids.extend(self.list_ids(DISABLED_MODS_SUBPATH).await?);
+        for id in ids {
+            // A mod in both places is only listed as enabled, and ids are matched ignoring case
+            // like `load_mod` does
+            let lower = id.to_lowercase();
+            if mods.iter().any(|m| m.id.to_lowercase() == lower) {
                 continue;
             }
-            let id = entry
-                .file_name()
-                .into_string()
-                .map_err(|e| Error::UnknownError(anyhow!("Couldn't convert to unicode: {e:?}")))?;
-            let res = self.get(&id).await;
+            let res = self.load_mod(&id, &load_order).await;
             match res {
                 Ok(r#mod) => mods.push(r#mod),
                 Err(Error::NonDirectory(_)) => {}
@@ -234,12 +373,17 @@ impl LocalMods for DiskMods {
             }
         }
 
+        // Directory order varies by file system
+        mods.sort_by(|a, b| a.id.cmp(&b.id));
         Ok(mods)
     }
 
     #[instrument(skip(self))]
     async fn remove(&self, id: &str) -> Result<()> {
-        let mod_path = self.mods_dir_path(id);
+        let (mod_path, _) = self
+            .locate(id)
+            .await?
+            .ok_or_else(|| Error::NotFound(id.to_string()))?;
         fs::remove_dir_all(mod_path)
             .await
             .map_err(|e| match e.kind() {
@@ -247,16 +391,19 @@ impl LocalMods for DiskMods {
                 _ => Error::UnknownError(e.into()),
             })?;
 
-        let metadata_path = self.install_path.join(MOD_METADATA_SUBPATH).join(id);
+        let metadata_path = self.manifest_dir_path(id).await?;
         let res = fs::remove_dir_all(metadata_path).await;
         // It's OK if the metadata directory doesn't exist
         match res {
-            Ok(()) => Ok(()),
+            Ok(()) => {}
             Err(e) => match e.kind() {
-                io::ErrorKind::NotFound => Ok(()),
-                _ => Err(Error::UnknownError(e.into())),
+                io::ErrorKind::NotFound => {}
+                _ => return Err(Error::UnknownError(e.into())),
             },
         }
+
+        self.sync_load_order().await?;
+        Ok(())
     }
 
     #[instrument(skip(self))]
@@ -275,11 +422,9 @@ impl LocalMods for DiskMods {
         )?;
         let dest_dir = self.make_dest_dir(dest_id).await?;
         self.install_main(&source.into(), &dest_dir).await?;
-
-        Ok(Mod {
-            id: dest_id.to_string(),
-            manifest: None,
-        })
+        let load_order = self.sync_load_order().await?;
+        // A line Playlunky left behind may still mark it with `--`
+        self.load_mod(dest_id, &load_order).await
     }
 
     #[instrument(skip_all)]
@@ -317,26 +462,25 @@ impl LocalMods for DiskMods {
         };
         self.write_mod_manifest(&dest_id, &manifest).await?;
         self.update_latest_json(&downloaded.r#mod).await?;
-
-        Ok(Mod {
-            id: dest_id,
-            manifest: Some(manifest),
-        })
+        let load_order = self.sync_load_order().await?;
+        self.load_mod(&dest_id, &load_order).await
     }
 
     #[instrument(skip(self))]
     async fn update_local(&self, source: &str, dest_id: &str) -> Result<Mod> {
-        let temp_dir = self.prep_for_update(dest_id).await?;
+        let (temp_dir, enabled) = self.prep_for_update(dest_id).await?;
         let install_result = self.install_local(source, dest_id).await;
-        self.finish_update(dest_id, temp_dir, install_result).await
+        self.finish_update(dest_id, temp_dir, enabled, install_result)
+            .await
     }
 
     #[instrument(skip_all)]
     async fn update_remote(&self, downloaded: &DownloadedMod) -> Result<Mod> {
         let dest_id = id_for_remote(&downloaded.r#mod);
-        let temp_dir = self.prep_for_update(&dest_id).await?;
+        let (temp_dir, enabled) = self.prep_for_update(&dest_id).await?;
         let install_result = self.install_remote(downloaded).await;
-        self.finish_update(&dest_id, temp_dir, install_result).await
+        self.finish_update(&dest_id, temp_dir, enabled, install_result)
+            .await
     }
 
     #[instrument(skip(self, api_mod))]
@@ -374,7 +518,7 @@ impl LocalMods for DiskMods {
             .ok_or_else(|| anyhow!("Mod {id:?} has no manifest. Therefore it has no logo"))?
             .logo
             .ok_or_else(|| anyhow!("Mod {id:?} has no logo"))?;
-        let logo_path = self.manifest_dir_path(id).join(&logo_name);
+        let logo_path = self.manifest_dir_path(id).await?.join(&logo_name);
 
         let ext = logo_path
             .extension()
@@ -394,12 +538,66 @@ impl LocalMods for DiskMods {
         let bytes = fs::read(&logo_path).await?;
         Ok(ModLogo { mime_type, bytes })
     }
+
+    #[instrument(skip(self))]
+    async fn enable(&self, id: &str) -> Result<Mod> {
+        self.set_enabled(id, true).await
+    }
+
+    #[instrument(skip(self))]
+    async fn disable(&self, id: &str) -> Result<Mod> {
+        self.set_enabled(id, false).await
+    }
+
+    #[instrument(skip(self))]
+    async fn load_order(&self) -> Result<Vec<String>> {
+        let load_order = self.read_load_order().await?;
+        Ok(selected_ids(load_order))
+    }
+
+    #[instrument(skip(self))]
+    async fn set_load_order(&self, ids: &[String]) -> Result<Vec<String>> {
+        let load_order = self.read_load_order().await?;
+        let load_order = load_order::reorder(&load_order, ids).map_err(Error::NotFound)?;
+        load_order::write(&self.load_order_path().await?, &load_order).await?;
+        Ok(selected_ids(load_order))
+    }
+
+    #[instrument(skip(self))]
+    async fn backup_saves(&self, reason: BackupReason, mod_id: &str) -> Result<Option<SaveBackup>> {
+        self.backups.backup(reason, mod_id).await
+    }
+
+    #[instrument(skip(self))]
+    async fn list_backups(&self) -> Result<Vec<SaveBackup>> {
+        self.backups.list().await
+    }
+
+    #[instrument(skip(self))]
+    async fn restore_backup(&self, id: &str) -> Result<SaveBackup> {
+        self.backups.restore(id).await
+    }
 }
 
 fn id_for_remote(remote: &ApiMod) -> String {
     format!("fyi.{}", remote.slug)
 }
 
+/// Where `id` is among the packs Playlunky loads, ignoring case like the game.
+fn position(load_order: &[LoadOrderEntry], id: &str) -> Option<usize> {
+    let selected = load_order.iter().filter(|e| e.selected);
+    load_order::position(selected.map(|e| e.id.as_str()), id)
+}
+
+/// Ids of the packs Playlunky loads, in order.
+fn selected_ids(load_order: Vec<LoadOrderEntry>) -> Vec<String> {
+    load_order
+        .into_iter()
+        .filter(|e| e.selected)
+        .map(|e| e.id)
+        .collect()
+}
+
 async fn path_metadata(path: impl AsRef<Path>) -> Result<Option<std::fs::Metadata>> {
     let dest_check_result = fs::metadata(path).await;
     match dest_check_result {
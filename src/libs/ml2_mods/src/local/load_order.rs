@@ -0,0 +1,174 @@
+use std::io;
+use std::path::Path;
+
+use tokio::fs;
+
+/// A line of Playlunky's `load_order.txt`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadOrderEntry {
+    pub id: String,
+    /// Playlunky skips packs whose line starts with `--`.
+    pub selected: bool,
+}
+
+const UNSELECTED_PREFIX: &str = "--";
+
+pub fn parse(contents: &str) -> Vec<LoadOrderEntry> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.strip_prefix(UNSELECTED_PREFIX) {
+            Some(id) => LoadOrderEntry {
+                id: id.to_string(),
+                selected: false,
+            },
+            None => LoadOrderEntry {
+                id: line.to_string(),
+                selected: true,
+            },
+        })
+        .collect()
+}
+
+pub fn format(entries: &[LoadOrderEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            let prefix = if entry.selected {
+                ""
+            } else {
+                UNSELECTED_PREFIX
+            };
+            format!("{prefix}{}\n", entry.id)
+        })
+        .collect()
+}
+
+/// Lines in `path`, or none if it doesn't exist.
+pub async fn read(path: &Path) -> io::Result<Vec<LoadOrderEntry>> {
+    match fs::read_to_string(path).await {
+        Ok(contents) => Ok(parse(&contents)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn write(path: &Path, entries: &[LoadOrderEntry]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(path, format(entries)).await
+}
+
+/// Matches the file's lines against the installed packs in `ids`, the way the game would.
+///
+/// Lines for packs that aren't installed are dropped, and packs without a line are added at the
+/// end in the order given. Ids are matched ignoring case, but the installed spelling is kept.
+pub fn reconcile(entries: &[LoadOrderEntry], ids: &[String]) -> Vec<LoadOrderEntry> {
+    let mut result: Vec<LoadOrderEntry> = Vec::with_capacity(ids.len());
+    for entry in entries {
+        let lower = entry.id.to_lowercase();
+        let Some(id) = ids.iter().find(|id| id.to_lowercase() == lower) else {
+            continue;
+        };
+        if result.iter().any(|e| &e.id == id) {
+            continue;
+        }
+        result.push(LoadOrderEntry {
+            id: id.clone(),
+            selected: entry.selected,
+        });
+    }
+    for id in ids {
+        if !result.iter().any(|e| &e.id == id) {
+            result.push(LoadOrderEntry {
+                id: id.clone(),
+                selected: true,
+            });
+        }
+    }
+    result
+}
+
+/// Where `id` is among `ids`, matched ignoring case like [`reconcile`].
+pub fn position<'a>(ids: impl IntoIterator<Item = &'a str>, id: &str) -> Option<usize> {
+    let id = id.to_lowercase();
+    ids.into_iter().position(|other| other.to_lowercase() == id)
+}
+
+/// Moves the packs in `ids` to the front, in that order, leaving the rest in their current
+/// order. Ids are matched ignoring case like [`reconcile`], and ones that aren't in `entries`
+/// are returned as an error.
+pub fn reorder(entries: &[LoadOrderEntry], ids: &[String]) -> Result<Vec<LoadOrderEntry>, String> {
+    let mut rest = entries.to_vec();
+    let mut result: Vec<LoadOrderEntry> = Vec::with_capacity(entries.len());
+    for id in ids {
+        if position(result.iter().map(|e| e.id.as_str()), id).is_some() {
+            continue;
+        }
+        let index = position(rest.iter().map(|e| e.id.as_str()), id).ok_or_else(|| id.clone())?;
+        result.push(rest.remove(index));
+    }
+    result.extend(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, selected: bool) -> LoadOrderEntry {
+        LoadOrderEntry {
+            id: id.to_string(),
+            selected,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let entries = parse("b\r\n--a\n\n  c  \n");
+        assert_eq!(
+            entries,
+            vec![entry("b", true), entry("a", false), entry("c", true)]
+        );
+        assert_eq!(format(&entries), "b\n--a\nc\n");
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let entries = vec![entry("Gone", true), entry("B", false), entry("a", true)];
+        let ids = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(
+            reconcile(&entries, &ids),
+            vec![entry("b", false), entry("a", true), entry("c", true)]
+        );
+    }
+
+    #[test]
+    fn test_position() {
+        let ids = ["a", "Provincial", "c"];
+        assert_eq!(position(ids, "provincial"), Some(1));
+        assert_eq!(position(ids, "PROVINCIAL"), Some(1));
+        assert_eq!(position(ids, "gone"), None);
+    }
+
+    #[test]
+    fn test_reorder() {
+        let entries = vec![entry("a", true), entry("b", false), entry("c", true)];
+        let ids = vec!["c".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(
+            reorder(&entries, &ids),
+            Ok(vec![entry("c", true), entry("b", false), entry("a", true)])
+        );
+        assert_eq!(reorder(&entries, &["d".to_string()]), Err("d".to_string()));
+
+        // The installed spelling is kept
+        let entries = vec![entry("a", true), entry("Provincial", true)];
+        let ids = vec!["provincial".to_string(), "PROVINCIAL".to_string()];
+        assert_eq!(
+            reorder(&entries, &ids),
+            Ok(vec![entry("Provincial", true), entry("a", true)])
+        );
+    }
+}
@@ -1,13 +1,16 @@
+pub mod backup;
 pub mod cache;
 pub mod constants;
 pub mod demo;
 pub mod disk;
+pub mod load_order;
+pub mod paths;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    data::Mod,
+    data::{BackupReason, Mod, SaveBackup},
     spelunkyfyi::http::{DownloadedMod, Mod as ApiMod},
 };
 
@@ -19,6 +22,8 @@ pub enum Error {
     NotFound(String),
     #[error("Mod {0} isn't in a directory")]
     NonDirectory(String),
+    #[error("Save backup {0} wasn't found")]
+    BackupNotFound(String),
 
     #[error("Problem with installation source")]
     SourceError(#[source] anyhow::Error),
@@ -51,4 +56,21 @@ pub trait LocalMods {
     async fn update_remote(&self, downloaded: &DownloadedMod) -> Result<Mod>;
     async fn update_latest_json(&self, api_mod: &ApiMod) -> Result<Option<String>>;
     async fn get_mod_logo(&self, id: &str) -> Result<ModLogo>;
+    /// Moves a disabled mod back into the packs directory, and unmarks it if `load_order.txt`
+    /// marks it with `--`. Enabled mods are left as they are.
+    async fn enable(&self, id: &str) -> Result<Mod>;
+    /// Moves a mod out of the packs directory, keeping its metadata and save.dat.
+    async fn disable(&self, id: &str) -> Result<Mod>;
+    /// Ids of the enabled mods, in Playlunky's load order. Mods marked with `--` in
+    /// `load_order.txt` aren't enabled, though the file keeps their place.
+    async fn load_order(&self) -> Result<Vec<String>>;
+    /// Moves the mods in `ids` to the front of the load order, keeping the rest in their current
+    /// order, and saves it. Returns the new order of the enabled mods.
+    async fn set_load_order(&self, ids: &[String]) -> Result<Vec<String>>;
+    /// Snapshots the game's save and each pack's save.dat. Returns `None` if there was nothing
+    /// to back up.
+    async fn backup_saves(&self, reason: BackupReason, mod_id: &str) -> Result<Option<SaveBackup>>;
+    /// Backups, newest first.
+    async fn list_backups(&self) -> Result<Vec<SaveBackup>>;
+    async fn restore_backup(&self, id: &str) -> Result<SaveBackup>;
 }
@@ -0,0 +1,100 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+use super::constants::{DISABLED_MODS_SUBPATH, MODS_SUBPATH};
+
+/// Builds a relative path from components, using the platform's separator.
+pub fn subpath(components: &[&str]) -> PathBuf {
+    components.iter().collect()
+}
+
+/// Joins `components` onto `base`, matching them case-insensitively like the game does.
+///
+/// The game looks paths up case-insensitively on Windows and under Proton, so a component that
+/// doesn't exist as given is matched against the names in its directory, ignoring case.
+/// Components with no match are joined as given, so the result can be used to create them.
+pub async fn resolve(base: &Path, components: &[&str]) -> io::Result<PathBuf> {
+    let mut path = base.to_path_buf();
+    // Nothing can exist under a directory that doesn't
+    let mut searching = true;
+    for component in components {
+        if searching && !exists(&path.join(component)).await? {
+            match find_ignoring_case(&path, component).await? {
+                Some(name) => {
+                    path.push(name);
+                    continue;
+                }
+                None => searching = false,
+            }
+        }
+        path.push(component);
+    }
+    Ok(path)
+}
+
+/// Splits a path stored with either separator, e.g. in metadata written on another platform.
+pub fn split_stored(path: &str) -> Vec<&str> {
+    path.split(['/', '\\']).filter(|c| !c.is_empty()).collect()
+}
+
+/// The name of the existing file or directory at `path` as it's spelled on disk.
+///
+/// This can differ in case from `path` on case-insensitive file systems, where `path` is found
+/// however it's spelled.
+pub async fn name_on_disk(path: &Path) -> io::Result<Option<String>> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(|n| n.to_str())) else {
+        return Ok(None);
+    };
+    let mut entries = fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_name() == name {
+            return Ok(Some(name.to_string()));
+        }
+    }
+    find_ignoring_case(dir, name).await
+}
+
+async fn find_ignoring_case(dir: &Path, name: &str) -> io::Result<Option<String>> {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let name = name.to_lowercase();
+    let mut matches = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        if let Some(entry_name) = entry.file_name().to_str() {
+            if entry_name.to_lowercase() == name {
+                matches.push(entry_name.to_string());
+            }
+        }
+    }
+    // Case-sensitive file systems can have several, so pick one consistently
+    matches.sort();
+    Ok(matches.into_iter().next())
+}
+
+pub(crate) async fn exists(path: &Path) -> io::Result<bool> {
+    match fs::metadata(path).await {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Where pack `id` is in the install directory, and whether it's enabled. If it's somehow both
+/// enabled and disabled, the enabled one is what the game sees.
+pub(crate) async fn locate_pack(
+    install_path: &Path,
+    id: &str,
+) -> io::Result<Option<(PathBuf, bool)>> {
+    for (subpath, enabled) in [(MODS_SUBPATH, true), (DISABLED_MODS_SUBPATH, false)] {
+        let path = resolve(install_path, &[subpath, &[id]].concat()).await?;
+        if exists(&path).await? {
+            return Ok(Some((path, enabled)));
+        }
+    }
+    Ok(None)
+}
@@ -9,7 +9,7 @@ use tokio::time::MissedTickBehavior;
 use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
 use tracing::{debug, info, instrument};
 
-use crate::data::{Change, DownloadProgress, Mod, ModProgress};
+use crate::data::{BackupReason, Change, DownloadProgress, Mod, ModProgress, SaveBackup};
 use crate::local::cache::DetectedChange;
 use crate::local::{Error as LocalError, LocalMods, ModLogo};
 use crate::spelunkyfyi::{
@@ -48,6 +48,30 @@ enum Command {
         #[derivative(Debug = "ignore")]
         resp: oneshot::Sender<Result<ModLogo>>,
     },
+    SetEnabled {
+        id: String,
+        enabled: bool,
+        #[derivative(Debug = "ignore")]
+        resp: oneshot::Sender<Result<Mod>>,
+    },
+    GetLoadOrder {
+        #[derivative(Debug = "ignore")]
+        resp: oneshot::Sender<Result<Vec<String>>>,
+    },
+    SetLoadOrder {
+        ids: Vec<String>,
+        #[derivative(Debug = "ignore")]
+        resp: oneshot::Sender<Result<Vec<String>>>,
+    },
+    ListBackups {
+        #[derivative(Debug = "ignore")]
+        resp: oneshot::Sender<Result<Vec<SaveBackup>>>,
+    },
+    RestoreBackup {
+        id: String,
+        #[derivative(Debug = "ignore")]
+        resp: oneshot::Sender<Result<SaveBackup>>,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -69,6 +93,8 @@ pub enum Error {
     ModNotFoundError(#[source] LocalError),
     #[error("{0}")]
     ModNonDirectoryError(#[source] LocalError),
+    #[error("{0}")]
+    BackupNotFoundError(#[source] LocalError),
 
     #[error("{0}")]
     ManifestParseError(#[source] LocalError),
@@ -195,6 +221,31 @@ where
                     info!("Receiver dropped for GetModLogo({:?})", id);
                 }
             }
+            Command::SetEnabled { id, enabled, resp } => {
+                if resp.send(self.set_enabled(&id, enabled).await).is_err() {
+                    info!("Receiver dropped for SetEnabled({:?}, {})", id, enabled);
+                }
+            }
+            Command::GetLoadOrder { resp } => {
+                if resp.send(self.load_order().await).is_err() {
+                    info!("Receiver dropped for GetLoadOrder()");
+                }
+            }
+            Command::SetLoadOrder { ids, resp } => {
+                if resp.send(self.set_load_order(&ids).await).is_err() {
+                    info!("Receiver dropped for SetLoadOrder({:?})", ids);
+                }
+            }
+            Command::ListBackups { resp } => {
+                if resp.send(self.list_backups().await).is_err() {
+                    info!("Receiver dropped for ListBackups()");
+                }
+            }
+            Command::RestoreBackup { id, resp } => {
+                if resp.send(self.restore_backup(&id).await).is_err() {
+                    info!("Receiver dropped for RestoreBackup({:?})", id);
+                }
+            }
         }
     }
 
@@ -208,8 +259,22 @@ where
         Ok(self.local_mods.list().await?)
     }
 
+    /// Fails unless `id` is installed, or unless it isn't if `installed` is false, so that saves
+    /// are only backed up for operations that can go ahead.
+    async fn check_installed(&self, id: &str, installed: bool) -> Result<()> {
+        match self.local_mods.get(id).await {
+            Ok(_) if !installed => Err(LocalError::AlreadyExists(id.to_string()).into()),
+            Err(LocalError::NotFound(_)) if !installed => Ok(()),
+            res => res.map(|_| ()).map_err(Into::into),
+        }
+    }
+
     #[instrument(skip(self))]
     async fn remove_mod(&self, id: &str) -> Result<()> {
+        self.check_installed(id, true).await?;
+        self.local_mods
+            .backup_saves(BackupReason::Remove, id)
+            .await?;
         self.local_mods.remove(id).await?;
         self.send_change(Change::Remove { id: id.to_string() });
         Ok(())
@@ -234,6 +299,10 @@ where
             },
         });
 
+        self.check_installed(dest_id, false).await?;
+        self.local_mods
+            .backup_saves(BackupReason::Install, dest_id)
+            .await?;
         let r#mod = self.local_mods.install_local(source_path, dest_id).await?;
 
         self.send_change(Change::Add {
@@ -248,10 +317,14 @@ where
     async fn install_remote_mod(&self, code: &str) -> Result<Mod> {
         let id = format!("fyi.{code}");
         self.send_change(Change::Add {
-            progress: ModProgress::Started { id },
+            progress: ModProgress::Started { id: id.clone() },
         });
 
         let downloaded = self.download_mod(code, OpKind::Install()).await?;
+        self.check_installed(&id, false).await?;
+        self.local_mods
+            .backup_saves(BackupReason::Install, &id)
+            .await?;
         let r#mod = self.local_mods.install_remote(&downloaded).await?;
 
         self.send_change(Change::Add {
@@ -281,6 +354,10 @@ where
             },
         });
 
+        self.check_installed(dest_id, true).await?;
+        self.local_mods
+            .backup_saves(BackupReason::Update, dest_id)
+            .await?;
         let r#mod = self.local_mods.update_local(source_path, dest_id).await?;
 
         self.send_change(Change::Update {
@@ -295,10 +372,14 @@ where
     async fn update_remote_mod(&self, code: &str) -> Result<Mod> {
         let id = format!("fyi.{code}");
         self.send_change(Change::Update {
-            progress: ModProgress::Started { id },
+            progress: ModProgress::Started { id: id.clone() },
         });
 
         let downloaded = self.download_mod(code, OpKind::Update()).await?;
+        self.check_installed(&id, true).await?;
+        self.local_mods
+            .backup_saves(BackupReason::Update, &id)
+            .await?;
         let r#mod = self.local_mods.update_remote(&downloaded).await?;
 
         self.send_change(Change::Update {
@@ -395,6 +476,42 @@ where
     async fn get_mod_logo(&self, id: &str) -> Result<ModLogo> {
         Ok(self.local_mods.get_mod_logo(id).await?)
     }
+
+    #[instrument(skip(self))]
+    async fn set_enabled(&self, id: &str, enabled: bool) -> Result<Mod> {
+        let r#mod = if enabled {
+            self.local_mods.enable(id).await?
+        } else {
+            self.local_mods.disable(id).await?
+        };
+        self.send_change(Change::SetEnabled {
+            id: r#mod.id.clone(),
+            enabled: r#mod.enabled,
+        });
+        Ok(r#mod)
+    }
+
+    #[instrument(skip(self))]
+    async fn load_order(&self) -> Result<Vec<String>> {
+        Ok(self.local_mods.load_order().await?)
+    }
+
+    #[instrument(skip(self))]
+    async fn set_load_order(&self, ids: &[String]) -> Result<Vec<String>> {
+        let new = self.local_mods.set_load_order(ids).await?;
+        self.send_change(Change::LoadOrder { ids: new.clone() });
+        Ok(new)
+    }
+
+    #[instrument(skip(self))]
+    async fn list_backups(&self) -> Result<Vec<SaveBackup>> {
+        Ok(self.local_mods.list_backups().await?)
+    }
+
+    #[instrument(skip(self))]
+    async fn restore_backup(&self, id: &str) -> Result<SaveBackup> {
+        Ok(self.local_mods.restore_backup(id).await?)
+    }
 }
 
 impl<A, L> std::fmt::Debug for ModManager<A, L>
@@ -502,6 +619,80 @@ impl ModManagerHandle {
             .map_err(|e| Error::ChannelError(e.into()))?;
         rx.await.map_err(|e| Error::ChannelError(e.into()))?
     }
+
+    #[instrument]
+    pub async fn enable(&self, mod_id: &str) -> Result<Mod> {
+        self.set_enabled(mod_id, true).await
+    }
+
+    #[instrument]
+    pub async fn disable(&self, mod_id: &str) -> Result<Mod> {
+        self.set_enabled(mod_id, false).await
+    }
+
+    async fn set_enabled(&self, mod_id: &str, enabled: bool) -> Result<Mod> {
+        let (tx, rx) = oneshot::channel();
+        self.commands_tx
+            .send(Command::SetEnabled {
+                id: mod_id.to_string(),
+                enabled,
+                resp: tx,
+            })
+            .await
+            .map_err(|e| Error::ChannelError(e.into()))?;
+        rx.await.map_err(|e| Error::ChannelError(e.into()))?
+    }
+
+    /// Ids of the enabled mods, in Playlunky's load order.
+    #[instrument]
+    pub async fn load_order(&self) -> Result<Vec<String>> {
+        let (tx, rx) = oneshot::channel();
+        self.commands_tx
+            .send(Command::GetLoadOrder { resp: tx })
+            .await
+            .map_err(|e| Error::ChannelError(e.into()))?;
+        rx.await.map_err(|e| Error::ChannelError(e.into()))?
+    }
+
+    /// Moves the given mods to the front of the load order, in that order, and saves it. Mods
+    /// that aren't listed keep their relative order. Returns the new order.
+    #[instrument]
+    pub async fn set_load_order(&self, mod_ids: &[String]) -> Result<Vec<String>> {
+        let (tx, rx) = oneshot::channel();
+        self.commands_tx
+            .send(Command::SetLoadOrder {
+                ids: mod_ids.to_vec(),
+                resp: tx,
+            })
+            .await
+            .map_err(|e| Error::ChannelError(e.into()))?;
+        rx.await.map_err(|e| Error::ChannelError(e.into()))?
+    }
+
+    /// Save backups, newest first.
+    #[instrument]
+    pub async fn list_backups(&self) -> Result<Vec<SaveBackup>> {
+        let (tx, rx) = oneshot::channel();
+        self.commands_tx
+            .send(Command::ListBackups { resp: tx })
+            .await
+            .map_err(|e| Error::ChannelError(e.into()))?;
+        rx.await.map_err(|e| Error::ChannelError(e.into()))?
+    }
+
+    /// Puts a backup's saves back, after backing up the current ones.
+    #[instrument]
+    pub async fn restore_backup(&self, backup_id: &str) -> Result<SaveBackup> {
+        let (tx, rx) = oneshot::channel();
+        self.commands_tx
+            .send(Command::RestoreBackup {
+                id: backup_id.to_string(),
+                resp: tx,
+            })
+            .await
+            .map_err(|e| Error::ChannelError(e.into()))?;
+        rx.await.map_err(|e| Error::ChannelError(e.into()))?
+    }
 }
 
 impl From<RemoteError> for Error {
@@ -516,6 +707,7 @@ impl From<LocalError> for Error {
             LocalError::AlreadyExists(_) => Error::ModExistsError(err),
             LocalError::NotFound(_) => Error::ModNotFoundError(err),
             LocalError::NonDirectory(_) => Error::ModNonDirectoryError(err),
+            LocalError::BackupNotFound(_) => Error::BackupNotFoundError(err),
             LocalError::SourceError(_) => Error::SourceError(err),
             LocalError::DestinationError(_) => Error::DestinationError(err),
             LocalError::IoError(_) => Error::UnknownError(err.into()),
@@ -0,0 +1,162 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ml2_mods::discovery::{auto_select, find_installs, library_folders, Install};
+use tempfile::tempdir;
+
+fn write(path: PathBuf, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+fn vdf_path(path: &Path) -> String {
+    path.to_str().unwrap().replace('\\', r"\\")
+}
+
+fn app_manifest(build_id: &str, state_flags: &str) -> String {
+    format!(
+        r#""AppState"
+{{
+	"appid"		"418530"
+	"name"		"Spelunky 2"
+	"StateFlags"		"{state_flags}"
+	"installdir"		"Spelunky 2"
+	"LastUpdated"		"1700000000"
+	"buildid"		"{build_id}"
+}}
+"#
+    )
+}
+
+#[test]
+fn test_find_installs() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("Steam");
+    let games = dir.path().join("Games");
+    let empty = dir.path().join("Empty");
+
+    write(
+        root.join("steamapps").join("libraryfolders.vdf"),
+        &format!(
+            r#""libraryfolders"
+{{
+	"0"
+	{{
+		"path"		"{}"
+		"apps" {{ "228980" "1" }}
+	}}
+	"1"
+	{{
+		"path"		"{}"
+		"apps" {{ "418530" "1" }}
+	}}
+	"2"
+	{{
+		"path"		"{}"
+	}}
+}}
+"#,
+            vdf_path(&root),
+            vdf_path(&games),
+            vdf_path(&empty),
+        ),
+    );
+    fs::create_dir_all(empty.join("steamapps")).unwrap();
+
+    let steamapps = games.join("steamapps");
+    write(
+        steamapps.join("appmanifest_418530.acf"),
+        &app_manifest("12345678", "4"),
+    );
+    let install_path = steamapps.join("common").join("Spelunky 2");
+    fs::create_dir_all(&install_path).unwrap();
+    let prefix = steamapps.join("compatdata").join("418530").join("pfx");
+    fs::create_dir_all(&prefix).unwrap();
+
+    assert_eq!(
+        library_folders(&root).unwrap(),
+        vec![root.clone(), games.clone(), empty]
+    );
+
+    let expected = Install {
+        path: install_path,
+        library: games,
+        build_id: Some(12345678),
+        last_updated: Some(1700000000),
+        fully_installed: true,
+        proton_prefix: Some(prefix),
+    };
+    // The same root twice only finds the install once
+    assert_eq!(
+        find_installs(&[root.clone(), root, dir.path().join("missing")]),
+        vec![expected]
+    );
+}
+
+#[test]
+fn test_old_library_format() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("Steam");
+    let library = dir.path().join("Library");
+    write(
+        root.join("steamapps").join("libraryfolders.vdf"),
+        &format!(
+            r#""LibraryFolders"
+{{
+	"TimeNextStatsReport"		"1700000000"
+	"ContentStatsID"		"-1234"
+	"1"		"{}"
+}}
+"#,
+            vdf_path(&library)
+        ),
+    );
+    // Updating, and missing the game's directory
+    write(
+        root.join("steamapps").join("appmanifest_418530.acf"),
+        &app_manifest("1", "1026"),
+    );
+    let steamapps = library.join("steamapps");
+    write(
+        steamapps.join("appmanifest_418530.acf"),
+        &app_manifest("2", "1026"),
+    );
+    fs::create_dir_all(steamapps.join("common").join("Spelunky 2")).unwrap();
+
+    assert_eq!(
+        library_folders(&root).unwrap(),
+        vec![root.clone(), library.clone()]
+    );
+    let installs = find_installs(&[root]);
+    assert_eq!(installs.len(), 1);
+    assert_eq!(installs[0].library, library);
+    assert_eq!(installs[0].build_id, Some(2));
+    assert!(!installs[0].fully_installed);
+    assert_eq!(installs[0].proton_prefix, None);
+}
+
+#[test]
+fn test_auto_select() {
+    let install = |path: &str, fully_installed| Install {
+        path: PathBuf::from(path),
+        library: PathBuf::from("library"),
+        build_id: None,
+        last_updated: None,
+        fully_installed,
+        proton_prefix: None,
+    };
+    let complete = install("complete", true);
+    let downloading = install("downloading", false);
+
+    assert_eq!(auto_select(&[]), None);
+    assert_eq!(auto_select(std::slice::from_ref(&downloading)), None);
+    assert_eq!(
+        auto_select(&[downloading.clone(), complete.clone()]),
+        Some(&complete)
+    );
+    // Several complete installs are ambiguous
+    assert_eq!(
+        auto_select(&[complete.clone(), downloading, install("other", true)]),
+        None
+    );
+}
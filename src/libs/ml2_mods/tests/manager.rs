@@ -4,10 +4,14 @@ use std::time::Duration;
 
 use anyhow::anyhow;
 use ml2_mods::{
-    data::{Manifest, ManifestModFile, Mod},
+    data::{BackupReason, Change, Manifest, ManifestModFile, Mod},
     local::{
-        constants::{MANIFEST_FILENAME, MODS_SUBPATH, MOD_METADATA_SUBPATH},
+        constants::{
+            DISABLED_MODS_SUBPATH, GAME_SAVE_FILENAME, LOAD_ORDER_FILENAME, MANIFEST_FILENAME,
+            MODS_SUBPATH, MOD_METADATA_SUBPATH, PACK_SAVE_FILENAME, SAVE_BACKUPS_SUBPATH,
+        },
         disk::DiskMods,
+        paths::subpath,
         Error as LocalError,
     },
     manager::{Error, ModManager, ModManagerHandle, ModSource, DEFAULT_RECEIVING_INTERVAL},
@@ -24,6 +28,8 @@ fn make_provincial_mod() -> Mod {
     Mod {
         id: "provincial".to_string(),
         manifest: None,
+        enabled: true,
+        load_order: Some(1),
     }
 }
 
@@ -43,22 +49,31 @@ fn make_remote_control_mod() -> Mod {
                         .to_string(),
             },
         }),
+        enabled: true,
+        load_order: Some(0),
     }
 }
 
 fn testdata_install_dir() -> String {
     PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join(r"tests\data\install_dir")
+        .join(subpath(&["tests", "data", "install_dir"]))
         .to_str()
         .unwrap()
         .into()
 }
 
 fn setup(install_path: &str) -> ModManagerHandle {
-    let local_mods = DiskMods::new(install_path);
+    setup_with(DiskMods::new(install_path))
+}
+
+fn setup_with(local_mods: DiskMods) -> ModManagerHandle {
+    setup_with_changes(local_mods).0
+}
+
+fn setup_with_changes(local_mods: DiskMods) -> (ModManagerHandle, broadcast::Receiver<Change>) {
     // These are "dangling" because we don't wire up a full system
     let (_detected_tx, detected_rx) = broadcast::channel(10);
-    let (changes_tx, _changes_rx) = broadcast::channel(10);
+    let (changes_tx, changes_rx) = broadcast::channel(10);
     let (manager, handle): (ModManager<HttpApiMods, DiskMods>, ModManagerHandle) = ModManager::new(
         None,
         local_mods,
@@ -68,7 +83,7 @@ fn setup(install_path: &str) -> ModManagerHandle {
     );
     let toplevel = Toplevel::new().start("ModManager", manager.into_subsystem());
     tokio::spawn(toplevel.handle_shutdown_requests(Duration::from_millis(1000)));
-    handle
+    (handle, changes_rx)
 }
 
 #[tokio::test]
@@ -108,6 +123,42 @@ async fn test_list_nonexistent() {
     assert_eq!(resp, vec![]);
 }
 
+#[tokio::test]
+async fn test_case_insensitive() {
+    let dir = tempdir().unwrap();
+    let mod_path = dir.path().join("mods").join("packs").join("Provincial");
+    fs::create_dir_all(&mod_path).await.unwrap();
+    touch_file(mod_path.join("main.lua")).await;
+
+    let handle = setup(dir.path().to_str().unwrap());
+
+    let resp = handle.get("provincial").await.unwrap();
+    let expected = Mod {
+        id: "Provincial".to_string(),
+        load_order: Some(0),
+        ..make_provincial_mod()
+    };
+    assert_eq!(resp, expected);
+
+    let resp = handle.list().await.unwrap();
+    assert_eq!(resp.len(), 1);
+    assert_eq!(resp[0].id, "Provincial");
+
+    // Moved under its own name, whatever the spelling asked for
+    let disabled = handle.disable("PROVINCIAL").await.unwrap();
+    assert_eq!(disabled.id, "Provincial");
+    // Under the existing "mods" directory rather than a new "Mods"
+    let disabled_path = dir
+        .path()
+        .join(subpath(&["mods", ".ml", "disabled-packs", "Provincial"]));
+    assert!(fs::metadata(&disabled_path).await.is_ok());
+    assert_eq!(handle.enable("pRoViNcIaL").await.unwrap().id, "Provincial");
+    assert!(fs::metadata(&mod_path).await.is_ok());
+
+    handle.remove("PROVINCIAL").await.unwrap();
+    assert!(fs::metadata(&mod_path).await.is_err());
+}
+
 async fn touch_file(path: PathBuf) {
     let file = OpenOptions::new()
         .create(true)
@@ -125,13 +176,13 @@ async fn test_remove() {
 
     let handle = setup(dir.path().to_str().unwrap());
 
-    let mod_path = dir.path().join(MODS_SUBPATH).join(mod_id);
+    let mod_path = dir.path().join(subpath(MODS_SUBPATH)).join(mod_id);
     fs::create_dir_all(mod_path.clone()).await.unwrap();
 
     let lua_path = mod_path.join("main.lua");
     touch_file(lua_path.clone()).await;
 
-    let metadata_dir_path = dir.path().join(MOD_METADATA_SUBPATH).join(mod_id);
+    let metadata_dir_path = dir.path().join(subpath(MOD_METADATA_SUBPATH)).join(mod_id);
     fs::create_dir_all(metadata_dir_path.clone()).await.unwrap();
 
     let manifest_path = mod_path.join(MANIFEST_FILENAME);
@@ -164,7 +215,7 @@ async fn test_remove() {
 
 async fn install_from_local_sources(handle: &ModManagerHandle, source_file: &str, dest_id: &str) {
     let source_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .join(r"tests\data\local_sources")
+        .join(subpath(&["tests", "data", "local_sources"]))
         .join(source_file)
         .as_os_str()
         .to_str()
@@ -177,17 +228,25 @@ async fn install_from_local_sources(handle: &ModManagerHandle, source_file: &str
         })
         .await
         .unwrap();
+    // New mods go at the end of the load order
+    let last = handle.load_order().await.unwrap().len() - 1;
     assert_eq!(
         resp,
         Mod {
             id: dest_id.to_string(),
             manifest: None,
+            enabled: true,
+            load_order: Some(last),
         }
     )
 }
 
 async fn assert_exits_in(dir: &TempDir, mod_id: &str, path: &str) {
-    let path = dir.path().join(MODS_SUBPATH).join(mod_id).join(path);
+    let path = dir
+        .path()
+        .join(subpath(MODS_SUBPATH))
+        .join(mod_id)
+        .join(path);
     fs::metadata(&path)
         .await
         .map_err(|e| anyhow!("checking for {path:?}: {e:?}"))
@@ -244,3 +303,311 @@ async fn test_install_locall() {
     install_from_local_sources(&handle, "unicode.zip", mod_id).await;
     assert_exits_in(&dir, mod_id, "unicode👀.txt").await;
 }
+
+#[tokio::test]
+async fn test_save_backups() {
+    let dir = tempfile::tempdir().unwrap();
+    let install_path = dir.path().to_str().unwrap();
+    let handle = setup_with(DiskMods::new(install_path).with_max_backups(2));
+
+    let game_save = dir.path().join(GAME_SAVE_FILENAME);
+    let pack_save = dir
+        .path()
+        .join(subpath(MODS_SUBPATH))
+        .join("pack")
+        .join(PACK_SAVE_FILENAME);
+    let source_path = dir.path().join("main.lua");
+    touch_file(source_path.clone()).await;
+    let source = ModSource::Local {
+        source_path: source_path.to_str().unwrap().to_string(),
+        dest_id: "pack".to_string(),
+    };
+
+    // Nothing to back up yet
+    handle.install(&source).await.unwrap();
+    assert_eq!(handle.list_backups().await.unwrap(), vec![]);
+
+    fs::write(&game_save, "first").await.unwrap();
+    fs::write(&pack_save, "pack first").await.unwrap();
+    handle.update(&source).await.unwrap();
+    assert_eq!(fs::read_to_string(&pack_save).await.unwrap(), "pack first");
+
+    fs::write(&game_save, "second").await.unwrap();
+    fs::write(&pack_save, "pack second").await.unwrap();
+    handle.update(&source).await.unwrap();
+
+    let backups = handle.list_backups().await.unwrap();
+    assert_eq!(backups.len(), 2);
+    let oldest = backups[1].clone();
+    assert_eq!(oldest.reason, BackupReason::Update);
+    assert_eq!(oldest.mod_id, "pack");
+    assert_eq!(oldest.files.len(), 2);
+
+    handle.restore_backup(&oldest.id).await.unwrap();
+    assert_eq!(fs::read_to_string(&game_save).await.unwrap(), "first");
+    assert_eq!(fs::read_to_string(&pack_save).await.unwrap(), "pack first");
+
+    // Restoring backed up the current saves, rotating out the restored backup
+    let backups = handle.list_backups().await.unwrap();
+    assert_eq!(backups.len(), 2);
+    assert_eq!(backups[0].reason, BackupReason::Restore);
+    assert!(backups.iter().all(|b| b.id != oldest.id));
+
+    handle.remove("pack").await.unwrap();
+    let backups = handle.list_backups().await.unwrap();
+    assert_eq!(backups[0].reason, BackupReason::Remove);
+
+    let err = handle.restore_backup("does-not-exist").await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::BackupNotFoundError(LocalError::BackupNotFound(_))
+    ));
+
+    // Operations that can't go ahead don't rotate out backups
+    let before = handle.list_backups().await.unwrap();
+    handle.install(&source).await.unwrap();
+    assert!(matches!(
+        handle.install(&source).await.unwrap_err(),
+        Error::ModExistsError(_)
+    ));
+    assert!(matches!(
+        handle.remove("does-not-exist").await.unwrap_err(),
+        Error::ModNotFoundError(_)
+    ));
+    let missing = ModSource::Local {
+        source_path: source_path.to_str().unwrap().to_string(),
+        dest_id: "does-not-exist".to_string(),
+    };
+    assert!(matches!(
+        handle.update(&missing).await.unwrap_err(),
+        Error::ModNotFoundError(_)
+    ));
+    let after = handle.list_backups().await.unwrap();
+    assert_eq!(after.len(), 2);
+    assert_eq!(after[1], before[0]);
+    assert_eq!(after[0].reason, BackupReason::Install);
+
+    // Stray files next to the backups are ignored
+    let backups_dir = dir.path().join(subpath(SAVE_BACKUPS_SUBPATH));
+    fs::write(backups_dir.join("notes.txt"), "").await.unwrap();
+    assert_eq!(handle.list_backups().await.unwrap(), after);
+}
+
+#[tokio::test]
+async fn test_disabled_pack_backups() {
+    let dir = tempfile::tempdir().unwrap();
+    let handle = setup_with(DiskMods::new(dir.path().to_str().unwrap()));
+
+    let source_path = dir.path().join("main.lua");
+    touch_file(source_path.clone()).await;
+    for dest_id in ["a", "b"] {
+        let source = ModSource::Local {
+            source_path: source_path.to_str().unwrap().to_string(),
+            dest_id: dest_id.to_string(),
+        };
+        handle.install(&source).await.unwrap();
+        let save = dir
+            .path()
+            .join(subpath(MODS_SUBPATH))
+            .join(dest_id)
+            .join(PACK_SAVE_FILENAME);
+        fs::write(save, format!("{dest_id} save")).await.unwrap();
+    }
+    handle.disable("a").await.unwrap();
+
+    // Saves of disabled packs are backed up before they're removed
+    handle.remove("a").await.unwrap();
+    let backup = handle.list_backups().await.unwrap().remove(0);
+    assert_eq!(
+        backup.files,
+        vec![
+            "Mods/.ml/disabled-packs/a/save.dat".to_string(),
+            "Mods/Packs/b/save.dat".to_string(),
+        ]
+    );
+
+    // Saves go back to wherever the pack is now
+    let b_save = dir
+        .path()
+        .join(subpath(MODS_SUBPATH))
+        .join("b")
+        .join(PACK_SAVE_FILENAME);
+    fs::write(&b_save, "b changed").await.unwrap();
+    handle.disable("b").await.unwrap();
+    handle.restore_backup(&backup.id).await.unwrap();
+    let disabled_b_save = dir
+        .path()
+        .join(subpath(DISABLED_MODS_SUBPATH))
+        .join("b")
+        .join(PACK_SAVE_FILENAME);
+    assert_eq!(
+        fs::read_to_string(&disabled_b_save).await.unwrap(),
+        "b save"
+    );
+    assert!(fs::metadata(&b_save).await.is_err());
+    assert!(
+        fs::metadata(dir.path().join(subpath(DISABLED_MODS_SUBPATH)).join("a"))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_enable_disable() {
+    let dir = tempfile::tempdir().unwrap();
+    let (handle, mut changes_rx) =
+        setup_with_changes(DiskMods::new(dir.path().to_str().unwrap()).with_max_backups(0));
+
+    let source_path = dir.path().join("main.lua");
+    touch_file(source_path.clone()).await;
+    let source = ModSource::Local {
+        source_path: source_path.to_str().unwrap().to_string(),
+        dest_id: "pack".to_string(),
+    };
+    handle.install(&source).await.unwrap();
+    let enabled_path = dir.path().join(subpath(MODS_SUBPATH)).join("pack");
+    let disabled_path = dir.path().join(subpath(DISABLED_MODS_SUBPATH)).join("pack");
+    fs::write(enabled_path.join(PACK_SAVE_FILENAME), "save")
+        .await
+        .unwrap();
+    let metadata_path = dir.path().join(subpath(MOD_METADATA_SUBPATH)).join("pack");
+    fs::create_dir_all(&metadata_path).await.unwrap();
+    while changes_rx.try_recv().is_ok() {}
+
+    let disabled = handle.disable("pack").await.unwrap();
+    assert!(!disabled.enabled);
+    assert_eq!(
+        changes_rx.try_recv().unwrap(),
+        Change::SetEnabled {
+            id: "pack".to_string(),
+            enabled: false,
+        }
+    );
+    assert!(fs::metadata(&enabled_path).await.is_err());
+    assert_eq!(
+        fs::read_to_string(disabled_path.join(PACK_SAVE_FILENAME))
+            .await
+            .unwrap(),
+        "save"
+    );
+    assert!(fs::metadata(&metadata_path).await.is_ok());
+    assert_eq!(handle.get("pack").await.unwrap(), disabled);
+    assert_eq!(handle.list().await.unwrap(), vec![disabled.clone()]);
+
+    // Disabled mods still count when installing, and stay disabled when updated
+    let err = handle.install(&source).await.unwrap_err();
+    assert!(matches!(err, Error::ModExistsError(_)));
+    assert!(!handle.update(&source).await.unwrap().enabled);
+    assert_eq!(
+        fs::read_to_string(disabled_path.join(PACK_SAVE_FILENAME))
+            .await
+            .unwrap(),
+        "save"
+    );
+
+    let enabled = handle.enable("pack").await.unwrap();
+    assert!(enabled.enabled);
+    assert!(fs::metadata(&disabled_path).await.is_err());
+    assert!(fs::metadata(enabled_path.join(PACK_SAVE_FILENAME))
+        .await
+        .is_ok());
+    // Enabling again does nothing
+    assert_eq!(handle.enable("pack").await.unwrap(), enabled);
+
+    // A stray disabled copy spelled differently is hidden by the enabled one
+    let stray_path = dir.path().join(subpath(DISABLED_MODS_SUBPATH)).join("PACK");
+    fs::create_dir_all(&stray_path).await.unwrap();
+    assert_eq!(handle.list().await.unwrap(), vec![enabled.clone()]);
+    fs::remove_dir_all(&stray_path).await.unwrap();
+
+    handle.disable("pack").await.unwrap();
+    handle.remove("pack").await.unwrap();
+    assert!(fs::metadata(&disabled_path).await.is_err());
+    let err = handle.disable("pack").await.unwrap_err();
+    assert!(matches!(err, Error::ModNotFoundError(_)));
+}
+
+#[tokio::test]
+async fn test_load_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let (handle, mut changes_rx) =
+        setup_with_changes(DiskMods::new(dir.path().to_str().unwrap()).with_max_backups(0));
+    let packs_path = dir.path().join(subpath(MODS_SUBPATH));
+    let load_order_path = packs_path.join(LOAD_ORDER_FILENAME);
+    for id in ["a", "b", "c"] {
+        fs::create_dir_all(packs_path.join(id)).await.unwrap();
+    }
+    // Lines are matched ignoring case, and ones for missing packs are dropped
+    fs::write(&load_order_path, "C\n--gone\n--a\n")
+        .await
+        .unwrap();
+
+    let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    // Playlunky skips packs marked with --, so they aren't enabled
+    assert_eq!(handle.load_order().await.unwrap(), ids(&["c", "b"]));
+    let positions: Vec<_> = handle
+        .list()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|m| (m.id, m.enabled, m.load_order))
+        .collect();
+    assert_eq!(
+        positions,
+        vec![
+            ("a".to_string(), false, None),
+            ("b".to_string(), true, Some(1)),
+            ("c".to_string(), true, Some(0)),
+        ]
+    );
+
+    let new = handle.set_load_order(&ids(&["b"])).await.unwrap();
+    assert_eq!(new, ids(&["b", "c"]));
+    assert_eq!(
+        changes_rx.try_recv().unwrap(),
+        Change::LoadOrder { ids: new.clone() }
+    );
+    assert_eq!(handle.get("b").await.unwrap().load_order, Some(0));
+    // Playlunky's unselected marker is kept
+    assert_eq!(
+        fs::read_to_string(&load_order_path).await.unwrap(),
+        "b\nc\n--a\n"
+    );
+
+    let err = handle.set_load_order(&ids(&["gone"])).await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ModNotFoundError(LocalError::NotFound(_))
+    ));
+    // Ids are matched ignoring case, keeping the installed spelling
+    let new = handle.set_load_order(&ids(&["C", "B"])).await.unwrap();
+    assert_eq!(new, ids(&["c", "b"]));
+
+    // Installing appends, while disabling and removing drop the mod from the file
+    let source_path = dir.path().join("main.lua");
+    touch_file(source_path.clone()).await;
+    let source = ModSource::Local {
+        source_path: source_path.to_str().unwrap().to_string(),
+        dest_id: "d".to_string(),
+    };
+    assert_eq!(handle.install(&source).await.unwrap().load_order, Some(2));
+    assert_eq!(handle.disable("c").await.unwrap().load_order, None);
+    handle.remove("b").await.unwrap();
+    assert_eq!(
+        fs::read_to_string(&load_order_path).await.unwrap(),
+        "--a\nd\n"
+    );
+
+    // Enabled mods go back at the end
+    assert_eq!(handle.enable("c").await.unwrap().load_order, Some(1));
+    assert_eq!(handle.load_order().await.unwrap(), ids(&["d", "c"]));
+
+    // Enabling a marked mod unmarks it where it is
+    let a = handle.enable("a").await.unwrap();
+    assert!(a.enabled);
+    assert_eq!(a.load_order, Some(0));
+    assert_eq!(
+        fs::read_to_string(&load_order_path).await.unwrap(),
+        "a\nd\nc\n"
+    );
+}
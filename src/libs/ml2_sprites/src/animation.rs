@@ -0,0 +1,222 @@
+//! Renders entity animations to animated GIFs and PNGs.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use ml2_types::Animation;
+
+use crate::sheet::SheetLayout;
+use crate::SpriteError;
+
+/// Animation intervals are counted in game frames.
+const GAME_FPS: u16 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    /// Animated PNG, which keeps full alpha and exact timing.
+    Apng,
+}
+
+impl AnimationFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+        }
+    }
+}
+
+/// The frames of one animation, cut from a sheet.
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    /// The animation's id within its entity.
+    pub id: i64,
+    pub frames: Vec<RgbaImage>,
+    /// Game frames each frame is shown for.
+    pub interval: u16,
+    /// Whether the animation loops, rather than stopping on its last frame.
+    pub repeat: bool,
+}
+
+impl AnimationClip {
+    pub fn new(
+        layout: &SheetLayout,
+        sheet: &RgbaImage,
+        id: i64,
+        animation: &Animation,
+    ) -> Result<Self, SpriteError> {
+        let frames = (0..animation.count.max(0))
+            .map(|frame| {
+                let index = animation.texture + frame;
+                let index =
+                    usize::try_from(index).map_err(|_| SpriteError::TileOutOfRange(index))?;
+                layout.slice(sheet, index)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            id,
+            frames,
+            interval: u16::try_from(animation.interval.max(1)).unwrap_or(u16::MAX),
+            repeat: animation.repeat != 0,
+        })
+    }
+
+    /// How long each frame is shown for.
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f64(f64::from(self.interval) / f64::from(GAME_FPS))
+    }
+
+    pub fn write<W: Write>(&self, format: AnimationFormat, writer: W) -> Result<(), SpriteError> {
+        match format {
+            AnimationFormat::Gif => self.write_gif(writer),
+            AnimationFormat::Apng => self.write_apng(writer),
+        }
+    }
+
+    /// GIF delays are in hundredths of a second, so timing is rounded.
+    pub fn write_gif<W: Write>(&self, writer: W) -> Result<(), SpriteError> {
+        let mut encoder = GifEncoder::new(writer);
+        encoder.set_repeat(if self.repeat {
+            Repeat::Infinite
+        } else {
+            Repeat::Finite(0)
+        })?;
+        let delay =
+            Delay::from_numer_denom_ms(u32::from(self.interval) * 1000, u32::from(GAME_FPS));
+        encoder.encode_frames(
+            self.frames
+                .iter()
+                .map(|frame| Frame::from_parts(frame.clone(), 0, 0, delay)),
+        )?;
+        Ok(())
+    }
+
+    pub fn write_apng<W: Write>(&self, writer: W) -> Result<(), SpriteError> {
+        let (width, height) = self
+            .frames
+            .first()
+            .map(RgbaImage::dimensions)
+            .unwrap_or_default();
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.frames.len() as u32, if self.repeat { 0 } else { 1 })?;
+        encoder.set_frame_delay(self.interval, GAME_FPS)?;
+
+        let mut writer = encoder.write_header()?;
+        for frame in &self.frames {
+            writer.write_image_data(frame.as_raw())?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+/// Cuts each of an entity's animations from its sheet, in id order.
+pub fn entity_clips(entity: &str, sheet: &RgbaImage) -> Result<Vec<AnimationClip>, SpriteError> {
+    let (data, layout) = SheetLayout::for_entity(entity)?;
+
+    let mut animations: Vec<(i64, &Animation)> = data
+        .animations
+        .iter()
+        .filter_map(|(id, animation)| Some((id.parse().ok()?, animation)))
+        .collect();
+    animations.sort_by_key(|(id, _)| *id);
+    animations
+        .into_iter()
+        .filter(|(_, animation)| animation.count > 0)
+        .map(|(id, animation)| AnimationClip::new(&layout, sheet, id, animation))
+        .collect()
+}
+
+/// Writes each of an entity's animations to `dir`, e.g. `mons_snake_2.gif`, using the sheet
+/// extracted to `extract_dir`.
+pub fn export_animations(
+    entity: &str,
+    extract_dir: &Path,
+    dir: &Path,
+    format: AnimationFormat,
+) -> Result<Vec<PathBuf>, SpriteError> {
+    let (_, layout) = SheetLayout::for_entity(entity)?;
+    let sheet = layout.load(extract_dir)?;
+
+    std::fs::create_dir_all(dir)?;
+    let name = entity.strip_prefix("ENT_TYPE_").unwrap_or(entity);
+    let name = name.to_ascii_lowercase();
+    let mut paths = Vec::new();
+    for clip in entity_clips(entity, &sheet)? {
+        let path = dir.join(format!("{name}_{}.{}", clip.id, format.extension()));
+        clip.write(format, BufWriter::new(File::create(&path)?))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use image::codecs::gif::GifDecoder;
+    use image::codecs::png::PngDecoder;
+    use image::{AnimationDecoder, Rgba, RgbaImage};
+
+    use super::{entity_clips, AnimationFormat};
+
+    fn snake_sheet() -> RgbaImage {
+        // Color each of the first row's tiles by its column
+        let mut sheet = RgbaImage::new(2048, 2048);
+        for (x, y, pixel) in sheet.enumerate_pixels_mut() {
+            if y < 128 {
+                *pixel = Rgba([(x / 128) as u8 * 10, 0, 0, 255]);
+            }
+        }
+        sheet
+    }
+
+    #[test]
+    fn test_clips() {
+        let clips = entity_clips("ENT_TYPE_MONS_SNAKE", &snake_sheet()).unwrap();
+        let ids: Vec<i64> = clips.iter().map(|clip| clip.id).collect();
+        assert_eq!(ids, vec![0, 2, 19]);
+
+        // Walking: 6 frames from tile 0, 5 game frames each, looping
+        let walk = &clips[1];
+        assert_eq!(walk.frames.len(), 6);
+        assert_eq!(walk.frames[3].get_pixel(0, 0), &Rgba([30, 0, 0, 255]));
+        assert_eq!(walk.interval, 5);
+        assert!(walk.repeat);
+        assert_eq!(walk.frame_duration(), Duration::from_secs_f64(5.0 / 60.0));
+    }
+
+    #[test]
+    fn test_write() {
+        let clips = entity_clips("ENT_TYPE_MONS_SNAKE", &snake_sheet()).unwrap();
+        let walk = &clips[1];
+
+        let mut gif = Vec::new();
+        walk.write(AnimationFormat::Gif, &mut gif).unwrap();
+        let frames = GifDecoder::new(&gif[..])
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 6);
+        assert_eq!(frames[0].delay().numer_denom_ms(), (80, 1));
+
+        let mut apng = Vec::new();
+        walk.write(AnimationFormat::Apng, &mut apng).unwrap();
+        let frames = PngDecoder::new(&apng[..])
+            .unwrap()
+            .apng()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 6);
+        assert_eq!(frames[2].buffer().get_pixel(0, 0), &Rgba([20, 0, 0, 255]));
+    }
+}
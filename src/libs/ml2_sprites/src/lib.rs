@@ -0,0 +1,55 @@
+mod animation;
+mod overlay;
+mod sheet;
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub use animation::{entity_clips, export_animations, AnimationClip, AnimationFormat};
+pub use overlay::{entity_overlay, export_overlay, overlay, Hitbox};
+pub use sheet::{SheetLayout, TileInfo, TileUser};
+
+#[derive(Error, Debug)]
+pub enum SpriteError {
+    #[error("IoError")]
+    IoError(#[from] std::io::Error),
+
+    #[error("ImageError")]
+    ImageError(#[from] image::ImageError),
+
+    #[error("PngError")]
+    PngError(#[from] png::EncodingError),
+
+    #[error("Unknown entity {0}")]
+    UnknownEntity(String),
+
+    #[error("Unknown texture {0}")]
+    UnknownTexture(i64),
+
+    #[error("Tile {0} is outside the sheet")]
+    TileOutOfRange(i64),
+
+    #[error("Sheet is {found:?} but its texture needs at least {expected:?}")]
+    SheetTooSmall {
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+
+    #[error("Tile {index} is {found:?} but should be {expected:?}")]
+    TileSizeMismatch {
+        index: usize,
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+
+    #[error("Tile {index} is in both {first:?} and {second:?}")]
+    DuplicateTile {
+        index: usize,
+        first: PathBuf,
+        second: PathBuf,
+    },
+
+    #[error("A {size:?} frame is too large to draw at a scale of {scale}")]
+    TooLarge { size: (u32, u32), scale: u32 },
+}
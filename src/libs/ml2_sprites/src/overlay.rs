@@ -0,0 +1,235 @@
+//! Debug overlays that show an entity's hitbox and attach point over its sprite.
+
+use std::path::Path;
+
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use ml2_types::Entity;
+
+use crate::sheet::SheetLayout;
+use crate::SpriteError;
+
+const SPRITE_BOUNDS_COLOR: Rgba<u8> = Rgba([128, 128, 128, 255]);
+const HITBOX_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const ORIGIN_COLOR: Rgba<u8> = Rgba([0, 128, 255, 255]);
+const ATTACH_COLOR: Rgba<u8> = Rgba([0, 255, 0, 255]);
+
+/// Pixels per tile in game textures, for entities that don't say how big they're drawn.
+const GAME_TILE_SIZE: f64 = 128.0;
+
+/// Most pixels in an overlay, 256 MiB of RGBA.
+const MAX_PIXELS: u64 = 64 << 20;
+
+/// An entity's collision rectangle, in tiles relative to its position, with y pointing up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hitbox {
+    pub left: f64,
+    pub right: f64,
+    pub bottom: f64,
+    pub top: f64,
+}
+
+impl Hitbox {
+    /// `side` and `up_plus_down` are half the width and height, and `up_minus_down` moves the
+    /// box up from the entity's position.
+    pub fn of(entity: &Entity) -> Self {
+        let collision = &entity.rect_collision;
+        Self {
+            left: -collision.side,
+            right: collision.side,
+            bottom: collision.up_minus_down - collision.up_plus_down,
+            top: collision.up_minus_down + collision.up_plus_down,
+        }
+    }
+}
+
+/// Draws an entity's sprite bounds, hitbox, position and attach point over one of its frames,
+/// scaled up by `scale` so thin lines don't hide the art.
+///
+/// The frame is centered on the entity's position and stretched over its `width` and `height`
+/// in tiles, as the game draws it. Entities with no size, like portals, are drawn at the frame's
+/// own size.
+pub fn overlay(entity: &Entity, frame: &RgbaImage, scale: u32) -> Result<RgbaImage, SpriteError> {
+    let scale = scale.max(1);
+    let too_large = || SpriteError::TooLarge {
+        size: frame.dimensions(),
+        scale,
+    };
+    let scaled_width = frame.width().checked_mul(scale).ok_or_else(too_large)?;
+    let scaled_height = frame.height().checked_mul(scale).ok_or_else(too_large)?;
+    if u64::from(scaled_width) * u64::from(scaled_height) > MAX_PIXELS {
+        return Err(too_large());
+    }
+    let mut image = imageops::resize(frame, scaled_width, scaled_height, FilterType::Nearest);
+    let (width, height) = (f64::from(image.width()), f64::from(image.height()));
+    let tiles = |size: f64, frame_pixels: u32| {
+        if size > 0.0 {
+            size
+        } else {
+            f64::from(frame_pixels) / GAME_TILE_SIZE
+        }
+    };
+    let pixels_per_tile_x = width / tiles(entity.width, frame.width());
+    let pixels_per_tile_y = height / tiles(entity.height, frame.height());
+    let to_pixels = |x: f64, y: f64| {
+        (
+            width / 2.0 + x * pixels_per_tile_x,
+            height / 2.0 - y * pixels_per_tile_y,
+        )
+    };
+
+    draw_rect(
+        &mut image,
+        (0.0, 0.0),
+        (width - 1.0, height - 1.0),
+        SPRITE_BOUNDS_COLOR,
+    );
+
+    let hitbox = Hitbox::of(entity);
+    let top_left = to_pixels(hitbox.left, hitbox.top);
+    let (right, bottom) = to_pixels(hitbox.right, hitbox.bottom);
+    draw_rect(
+        &mut image,
+        top_left,
+        (right - 1.0, bottom - 1.0),
+        HITBOX_COLOR,
+    );
+
+    let arm = f64::from(2 * scale + 2);
+    draw_cross(&mut image, to_pixels(0.0, 0.0), arm, ORIGIN_COLOR);
+    draw_cross(
+        &mut image,
+        to_pixels(entity.attach_offset_x, entity.attach_offset_y),
+        arm,
+        ATTACH_COLOR,
+    );
+    Ok(image)
+}
+
+/// Renders the overlay on an entity's idle frame, cut from its sheet.
+pub fn entity_overlay(
+    entity: &str,
+    sheet: &RgbaImage,
+    scale: u32,
+) -> Result<RgbaImage, SpriteError> {
+    let (data, layout) = SheetLayout::for_entity(entity)?;
+    let index = layout
+        .first_frame(entity)
+        .ok_or_else(|| SpriteError::UnknownEntity(entity.into()))?;
+    overlay(data, &layout.slice(sheet, index)?, scale)
+}
+
+/// Writes an entity's overlay as a PNG, using the sheet extracted to `extract_dir`.
+pub fn export_overlay(
+    entity: &str,
+    extract_dir: &Path,
+    path: &Path,
+    scale: u32,
+) -> Result<(), SpriteError> {
+    let (_, layout) = SheetLayout::for_entity(entity)?;
+    let sheet = layout.load(extract_dir)?;
+    entity_overlay(entity, &sheet, scale)?.save(path)?;
+    Ok(())
+}
+
+fn put(image: &mut RgbaImage, x: f64, y: f64, color: Rgba<u8>) {
+    let (x, y) = (x.round(), y.round());
+    if x >= 0.0 && y >= 0.0 && x < f64::from(image.width()) && y < f64::from(image.height()) {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+/// Rounds a coordinate and clamps it to one pixel either side of `0..size`, so edges that are
+/// off the image stay off it without looping over far away pixels.
+fn clamp_to(coordinate: f64, size: u32) -> i64 {
+    coordinate.round().clamp(-1.0, f64::from(size)) as i64
+}
+
+fn put_at(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>) {
+    if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
+        if x < image.width() && y < image.height() {
+            image.put_pixel(x, y, color);
+        }
+    }
+}
+
+fn draw_rect(image: &mut RgbaImage, (x0, y0): (f64, f64), (x1, y1): (f64, f64), color: Rgba<u8>) {
+    let (x0, x1) = (clamp_to(x0, image.width()), clamp_to(x1, image.width()));
+    let (y0, y1) = (clamp_to(y0, image.height()), clamp_to(y1, image.height()));
+    for x in x0..=x1 {
+        put_at(image, x, y0, color);
+        put_at(image, x, y1, color);
+    }
+    for y in y0..=y1 {
+        put_at(image, x0, y, color);
+        put_at(image, x1, y, color);
+    }
+}
+
+fn draw_cross(image: &mut RgbaImage, (x, y): (f64, f64), arm: f64, color: Rgba<u8>) {
+    let mut offset = -arm;
+    while offset <= arm {
+        put(image, x + offset, y, color);
+        put(image, x, y + offset, color);
+        offset += 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+    use ml2_entity_data::ENTITIES;
+
+    use super::{entity_overlay, overlay, Hitbox, ATTACH_COLOR, HITBOX_COLOR};
+    use crate::SpriteError;
+
+    #[test]
+    fn test_hitbox() {
+        let snake = &ENTITIES["ENT_TYPE_MONS_SNAKE"];
+        let hitbox = Hitbox::of(snake);
+        assert_eq!((hitbox.left, hitbox.right), (-0.4, 0.4));
+        assert!((hitbox.bottom - -0.55).abs() < 1e-6);
+        assert!((hitbox.top - 0.15).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_overlay() {
+        // The snake's 1.25 tiles are drawn with 128 pixels, so a tile is 102.4 pixels
+        let sheet = RgbaImage::new(2048, 2048);
+        let image = entity_overlay("ENT_TYPE_MONS_SNAKE", &sheet, 1).unwrap();
+        assert_eq!(image.dimensions(), (128, 128));
+
+        // Hitbox left edge: 64 - 0.4 * 102.4 = 23, from y = 64 - 0.15 * 102.4 = 49
+        assert_eq!(image.get_pixel(23, 60), &HITBOX_COLOR);
+        assert_eq!(image.get_pixel(22, 60), &Rgba([0, 0, 0, 0]));
+        // Attach point: x = 64 + 0.25 * 102.4 = 90, y = 64
+        assert_eq!(image.get_pixel(90, 64), &ATTACH_COLOR);
+
+        let scaled = entity_overlay("ENT_TYPE_MONS_SNAKE", &sheet, 2).unwrap();
+        assert_eq!(scaled.dimensions(), (256, 256));
+
+        let snake = &ENTITIES["ENT_TYPE_MONS_SNAKE"];
+        assert!(matches!(
+            overlay(snake, &RgbaImage::new(128, 128), 1 << 26),
+            Err(SpriteError::TooLarge {
+                size: (128, 128),
+                scale: 67108864,
+            })
+        ));
+        // 128 * 65 = 8320 pixels square, just over the cap of 8192 square
+        assert!(matches!(
+            overlay(snake, &RgbaImage::new(128, 128), 65),
+            Err(SpriteError::TooLarge { scale: 65, .. })
+        ));
+    }
+
+    #[test]
+    fn test_overlay_without_size() {
+        // Portals have no width or height, so the frame's own size is used
+        let portal = &ENTITIES["ENT_TYPE_FX_PORTAL"];
+        assert_eq!((portal.width, portal.height), (0.0, 0.0));
+        let sheet = RgbaImage::new(1920, 2816);
+        let image = entity_overlay("ENT_TYPE_FX_PORTAL", &sheet, 1).unwrap();
+        assert!(image.pixels().any(|pixel| pixel == &HITBOX_COLOR));
+    }
+}
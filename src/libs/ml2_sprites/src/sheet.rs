@@ -0,0 +1,394 @@
+//! Cuts texture sheets into tiles and puts edited tiles back.
+
+use std::path::{Path, PathBuf};
+
+use image::imageops;
+use image::RgbaImage;
+use ml2_entity_data::{ENTITIES, TEXTURES};
+use ml2_types::{Entity, Texture};
+
+use crate::SpriteError;
+
+const ENTITY_PREFIX: &str = "ENT_TYPE_";
+
+/// An entity frame drawn from a tile.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TileUser {
+    /// e.g. `ENT_TYPE_MONS_SNAKE`
+    pub entity: String,
+    /// The animation the frame belongs to, or `None` for an entity without animations.
+    pub animation: Option<i64>,
+    pub frame: i64,
+}
+
+impl TileUser {
+    fn label(&self) -> String {
+        let entity = self
+            .entity
+            .strip_prefix(ENTITY_PREFIX)
+            .unwrap_or(&self.entity);
+        let entity = entity.to_ascii_lowercase();
+        match self.animation {
+            Some(animation) => format!("{entity}_{animation}_{}", self.frame),
+            None => entity,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TileInfo {
+    /// Position in the sheet, counting left to right then top to bottom.
+    pub index: usize,
+    pub column: u32,
+    pub row: u32,
+    /// Sorted by entity, animation and frame.
+    pub users: Vec<TileUser>,
+}
+
+impl TileInfo {
+    /// The file a tile is exported as, e.g. `032_mons_snake_2_0.png`.
+    ///
+    /// The index prefix is what [`SheetLayout::assemble`] reads back, so the rest of the name
+    /// is only a guide for artists.
+    pub fn file_name(&self) -> String {
+        let label = match self.users.first() {
+            Some(user) => user.label(),
+            None => "unused".into(),
+        };
+        format!("{:03}_{label}.png", self.index)
+    }
+}
+
+/// The grid of tiles in a texture sheet.
+#[derive(Clone, Copy, Debug)]
+pub struct SheetLayout<'a> {
+    id: i64,
+    texture: &'a Texture,
+}
+
+impl SheetLayout<'static> {
+    /// Looks up a texture in the game's texture data.
+    pub fn from_id(id: i64) -> Option<Self> {
+        TEXTURES
+            .get(&id.to_string())
+            .map(|texture| Self::new(id, texture))
+    }
+
+    /// Looks up an entity and the sheet it's drawn from.
+    pub fn for_entity(entity: &str) -> Result<(&'static Entity, Self), SpriteError> {
+        let data = ENTITIES
+            .get(entity)
+            .ok_or_else(|| SpriteError::UnknownEntity(entity.into()))?;
+        let layout =
+            Self::from_id(data.texture).ok_or(SpriteError::UnknownTexture(data.texture))?;
+        Ok((data, layout))
+    }
+}
+
+impl<'a> SheetLayout<'a> {
+    pub fn new(id: i64, texture: &'a Texture) -> Self {
+        Self { id, texture }
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// Where the sheet is after extraction, e.g. `Data/Textures/monstersbasic01.png`.
+    pub fn extracted_path(&self, extract_dir: &Path) -> PathBuf {
+        extract_dir.join(Path::new(&self.texture.path).with_extension("png"))
+    }
+
+    /// Loads the extracted sheet.
+    pub fn load(&self, extract_dir: &Path) -> Result<RgbaImage, SpriteError> {
+        let sheet = image::open(self.extracted_path(extract_dir))?.into_rgba8();
+        self.check_sheet(&sheet)?;
+        Ok(sheet)
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.texture.num_tiles.width.max(0) as u32
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.texture.num_tiles.height.max(0) as u32
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.columns() as usize * self.rows() as usize
+    }
+
+    pub fn tile_size(&self) -> (u32, u32) {
+        (
+            self.texture.tile_width.max(0) as u32,
+            self.texture.tile_height.max(0) as u32,
+        )
+    }
+
+    /// The left, top, width and height of a tile in the sheet.
+    pub fn tile_rect(&self, index: usize) -> (u32, u32, u32, u32) {
+        let (width, height) = self.tile_size();
+        let column = index as u32 % self.columns().max(1);
+        let row = index as u32 / self.columns().max(1);
+        (
+            self.texture.offset.width.max(0) as u32 + column * width,
+            self.texture.offset.height.max(0) as u32 + row * height,
+            width,
+            height,
+        )
+    }
+
+    /// Every tile in the sheet, with the entity frames drawn from it.
+    pub fn tiles(&self) -> Vec<TileInfo> {
+        let columns = self.columns().max(1);
+        let mut tiles: Vec<TileInfo> = (0..self.tile_count())
+            .map(|index| TileInfo {
+                index,
+                column: index as u32 % columns,
+                row: index as u32 / columns,
+                users: Vec::new(),
+            })
+            .collect();
+
+        for (entity, frames) in ENTITIES
+            .keys()
+            .map(|entity| (entity, self.entity_frames(entity)))
+        {
+            for (index, animation, frame) in frames {
+                if let Some(tile) = tiles.get_mut(index) {
+                    tile.users.push(TileUser {
+                        entity: entity.clone(),
+                        animation,
+                        frame,
+                    });
+                }
+            }
+        }
+        for tile in &mut tiles {
+            tile.users.sort();
+        }
+        tiles
+    }
+
+    /// Tiles used by an entity's frames, in sheet order.
+    pub fn entity_tiles(&self, entity: &str) -> Vec<TileInfo> {
+        let mut indexes: Vec<usize> = self
+            .entity_frames(entity)
+            .into_iter()
+            .map(|(index, _, _)| index)
+            .collect();
+        indexes.sort_unstable();
+        indexes.dedup();
+
+        let tiles = self.tiles();
+        indexes
+            .into_iter()
+            .filter_map(|index| tiles.get(index).cloned())
+            .collect()
+    }
+
+    /// The tile an entity is drawn with when idle, see [`Entity::idle_tile`].
+    pub fn first_frame(&self, entity: &str) -> Option<usize> {
+        let entity = ENTITIES.get(entity).filter(|e| e.texture == self.id)?;
+        usize::try_from(entity.idle_tile(i64::from(self.columns()))).ok()
+    }
+
+    /// The tile index, animation and frame of each of an entity's frames in this sheet.
+    ///
+    /// Like the game, an entity without animations uses its `tile_x` and `tile_y`.
+    fn entity_frames(&self, entity: &str) -> Vec<(usize, Option<i64>, i64)> {
+        let Some(entity) = ENTITIES.get(entity).filter(|e| e.texture == self.id) else {
+            return Vec::new();
+        };
+        if entity.animations.is_empty() {
+            let index = entity.tile_y * i64::from(self.columns()) + entity.tile_x;
+            return usize::try_from(index)
+                .map(|index| vec![(index, None, 0)])
+                .unwrap_or_default();
+        }
+
+        let mut frames = Vec::new();
+        for (id, animation) in &entity.animations {
+            let id = id.parse().ok();
+            for frame in 0..animation.count {
+                if let Ok(index) = usize::try_from(animation.texture + frame) {
+                    frames.push((index, id, frame));
+                }
+            }
+        }
+        frames
+    }
+
+    fn check_sheet(&self, sheet: &RgbaImage) -> Result<(), SpriteError> {
+        let expected = match self.tile_count().checked_sub(1) {
+            Some(last) => {
+                let (left, top, width, height) = self.tile_rect(last);
+                (left + width, top + height)
+            }
+            None => (0, 0),
+        };
+        let found = sheet.dimensions();
+        if found.0 < expected.0 || found.1 < expected.1 {
+            return Err(SpriteError::SheetTooSmall { expected, found });
+        }
+        Ok(())
+    }
+
+    /// Cuts a tile out of the sheet.
+    pub fn slice(&self, sheet: &RgbaImage, index: usize) -> Result<RgbaImage, SpriteError> {
+        self.check_sheet(sheet)?;
+        if index >= self.tile_count() {
+            return Err(SpriteError::TileOutOfRange(index as i64));
+        }
+        let (left, top, width, height) = self.tile_rect(index);
+        Ok(imageops::crop_imm(sheet, left, top, width, height).to_image())
+    }
+
+    /// Writes tiles to `dir` as PNGs named by [`TileInfo::file_name`], returning their paths.
+    pub fn export(
+        &self,
+        sheet: &RgbaImage,
+        tiles: &[TileInfo],
+        dir: &Path,
+    ) -> Result<Vec<PathBuf>, SpriteError> {
+        std::fs::create_dir_all(dir)?;
+        let mut paths = Vec::with_capacity(tiles.len());
+        for tile in tiles {
+            let path = dir.join(tile.file_name());
+            self.slice(sheet, tile.index)?.save(&path)?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Copies tiles from PNGs in `dir` back into the sheet, returning the indexes replaced.
+    ///
+    /// Files are matched by the index at the start of their names, and other files are ignored.
+    /// Two files for the same index are an error, and the sheet is only changed if every tile
+    /// can be copied.
+    pub fn assemble(&self, sheet: &mut RgbaImage, dir: &Path) -> Result<Vec<usize>, SpriteError> {
+        self.check_sheet(sheet)?;
+
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("png") {
+                continue;
+            }
+            let Some(index) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.split('_').next())
+                .and_then(|index| index.parse::<usize>().ok())
+                .filter(|index| *index < self.tile_count())
+            else {
+                continue;
+            };
+            files.push((index, path));
+        }
+        // Directory order varies by platform
+        files.sort();
+        for pair in files.windows(2) {
+            if let [(index, first), (next, second)] = pair {
+                if index == next {
+                    return Err(SpriteError::DuplicateTile {
+                        index: *index,
+                        first: first.clone(),
+                        second: second.clone(),
+                    });
+                }
+            }
+        }
+
+        let mut assembled = sheet.clone();
+        for (index, path) in &files {
+            let tile = image::open(path)?.into_rgba8();
+            let (left, top, width, height) = self.tile_rect(*index);
+            if tile.dimensions() != (width, height) {
+                return Err(SpriteError::TileSizeMismatch {
+                    index: *index,
+                    expected: (width, height),
+                    found: tile.dimensions(),
+                });
+            }
+            imageops::replace(&mut assembled, &tile, i64::from(left), i64::from(top));
+        }
+        *sheet = assembled;
+        Ok(files.into_iter().map(|(index, _)| index).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::{SheetLayout, TileUser};
+    use crate::SpriteError;
+
+    // Data/Textures/monstersbasic01.DDS
+    const MONSTERS_BASIC: i64 = 308;
+
+    #[test]
+    fn test_tiles() {
+        let layout = SheetLayout::from_id(MONSTERS_BASIC).unwrap();
+        assert_eq!(layout.tile_count(), 256);
+
+        let tiles = layout.tiles();
+        assert_eq!(
+            tiles[2].users[0],
+            TileUser {
+                entity: "ENT_TYPE_MONS_SNAKE".into(),
+                animation: Some(0),
+                frame: 0,
+            }
+        );
+        assert_eq!(tiles[2].file_name(), "002_mons_snake_0_0.png");
+
+        let snake: Vec<usize> = layout
+            .entity_tiles("ENT_TYPE_MONS_SNAKE")
+            .iter()
+            .map(|tile| tile.index)
+            .collect();
+        assert_eq!(snake, (0..6).chain(16..22).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let layout = SheetLayout::from_id(MONSTERS_BASIC).unwrap();
+        let mut sheet = RgbaImage::new(2048, 2048);
+        let tiles = layout.entity_tiles("ENT_TYPE_MONS_SNAKE");
+
+        let dir = tempfile::tempdir().unwrap();
+        let paths = layout.export(&sheet, &tiles, dir.path()).unwrap();
+        assert_eq!(paths.len(), 12);
+
+        let mut edited = image::open(&paths[1]).unwrap().into_rgba8();
+        edited.put_pixel(5, 7, Rgba([255, 0, 0, 255]));
+        edited.save(&paths[1]).unwrap();
+
+        let replaced = layout.assemble(&mut sheet, dir.path()).unwrap();
+        assert_eq!(replaced.len(), 12);
+        assert_eq!(sheet.get_pixel(128 + 5, 7), &Rgba([255, 0, 0, 255]));
+
+        // Nothing is copied if any tile can't be
+        let mut edited = image::open(&paths[0]).unwrap().into_rgba8();
+        edited.put_pixel(1, 1, Rgba([0, 255, 0, 255]));
+        edited.save(&paths[0]).unwrap();
+        image::RgbaImage::new(64, 64)
+            .save(dir.path().join("003_too_small.png"))
+            .unwrap();
+        let err = layout.assemble(&mut sheet, dir.path()).unwrap_err();
+        assert!(matches!(err, SpriteError::DuplicateTile { index: 3, .. }));
+
+        std::fs::remove_file(&paths[3]).unwrap();
+        let err = layout.assemble(&mut sheet, dir.path()).unwrap_err();
+        assert!(matches!(
+            err,
+            SpriteError::TileSizeMismatch { index: 3, .. }
+        ));
+        assert_eq!(sheet.get_pixel(1, 1), &Rgba([0, 0, 0, 0]));
+
+        let err = layout.slice(&RgbaImage::new(512, 512), 0).unwrap_err();
+        assert!(matches!(err, SpriteError::SheetTooSmall { .. }));
+    }
+}
@@ -0,0 +1,877 @@
+use std::fmt;
+
+use ml2_mem::{LoadingState, Screen, WinState};
+use serde::{Deserialize, Serialize};
+
+use crate::{format_time, ChainRule, LowPercentRule, NoGoldRule, PacifistRule, Rule, RunFrame};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Category {
+    Pacifist,
+    NoGold,
+    LowPercent,
+    Chain,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Category::Pacifist => "Pacifist",
+            Category::NoGold => "No Gold",
+            Category::LowPercent => "Low%",
+            Category::Chain => "Chain",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// When and why a run stopped counting for a category.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Break {
+    pub category: Category,
+    pub reason: String,
+    /// Frames since the run started.
+    pub time: u32,
+}
+
+impl fmt::Display for Break {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} broken at {}: {}",
+            self.category,
+            format_time(self.time),
+            self.reason
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TrackerEvent {
+    RunStarted {
+        time: u32,
+    },
+    CategoryBroken(Break),
+    RunFinished {
+        win_state: WinState,
+        categories: Vec<Category>,
+    },
+}
+
+pub struct CategoryTracker {
+    rules: Vec<Box<dyn Rule + Send>>,
+    breaks: Vec<Break>,
+    previous: Option<RunFrame>,
+    finished: bool,
+}
+
+impl Default for CategoryTracker {
+    fn default() -> Self {
+        Self::new(vec![
+            Box::new(PacifistRule),
+            Box::new(NoGoldRule),
+            Box::<LowPercentRule>::default(),
+            Box::<ChainRule>::default(),
+        ])
+    }
+}
+
+impl CategoryTracker {
+    pub fn new(rules: Vec<Box<dyn Rule + Send>>) -> Self {
+        Self {
+            rules,
+            breaks: Vec::new(),
+            previous: None,
+            finished: false,
+        }
+    }
+
+    /// Categories the current run counts for so far.
+    pub fn categories(&self) -> Vec<Category> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.qualifies() && !self.is_broken(rule.category()))
+            .map(|rule| rule.category())
+            .collect()
+    }
+
+    /// Categories the current run has broken, in the order it broke them.
+    pub fn breaks(&self) -> &[Break] {
+        &self.breaks
+    }
+
+    fn is_broken(&self, category: Category) -> bool {
+        self.breaks.iter().any(|b| b.category == category)
+    }
+
+    /// Updates the run with the next frame.
+    ///
+    /// A new run starts when a level is entered from outside a run, or when the run timer goes
+    /// backwards. Frames while the game is loading are skipped.
+    pub fn update(&mut self, frame: RunFrame) -> Vec<TrackerEvent> {
+        let mut events = Vec::new();
+        if frame.state.loading != LoadingState::NotLoading {
+            return events;
+        }
+        if !in_run(frame.state.screen) {
+            self.previous = None;
+            return events;
+        }
+        let restarted = match &self.previous {
+            Some(previous) => frame.time() < previous.time(),
+            None => frame.state.screen == Screen::Level,
+        };
+        if restarted {
+            self.reset();
+            events.push(TrackerEvent::RunStarted { time: frame.time() });
+        } else if self.previous.is_none() {
+            // Outside of a run we know the start of
+            return events;
+        }
+
+        if !self.finished {
+            for rule in &mut self.rules {
+                let category = rule.category();
+                if self.breaks.iter().any(|b| b.category == category) {
+                    continue;
+                }
+                if let Some(reason) = rule.check(self.previous.as_ref(), &frame) {
+                    let broken = Break {
+                        category,
+                        reason,
+                        time: frame.time(),
+                    };
+                    self.breaks.push(broken.clone());
+                    events.push(TrackerEvent::CategoryBroken(broken));
+                }
+            }
+            if frame.state.win_state != WinState::NoWin {
+                self.finished = true;
+                events.push(TrackerEvent::RunFinished {
+                    win_state: frame.state.win_state,
+                    categories: self.categories(),
+                });
+            }
+        }
+        self.previous = Some(frame);
+        events
+    }
+
+    fn reset(&mut self) {
+        self.breaks.clear();
+        self.previous = None;
+        self.finished = false;
+        for rule in &mut self.rules {
+            rule.reset();
+        }
+    }
+}
+
+/// Screens that are part of a run, as opposed to menus, camp and the arena.
+fn in_run(screen: Screen) -> bool {
+    matches!(
+        screen,
+        Screen::Level
+            | Screen::LevelTransition
+            | Screen::Death
+            | Screen::Spaceship
+            | Screen::Win
+            | Screen::Credits
+            | Screen::Scores
+            | Screen::Constellation
+            | Screen::Recap
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use ml2_entity_data::EntityType;
+    use ml2_mem::{
+        Array, CharState, Inventory, LoadingState, Player, Pointer, Screen, State, Theme,
+        UidEntityMap, Vector, WinState,
+    };
+
+    use super::{Break, Category, CategoryTracker, TrackerEvent};
+    use crate::{MountFrame, PlayerFrame, RunFrame};
+
+    fn player(health: i8, bombs: u8, ropes: u8, kills: u32, money: u32) -> PlayerFrame {
+        PlayerFrame {
+            slot: 0,
+            player: Player {
+                entity_type: Pointer::default(),
+                overlay: Pointer::default(),
+                items: Vector::default(),
+                uid: 1,
+                x: 0.0,
+                y: 0.0,
+                layer: 0,
+                state: CharState::Standing,
+                last_state: CharState::Standing,
+                health,
+                inventory: Pointer::new(0x1000),
+                linked_companion_child: 0,
+            },
+            inventory: Some(Inventory {
+                money,
+                bombs,
+                ropes,
+                collected_money_total: money,
+                kills_level: 0,
+                kills_total: kills,
+            }),
+            item_types: HashSet::new(),
+            companion_item_types: HashSet::new(),
+            mount: None,
+        }
+    }
+
+    /// A player with the starting health, bombs and ropes, holding `items`.
+    fn holding(items: &[EntityType]) -> PlayerFrame {
+        let mut p = player(4, 4, 4, 0, 0);
+        p.item_types = items.iter().map(|t| t.id()).collect();
+        p
+    }
+
+    /// A player in the middle of `state`, e.g. attacking.
+    fn doing(state: CharState, last_state: CharState, items: &[EntityType]) -> PlayerFrame {
+        let mut p = holding(items);
+        p.player.state = state;
+        p.player.last_state = last_state;
+        p
+    }
+
+    /// A frame where the run recap has only dropped what the players' kills and gold break.
+    fn frame(time: u32, screen: Screen, level: (u8, u8, Theme), p: PlayerFrame) -> RunFrame {
+        let (world, level, theme) = level;
+        let (kills, money) = p.inventory.as_ref().map_or((0, 0), |inventory| {
+            (
+                inventory.kills_total,
+                inventory.money + inventory.collected_money_total,
+            )
+        });
+        let mut run_recap_flags = 0;
+        if kills == 0 {
+            run_recap_flags |= State::RECAP_PACIFIST;
+        }
+        if money == 0 {
+            run_recap_flags |= State::RECAP_NO_GOLD;
+        }
+        RunFrame {
+            state: State {
+                screen_last: screen,
+                screen,
+                screen_next: screen,
+                loading: LoadingState::NotLoading,
+                ingame: true,
+                playing: true,
+                pause: 0,
+                quest_flags: 0,
+                world_start: 1,
+                level_start: 1,
+                theme_start: 1,
+                seed: 0,
+                time_total: time,
+                world,
+                world_next: world,
+                level,
+                level_next: level,
+                theme: theme as u8,
+                theme_next: theme as u8,
+                win_state: WinState::NoWin,
+                waddler_storage: Array(vec![0; 99]),
+                run_recap_flags,
+                time_level: time,
+                hud_flags: 0,
+                presence_flags: 0,
+                next_entity_uid: 0,
+                items: Pointer::new(0x2000),
+                instance_id_to_pointer: UidEntityMap::default(),
+            },
+            players: vec![p],
+            new_entity_types: Vec::new(),
+        }
+    }
+
+    fn feed(tracker: &mut CategoryTracker, frames: Vec<RunFrame>) -> Vec<TrackerEvent> {
+        frames.into_iter().flat_map(|f| tracker.update(f)).collect()
+    }
+
+    /// Why a new run of `frames` broke `category`, if it did.
+    fn broken(category: Category, frames: Vec<RunFrame>) -> Option<String> {
+        let mut tracker = CategoryTracker::default();
+        feed(&mut tracker, frames)
+            .into_iter()
+            .find_map(|event| match event {
+                TrackerEvent::CategoryBroken(b) if b.category == category => Some(b.reason),
+                _ => None,
+            })
+    }
+
+    const DWELLING: (u8, u8, Theme) = (1, 1, Theme::Dwelling);
+
+    /// A run starting in 1-1, then `p` in `level`.
+    fn after_start(level: (u8, u8, Theme), p: PlayerFrame) -> Vec<RunFrame> {
+        vec![
+            frame(10, Screen::Level, DWELLING, player(4, 4, 4, 0, 0)),
+            frame(20, Screen::Level, level, p),
+        ]
+    }
+
+    #[test]
+    fn test_breaks() {
+        let ice_caves = (5, 1, Theme::IceCaves);
+        let mut win = frame(
+            900,
+            Screen::Win,
+            (6, 4, Theme::Tiamat),
+            player(1, 3, 4, 1, 0),
+        );
+        win.state.win_state = WinState::Tiamat;
+
+        let mut tracker = CategoryTracker::default();
+        let events = feed(
+            &mut tracker,
+            vec![
+                frame(0, Screen::Camp, DWELLING, player(4, 4, 4, 0, 0)),
+                frame(10, Screen::Level, DWELLING, player(4, 4, 4, 0, 0)),
+                frame(20, Screen::Level, DWELLING, player(4, 3, 4, 0, 0)),
+                frame(30, Screen::Level, DWELLING, player(3, 3, 4, 1, 0)),
+                frame(40, Screen::Level, DWELLING, player(3, 4, 4, 1, 0)),
+                frame(500, Screen::Level, ice_caves, player(1, 3, 4, 1, 0)),
+                win,
+            ],
+        );
+        let broken = |category, reason: &str, time| {
+            TrackerEvent::CategoryBroken(Break {
+                category,
+                reason: reason.to_string(),
+                time,
+            })
+        };
+        let no_eye = "collecting the Udjat eye, hedjet or crown";
+        assert_eq!(
+            events,
+            vec![
+                TrackerEvent::RunStarted { time: 10 },
+                broken(Category::Pacifist, "Player 1 has 1 kills", 30),
+                broken(Category::LowPercent, "Player 1 gained bombs (3 -> 4)", 40),
+                broken(
+                    Category::Chain,
+                    &format!("Abzu failed {no_eye}; Duat failed {no_eye}"),
+                    500
+                ),
+                TrackerEvent::RunFinished {
+                    win_state: WinState::Tiamat,
+                    categories: vec![Category::NoGold],
+                },
+            ]
+        );
+        assert_eq!(
+            tracker.breaks()[1].to_string(),
+            "Low% broken at 0:00.666: Player 1 gained bombs (3 -> 4)"
+        );
+    }
+
+    /// A run through the Abzu chain, which fails Duat's at the Tide Pool, ending with a win.
+    fn abzu_chain(win_state: WinState) -> Vec<RunFrame> {
+        let mut items = vec![EntityType::ITEM_POWERUP_UDJATEYE];
+        let mut step = |time, screen, level, item: Option<EntityType>| {
+            items.extend(item);
+            frame(time, screen, level, holding(&items))
+        };
+        let win_level = match win_state {
+            WinState::Tiamat => (6, 4, Theme::Tiamat),
+            _ => (7, 4, Theme::Hundun),
+        };
+        let mut frames = vec![
+            step(100, Screen::Level, (1, 2, Theme::Dwelling), None),
+            step(
+                200,
+                Screen::Level,
+                (2, 2, Theme::Jungle),
+                Some(EntityType::ITEM_POWERUP_HEDJET),
+            ),
+            step(
+                300,
+                Screen::Level,
+                (3, 1, Theme::Olmec),
+                Some(EntityType::ITEM_POWERUP_ANKH),
+            ),
+            step(400, Screen::Level, (4, 1, Theme::TidePool), None),
+            step(
+                500,
+                Screen::Level,
+                (4, 2, Theme::TidePool),
+                Some(EntityType::ITEM_EXCALIBUR),
+            ),
+            step(600, Screen::Level, (4, 4, Theme::Abzu), None),
+            step(
+                700,
+                Screen::Level,
+                (4, 4, Theme::Abzu),
+                Some(EntityType::ITEM_POWERUP_TABLETOFDESTINY),
+            ),
+            step(
+                800,
+                Screen::LevelTransition,
+                (6, 2, Theme::SunkenCity),
+                None,
+            ),
+            step(900, Screen::Win, win_level, None),
+        ];
+        // A hired hand carries the ushabti to 6-3
+        frames[7].players[0]
+            .companion_item_types
+            .insert(EntityType::ITEM_USHABTI.id());
+        frames[8].state.win_state = win_state;
+        frames
+    }
+
+    #[test]
+    fn test_restart_and_chain() {
+        let mut tracker = CategoryTracker::default();
+        feed(
+            &mut tracker,
+            vec![
+                frame(10, Screen::Level, DWELLING, player(4, 4, 4, 0, 0)),
+                frame(20, Screen::Level, DWELLING, player(4, 4, 4, 0, 500)),
+            ],
+        );
+        assert_eq!(tracker.breaks().len(), 1);
+        assert!(!tracker.categories().contains(&Category::Chain));
+
+        // Restarting resets the timer without leaving the level
+        let events = tracker.update(frame(1, Screen::Level, DWELLING, player(4, 4, 4, 0, 0)));
+        assert_eq!(events, vec![TrackerEvent::RunStarted { time: 1 }]);
+        assert!(tracker.breaks().is_empty());
+
+        let events = feed(&mut tracker, abzu_chain(WinState::Hundun));
+        assert_eq!(
+            events,
+            vec![TrackerEvent::RunFinished {
+                win_state: WinState::Hundun,
+                categories: vec![
+                    Category::Pacifist,
+                    Category::NoGold,
+                    Category::LowPercent,
+                    Category::Chain
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_chain_breaks() {
+        let eye = EntityType::ITEM_POWERUP_UDJATEYE;
+        let hedjet = EntityType::ITEM_POWERUP_HEDJET;
+        let ankh = EntityType::ITEM_POWERUP_ANKH;
+        // Chains take a step a frame, so a few frames in `level` catch up with `items`
+        let on_chain = |level, items: &[EntityType]| {
+            let mut frames = vec![frame(
+                10,
+                Screen::Level,
+                (1, 2, Theme::Dwelling),
+                holding(&[eye]),
+            )];
+            for time in [20, 21, 22, 23] {
+                frames.push(frame(time, Screen::Level, level, holding(items)));
+            }
+            frames
+        };
+        assert_eq!(
+            broken(Category::Chain, on_chain((3, 1, Theme::Olmec), &[eye])),
+            Some(
+                "Abzu failed collecting the hedjet or crown; Duat failed collecting the hedjet or crown"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            broken(
+                Category::Chain,
+                on_chain((4, 1, Theme::Temple), &[eye, hedjet])
+            ),
+            Some("Abzu failed collecting the ankh; Duat failed collecting the ankh".to_string())
+        );
+
+        // Taking the scepter through the door to 4-2, or not
+        let mut frames = on_chain((4, 1, Theme::Temple), &[eye, hedjet, ankh]);
+        frames.push(frame(
+            30,
+            Screen::LevelTransition,
+            (4, 1, Theme::Temple),
+            holding(&[eye, hedjet, ankh]),
+        ));
+        assert_eq!(
+            broken(Category::Chain, frames.clone()),
+            Some("Abzu failed reaching 4-1; Duat failed carrying the scepter to 4-2".to_string())
+        );
+        frames[5].players[0]
+            .item_types
+            .insert(EntityType::ITEM_SCEPTER.id());
+        assert_eq!(broken(Category::Chain, frames), None);
+
+        // Leaving 4-2 without Excalibur
+        let mut frames = on_chain((4, 1, Theme::TidePool), &[eye, hedjet, ankh]);
+        frames.push(frame(
+            30,
+            Screen::Level,
+            (4, 3, Theme::TidePool),
+            holding(&[eye, hedjet, ankh]),
+        ));
+        assert_eq!(
+            broken(Category::Chain, frames),
+            Some("Abzu failed collecting Excalibur; Duat failed reaching 4-1".to_string())
+        );
+
+        // Winning at Tiamat ends the chain
+        assert_eq!(
+            broken(Category::Chain, abzu_chain(WinState::Tiamat)),
+            Some("Abzu failed beating Hundun; Duat failed reaching 4-1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_recap_flags() {
+        // The run recap decides, even when the inventory doesn't show why
+        let mut frames = after_start(DWELLING, player(4, 4, 4, 0, 0));
+        frames[1].state.run_recap_flags = 0;
+        assert_eq!(
+            broken(Category::Pacifist, frames.clone()),
+            Some("Killed something".to_string())
+        );
+        assert_eq!(
+            broken(Category::NoGold, frames),
+            Some("Collected gold".to_string())
+        );
+
+        let mut frames = after_start(DWELLING, player(4, 4, 4, 2, 100));
+        frames[1].state.run_recap_flags = State::RECAP_PACIFIST | State::RECAP_NO_GOLD;
+        assert_eq!(broken(Category::Pacifist, frames.clone()), None);
+        assert_eq!(broken(Category::NoGold, frames), None);
+    }
+
+    #[test]
+    fn test_gold_this_level() {
+        // Gold picked up in the current level is only in `money` until the level ends
+        let mut p = player(4, 4, 4, 0, 0);
+        p.inventory.as_mut().unwrap().money = 300;
+        let mut tracker = CategoryTracker::default();
+        let events = feed(
+            &mut tracker,
+            vec![
+                frame(10, Screen::Level, DWELLING, player(4, 4, 4, 0, 0)),
+                frame(20, Screen::Level, DWELLING, p),
+            ],
+        );
+        assert_eq!(
+            events[1],
+            TrackerEvent::CategoryBroken(Break {
+                category: Category::NoGold,
+                reason: "Player 1 collected $300".to_string(),
+                time: 20,
+            })
+        );
+    }
+
+    #[test]
+    fn test_player_slots() {
+        let mut both = frame(10, Screen::Level, DWELLING, player(2, 4, 4, 0, 0));
+        let mut second = player(4, 4, 4, 0, 0);
+        second.slot = 1;
+        both.players.push(second.clone());
+
+        // Player 1 dies and leaves their slot, so player 2 must not be compared with them
+        let only_second = frame(20, Screen::Level, DWELLING, second.clone());
+        let mut healed = second;
+        healed.player.health = 5;
+        let healed = frame(30, Screen::Level, DWELLING, healed);
+
+        let mut tracker = CategoryTracker::default();
+        let events = feed(&mut tracker, vec![both, only_second, healed]);
+        assert_eq!(
+            events,
+            vec![
+                TrackerEvent::RunStarted { time: 10 },
+                TrackerEvent::CategoryBroken(Break {
+                    category: Category::LowPercent,
+                    reason: "Player 2 gained health (4 -> 5)".to_string(),
+                    time: 30,
+                }),
+            ]
+        );
+    }
+
+    fn low(frames: Vec<RunFrame>) -> Option<String> {
+        broken(Category::LowPercent, frames)
+    }
+
+    #[test]
+    fn test_low_resources() {
+        let mut dying = player(5, 4, 4, 0, 0);
+        dying.player.state = CharState::Dying;
+        assert_eq!(
+            low(after_start(DWELLING, dying)),
+            Some("Player 1 has 5 health".to_string())
+        );
+        assert_eq!(
+            low(after_start(DWELLING, player(4, 5, 4, 0, 0))),
+            Some("Player 1 gained bombs (4 -> 5)".to_string())
+        );
+        assert_eq!(
+            low(after_start(DWELLING, player(4, 4, 5, 0, 0))),
+            Some("Player 1 has more ropes than at the start of the level (4 -> 5)".to_string())
+        );
+
+        // Ropes can be taken back down within a level, but not gained past the level's start
+        let level_2 = (1, 2, Theme::Dwelling);
+        let mut frames = after_start(DWELLING, player(4, 4, 2, 0, 0));
+        frames.extend([
+            frame(30, Screen::Level, level_2, player(4, 4, 2, 0, 0)),
+            frame(40, Screen::Level, level_2, player(4, 4, 1, 0, 0)),
+            frame(50, Screen::Level, level_2, player(4, 4, 2, 0, 0)),
+        ]);
+        assert_eq!(low(frames.clone()), None);
+        frames.push(frame(60, Screen::Level, level_2, player(4, 4, 3, 0, 0)));
+        assert_eq!(
+            low(frames),
+            Some("Player 1 has more ropes than at the start of the level (2 -> 3)".to_string())
+        );
+
+        // Duat restores health
+        let mut frames = after_start((4, 3, Theme::CityOfGold), player(1, 4, 4, 0, 0));
+        frames.push(frame(
+            30,
+            Screen::Level,
+            (4, 4, Theme::Duat),
+            player(4, 4, 4, 0, 0),
+        ));
+        assert_eq!(low(frames), None);
+
+        // Loading frames are skipped
+        let mut frames = after_start(DWELLING, player(5, 4, 4, 0, 0));
+        frames[1].state.loading = LoadingState::Loading;
+        assert_eq!(low(frames), None);
+    }
+
+    #[test]
+    fn test_low_effects() {
+        let poison = EntityType::LOGICAL_POISONED_EFFECT;
+        let curse = EntityType::LOGICAL_CURSED_EFFECT;
+        let cured = |effect, state| {
+            let mut frames = after_start(DWELLING, holding(&[effect]));
+            frames.push(frame(30, Screen::Level, DWELLING, doing(state, state, &[])));
+            low(frames)
+        };
+        assert_eq!(
+            cured(poison, CharState::Standing),
+            Some("Player 1 was cured of poison".to_string())
+        );
+        assert_eq!(
+            cured(curse, CharState::Standing),
+            Some("Player 1 was cured of a curse".to_string())
+        );
+        // Effects go away when dying, and while going through doors
+        assert_eq!(cured(poison, CharState::Dying), None);
+        assert_eq!(cured(curse, CharState::Entering), None);
+    }
+
+    #[test]
+    fn test_low_clover() {
+        let clover = |time, p| {
+            let mut frames = after_start(DWELLING, p);
+            frames[1].state.time_level = time;
+            frames[1].state.hud_flags = State::HUD_HAVE_CLOVER;
+            frames
+        };
+        let kept = Some("Kept a clover until the ghost was due".to_string());
+        let cursed = || holding(&[EntityType::LOGICAL_CURSED_EFFECT]);
+        assert_eq!(low(clover(60 * 60, player(4, 4, 4, 0, 0))), None);
+        assert_eq!(low(clover(3 * 60 * 60 - 5, player(4, 4, 4, 0, 0))), kept);
+        assert_eq!(low(clover(150 * 60, player(4, 4, 4, 0, 0))), None);
+        assert_eq!(low(clover(150 * 60, cursed())), kept);
+
+        // Once the ghost is out, the clover doesn't matter
+        let mut frames = clover(3 * 60 * 60, player(4, 4, 4, 0, 0));
+        frames[1].new_entity_types = vec![EntityType::MONS_GHOST.id()];
+        assert_eq!(low(frames), None);
+    }
+
+    #[test]
+    fn test_low_items() {
+        let with = |items: &[EntityType]| low(after_start(DWELLING, holding(items)));
+        assert_eq!(
+            with(&[EntityType::ITEM_JETPACK]),
+            Some("Player 1 wore ITEM_JETPACK".to_string())
+        );
+        assert_eq!(
+            with(&[EntityType::ITEM_WOODEN_SHIELD]),
+            Some("Player 1 held ITEM_WOODEN_SHIELD".to_string())
+        );
+        assert_eq!(
+            with(&[EntityType::ITEM_POWERUP_CLIMBING_GLOVES]),
+            Some("Player 1 collected ITEM_POWERUP_CLIMBING_GLOVES".to_string())
+        );
+        assert_eq!(
+            with(&[EntityType::ITEM_POWERUP_ANKH]),
+            Some("Player 1 collected ITEM_POWERUP_ANKH off the chain".to_string())
+        );
+        assert_eq!(with(&[EntityType::ITEM_POWERUP_EGGPLANTCROWN]), None);
+        // Holding weapons is fine, as long as they aren't used
+        assert_eq!(with(&[EntityType::ITEM_SHOTGUN]), None);
+    }
+
+    #[test]
+    fn test_low_mounts() {
+        let riding = |level, entity_type: EntityType, is_tamed| {
+            let mut p = player(4, 4, 4, 0, 0);
+            p.mount = Some(MountFrame {
+                entity_type: entity_type.id(),
+                is_tamed,
+            });
+            low(after_start(level, p))
+        };
+        assert_eq!(riding(DWELLING, EntityType::MOUNT_TURKEY, false), None);
+        assert_eq!(
+            riding(DWELLING, EntityType::MOUNT_TURKEY, true),
+            Some("Player 1 rode a tamed MOUNT_TURKEY".to_string())
+        );
+        assert_eq!(
+            riding((6, 4, Theme::Tiamat), EntityType::MOUNT_QILIN, true),
+            Some("Player 1 rode a qilin in Tiamat off the chain".to_string())
+        );
+    }
+
+    #[test]
+    fn test_low_attacks() {
+        use CharState::{Attacking, Standing, Throwing};
+
+        let attack = |level, layer, presence_flags, items: &[EntityType]| {
+            let mut p = doing(Attacking, Standing, items);
+            p.player.layer = layer;
+            let mut frames = after_start(level, p);
+            frames[1].state.presence_flags = presence_flags;
+            low(frames)
+        };
+        assert_eq!(
+            attack(DWELLING, 0, 0, &[EntityType::ITEM_SHOTGUN]),
+            Some("Player 1 attacked with ITEM_SHOTGUN".to_string())
+        );
+
+        // The bow is allowed in challenges, Waddler's and against Hundun
+        let bow = &[EntityType::ITEM_HOUYIBOW];
+        let moon = State::PRESENCE_MOON_CHALLENGE;
+        let sun = State::PRESENCE_SUN_CHALLENGE;
+        assert_eq!(
+            attack((1, 2, Theme::Dwelling), 0, 0, bow),
+            Some("Player 1 attacked with ITEM_HOUYIBOW".to_string())
+        );
+        assert_eq!(attack((2, 2, Theme::Jungle), 1, moon, bow), None);
+        assert_eq!(attack((6, 2, Theme::SunkenCity), 1, sun, bow), None);
+        assert_eq!(attack((3, 1, Theme::Olmec), 1, 0, bow), None);
+        assert_eq!(attack((7, 4, Theme::Hundun), 0, 0, bow), None);
+
+        // A thrown boomerang has left the player's items
+        let mut frames = after_start(DWELLING, holding(&[EntityType::ITEM_BOOMERANG]));
+        frames.push(frame(
+            30,
+            Screen::Level,
+            DWELLING,
+            doing(Attacking, Standing, &[]),
+        ));
+        assert_eq!(
+            low(frames),
+            Some("Player 1 attacked with ITEM_BOOMERANG".to_string())
+        );
+
+        let arrow = &[EntityType::ITEM_LIGHT_ARROW];
+        assert_eq!(
+            low(after_start(DWELLING, doing(Throwing, Standing, arrow))),
+            Some("Player 1 threw ITEM_LIGHT_ARROW".to_string())
+        );
+
+        // The mattock is allowed in the moon challenge, if the run goes to the Cosmic Ocean
+        let mattock = &[EntityType::ITEM_MATTOCK];
+        let mut frames = after_start((2, 2, Theme::Jungle), {
+            let mut p = doing(Attacking, Standing, mattock);
+            p.player.layer = 1;
+            p
+        });
+        frames[1].state.presence_flags = moon;
+        assert_eq!(low(frames.clone()), None);
+        frames.push(frame(
+            30,
+            Screen::Level,
+            (3, 1, Theme::Olmec),
+            holding(mattock),
+        ));
+        assert_eq!(
+            low(frames),
+            Some(
+                "Swung the mattock in the moon challenge without going to the Cosmic Ocean"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_low_on_chain() {
+        let eye = EntityType::ITEM_POWERUP_UDJATEYE;
+        let hedjet = EntityType::ITEM_POWERUP_HEDJET;
+        let ankh = EntityType::ITEM_POWERUP_ANKH;
+        let excalibur = EntityType::ITEM_EXCALIBUR;
+        let chain_items = [eye, hedjet, ankh, excalibur];
+        let mut frames = vec![
+            frame(10, Screen::Level, (1, 2, Theme::Dwelling), holding(&[eye])),
+            frame(
+                20,
+                Screen::Level,
+                (2, 2, Theme::Jungle),
+                holding(&[eye, hedjet]),
+            ),
+            frame(
+                30,
+                Screen::Level,
+                (3, 1, Theme::Olmec),
+                holding(&[eye, hedjet, ankh]),
+            ),
+            frame(
+                40,
+                Screen::Level,
+                (4, 1, Theme::TidePool),
+                holding(&chain_items),
+            ),
+            frame(
+                50,
+                Screen::Level,
+                (4, 4, Theme::Abzu),
+                holding(&chain_items),
+            ),
+            frame(
+                60,
+                Screen::Level,
+                (4, 4, Theme::Abzu),
+                doing(CharState::Attacking, CharState::Standing, &chain_items),
+            ),
+        ];
+        // The ankh and Excalibur in Abzu are allowed on the chain
+        assert_eq!(low(frames.clone()), None);
+
+        // Leaving world 4 without the tablet
+        frames.push(frame(
+            70,
+            Screen::Level,
+            (5, 1, Theme::IceCaves),
+            holding(&chain_items),
+        ));
+        assert_eq!(
+            low(frames),
+            Some(
+                "Left the chain after using what only it allows \
+                 (Abzu failed collecting the Tablet of Destiny; Duat failed reaching 4-1)"
+                    .to_string()
+            )
+        );
+    }
+}
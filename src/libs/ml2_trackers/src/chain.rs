@@ -0,0 +1,364 @@
+//! The quest chains some categories require, as in `modlunky2/category/chain`.
+
+use std::fmt;
+
+use ml2_entity_data::EntityType;
+use ml2_mem::{CharState, Screen, State, Theme, WinState};
+
+use crate::PlayerFrame;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChainStatus {
+    /// Waiting for the chain's first step.
+    Unstarted,
+    InProgress,
+    /// The chain can no longer be finished.
+    Failed,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chain {
+    Abzu,
+    Duat,
+    CosmicOcean,
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Chain::Abzu => "Abzu",
+            Chain::Duat => "Duat",
+            Chain::CosmicOcean => "Cosmic Ocean",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// What a chain is waiting for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    CollectEyeOrHeadwear,
+    CollectHeadwear,
+    CollectAnkh,
+    VisitWorld41Theme,
+    CollectExcalibur,
+    CarryScepterTo42,
+    VisitCityOfGold,
+    KeepAnkh,
+    VisitWorld44Theme,
+    CollectTablet,
+    CarryUshabtiTo63,
+    WinViaHundunOrCo,
+    CollectBow,
+    CarryBowToHundun,
+    WinViaCo,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let step = match self {
+            Step::CollectEyeOrHeadwear => "collecting the Udjat eye, hedjet or crown",
+            Step::CollectHeadwear => "collecting the hedjet or crown",
+            Step::CollectAnkh => "collecting the ankh",
+            Step::VisitWorld41Theme => "reaching 4-1",
+            Step::CollectExcalibur => "collecting Excalibur",
+            Step::CarryScepterTo42 => "carrying the scepter to 4-2",
+            Step::VisitCityOfGold => "reaching the City of Gold",
+            Step::KeepAnkh => "keeping the ankh",
+            Step::VisitWorld44Theme => "reaching 4-4",
+            Step::CollectTablet => "collecting the Tablet of Destiny",
+            Step::CarryUshabtiTo63 => "carrying an ushabti to 6-3",
+            Step::WinViaHundunOrCo => "beating Hundun",
+            Step::CollectBow => "collecting Hou Yi's bow",
+            Step::CarryBowToHundun => "carrying the bow to Hundun",
+            Step::WinViaCo => "reaching the Cosmic Ocean",
+        };
+        write!(f, "{step}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StepperState {
+    /// Unstarted chains go back to their first step.
+    Unstarted,
+    InProgress(Step),
+    /// The step the chain failed at.
+    Failed(Step),
+}
+
+/// Follows one quest chain, a step at a time.
+///
+/// Each frame evaluates the step the chain is waiting on. Chains can go back to being unstarted,
+/// but never recover from failing.
+#[derive(Clone, Debug)]
+pub struct ChainStepper {
+    chain: Chain,
+    state: StepperState,
+}
+
+impl ChainStepper {
+    pub fn new(chain: Chain) -> Self {
+        Self {
+            chain,
+            state: StepperState::Unstarted,
+        }
+    }
+
+    pub fn chain(&self) -> Chain {
+        self.chain
+    }
+
+    /// The status after the last [`ChainStepper::evaluate`].
+    pub fn status(&self) -> ChainStatus {
+        match self.state {
+            StepperState::Unstarted => ChainStatus::Unstarted,
+            StepperState::InProgress(_) => ChainStatus::InProgress,
+            StepperState::Failed(_) => ChainStatus::Failed,
+        }
+    }
+
+    /// The step the chain failed at, if it has.
+    pub fn failed_step(&self) -> Option<Step> {
+        match self.state {
+            StepperState::Failed(step) => Some(step),
+            _ => None,
+        }
+    }
+
+    /// Evaluates the current step for the first player.
+    pub fn evaluate(&mut self, state: &State, player: &PlayerFrame) -> ChainStatus {
+        let step = match self.state {
+            StepperState::Unstarted => self.first_step(),
+            StepperState::InProgress(step) => step,
+            StepperState::Failed(_) => return ChainStatus::Failed,
+        };
+        self.state = self.step(step, state, player);
+        self.status()
+    }
+
+    fn first_step(&self) -> Step {
+        match self.chain {
+            Chain::Abzu | Chain::Duat => Step::CollectEyeOrHeadwear,
+            Chain::CosmicOcean => Step::CollectBow,
+        }
+    }
+
+    fn step(&self, step: Step, state: &State, player: &PlayerFrame) -> StepperState {
+        use StepperState::{Failed, InProgress, Unstarted};
+
+        let world_level = (state.world, state.level);
+        let theme = state.theme();
+        let has_headwear = player.has(EntityType::ITEM_POWERUP_HEDJET)
+            || player.has(EntityType::ITEM_POWERUP_CROWN);
+        match step {
+            Step::CollectEyeOrHeadwear => {
+                if player.has(EntityType::ITEM_POWERUP_UDJATEYE) {
+                    InProgress(Step::CollectHeadwear)
+                } else if has_headwear {
+                    InProgress(Step::CollectAnkh)
+                } else if state.world > 2 {
+                    Failed(step)
+                } else {
+                    Unstarted
+                }
+            }
+            Step::CollectHeadwear => {
+                if has_headwear {
+                    InProgress(Step::CollectAnkh)
+                } else if state.world > 2 {
+                    Failed(step)
+                } else {
+                    InProgress(step)
+                }
+            }
+            Step::CollectAnkh => {
+                if player.has(EntityType::ITEM_POWERUP_ANKH) {
+                    InProgress(Step::VisitWorld41Theme)
+                } else if state.world > 3 {
+                    Failed(step)
+                } else {
+                    InProgress(step)
+                }
+            }
+            Step::VisitWorld41Theme => {
+                let (world41_theme, next) = match self.chain {
+                    Chain::Abzu => (Theme::TidePool, Step::CollectExcalibur),
+                    _ => (Theme::Temple, Step::CarryScepterTo42),
+                };
+                if theme == Some(world41_theme) {
+                    InProgress(next)
+                } else if state.world > 3 {
+                    Failed(step)
+                } else {
+                    InProgress(step)
+                }
+            }
+            Step::CollectExcalibur => {
+                if player.has(EntityType::ITEM_EXCALIBUR) {
+                    InProgress(Step::VisitWorld44Theme)
+                } else if world_level > (4, 2) {
+                    Failed(step)
+                } else {
+                    InProgress(step)
+                }
+            }
+            Step::CarryScepterTo42 => {
+                // The ankh is required to reach Duat
+                if !player.has(EntityType::ITEM_POWERUP_ANKH) {
+                    Failed(step)
+                } else if world_level > (4, 2) {
+                    // Already in the City of Gold
+                    InProgress(Step::VisitCityOfGold)
+                } else if (state.world, state.level, state.screen)
+                    != (4, 1, Screen::LevelTransition)
+                {
+                    InProgress(step)
+                } else if player.has_with_companions(EntityType::ITEM_SCEPTER) {
+                    InProgress(Step::VisitCityOfGold)
+                } else {
+                    Failed(step)
+                }
+            }
+            Step::VisitCityOfGold => {
+                if !player.has(EntityType::ITEM_POWERUP_ANKH) {
+                    Failed(step)
+                } else if theme == Some(Theme::CityOfGold) {
+                    InProgress(Step::KeepAnkh)
+                } else if world_level > (4, 2) {
+                    Failed(step)
+                } else {
+                    InProgress(step)
+                }
+            }
+            Step::KeepAnkh => {
+                if world_level > (4, 3) {
+                    InProgress(Step::VisitWorld44Theme)
+                // There are frames where the player is stunned on the altar without the ankh
+                } else if player.player.state != CharState::Stunned
+                    && !player.has(EntityType::ITEM_POWERUP_ANKH)
+                {
+                    Failed(step)
+                } else {
+                    InProgress(step)
+                }
+            }
+            Step::VisitWorld44Theme => {
+                let world44_theme = match self.chain {
+                    Chain::Abzu => Theme::Abzu,
+                    _ => Theme::Duat,
+                };
+                if theme == Some(world44_theme) {
+                    InProgress(Step::CollectTablet)
+                } else if world_level > (4, 3) {
+                    Failed(step)
+                } else {
+                    InProgress(step)
+                }
+            }
+            Step::CollectTablet => {
+                if player.has(EntityType::ITEM_POWERUP_TABLETOFDESTINY) {
+                    InProgress(Step::CarryUshabtiTo63)
+                } else if state.world > 4 {
+                    Failed(step)
+                } else {
+                    InProgress(step)
+                }
+            }
+            Step::CarryUshabtiTo63 => {
+                if (state.world, state.level, state.screen) != (6, 2, Screen::LevelTransition) {
+                    InProgress(step)
+                } else if player.has_with_companions(EntityType::ITEM_USHABTI) {
+                    InProgress(Step::WinViaHundunOrCo)
+                } else {
+                    Failed(step)
+                }
+            }
+            Step::WinViaHundunOrCo => {
+                if state.win_state == WinState::Tiamat {
+                    Failed(step)
+                } else {
+                    InProgress(step)
+                }
+            }
+            Step::CollectBow => {
+                if player.has(EntityType::ITEM_HOUYIBOW) {
+                    InProgress(Step::CarryBowToHundun)
+                } else if state.world > 2 {
+                    Failed(step)
+                } else {
+                    Unstarted
+                }
+            }
+            Step::CarryBowToHundun => {
+                let bow = EntityType::ITEM_HOUYIBOW.id();
+                if state.win_state != WinState::NoWin {
+                    Failed(step)
+                } else if world_level > (7, 3) {
+                    InProgress(Step::WinViaCo)
+                } else if state.screen != Screen::LevelTransition
+                    || player.has_with_companions(EntityType::ITEM_HOUYIBOW)
+                    || (world_level < (7, 1) && state.waddler_storage.0.contains(&bow))
+                {
+                    InProgress(step)
+                } else {
+                    Failed(step)
+                }
+            }
+            Step::WinViaCo => match state.win_state {
+                WinState::NoWin | WinState::CosmicOcean => InProgress(step),
+                _ => Failed(step),
+            },
+        }
+    }
+}
+
+/// The Abzu and Duat chains, which share their first steps. A run is on the chain while either
+/// is in progress, and off it once both fail.
+#[derive(Clone, Debug)]
+pub struct SunkenChains {
+    abzu: ChainStepper,
+    duat: ChainStepper,
+    status: ChainStatus,
+}
+
+impl Default for SunkenChains {
+    fn default() -> Self {
+        Self {
+            abzu: ChainStepper::new(Chain::Abzu),
+            duat: ChainStepper::new(Chain::Duat),
+            status: ChainStatus::Unstarted,
+        }
+    }
+}
+
+impl SunkenChains {
+    pub fn status(&self) -> ChainStatus {
+        self.status
+    }
+
+    pub fn evaluate(&mut self, state: &State, player: &PlayerFrame) -> ChainStatus {
+        if self.status == ChainStatus::Failed {
+            return self.status;
+        }
+        let abzu = self.abzu.evaluate(state, player);
+        let duat = self.duat.evaluate(state, player);
+        self.status = match (abzu, duat) {
+            (ChainStatus::Unstarted, ChainStatus::Unstarted) => ChainStatus::Unstarted,
+            (ChainStatus::Failed, ChainStatus::Failed) => ChainStatus::Failed,
+            _ => ChainStatus::InProgress,
+        };
+        self.status
+    }
+
+    /// Where each chain failed, e.g. "Abzu failed collecting Excalibur; Duat failed ...".
+    pub fn describe_failure(&self) -> String {
+        [&self.abzu, &self.duat]
+            .iter()
+            .filter_map(|stepper| {
+                let step = stepper.failed_step()?;
+                Some(format!("{} failed {step}", stepper.chain()))
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+
+use ml2_entity_data::EntityType;
+use ml2_mem::{Inventory, MemError, MemStruct, MemorySource, Mount, Player, Screen, State};
+use serde::{Deserialize, Serialize};
+
+use crate::rules::MOUNTS;
+
+/// The game runs at 60 frames per second.
+pub const FRAMES_PER_SECOND: u32 = 60;
+
+/// Everything trackers look at, decoded from one moment of the game.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RunFrame {
+    pub state: State,
+    /// The players in occupied slots, in slot order.
+    pub players: Vec<PlayerFrame>,
+    /// Types of the entities spawned since the previous frame, while in the same level.
+    pub new_entity_types: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerFrame {
+    /// Which of the four player slots this is, from 0.
+    pub slot: usize,
+    pub player: Player,
+    pub inventory: Option<Inventory>,
+    /// Types of the entities the player holds, wears or is affected by.
+    pub item_types: HashSet<u32>,
+    /// Types of the items the player's hired hands hold.
+    pub companion_item_types: HashSet<u32>,
+    /// The mount the player is riding, if any.
+    pub mount: Option<MountFrame>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MountFrame {
+    pub entity_type: u32,
+    pub is_tamed: bool,
+}
+
+impl PlayerFrame {
+    pub fn has(&self, entity_type: EntityType) -> bool {
+        self.item_types.contains(&entity_type.id())
+    }
+
+    /// Whether the player or one of their hired hands holds an item.
+    pub fn has_with_companions(&self, entity_type: EntityType) -> bool {
+        self.has(entity_type) || self.companion_item_types.contains(&entity_type.id())
+    }
+}
+
+impl RunFrame {
+    /// Reads the state at `state_addr` and the players it points to.
+    ///
+    /// `previous` is the frame read before this one, to find the entities spawned since.
+    pub fn read<S: MemorySource + ?Sized>(
+        src: &S,
+        state_addr: u64,
+        previous: Option<&RunFrame>,
+    ) -> Result<Self, MemError> {
+        let state = State::read(src, state_addr)?;
+        let mut players = Vec::new();
+        for (slot, player) in state.player_slots(src)?.into_iter().enumerate() {
+            let Some(player) = player else { continue };
+            let inventory = player.inventory.read(src)?;
+            let item_types = item_types(src, &state, &player.items.read(src)?)?;
+            let companion_item_types = companion_item_types(src, &state, &player)?;
+            let mount = mount(src, &player)?;
+            players.push(PlayerFrame {
+                slot,
+                player,
+                inventory,
+                item_types,
+                companion_item_types,
+                mount,
+            });
+        }
+        let new_entity_types = new_entity_types(src, &state, previous)?;
+        Ok(Self {
+            state,
+            players,
+            new_entity_types,
+        })
+    }
+
+    /// Frames since the run started.
+    pub fn time(&self) -> u32 {
+        self.state.time_total
+    }
+}
+
+/// The types of the entities with these uids that still exist.
+fn item_types<S: MemorySource + ?Sized>(
+    src: &S,
+    state: &State,
+    uids: &[u32],
+) -> Result<HashSet<u32>, MemError> {
+    let mut types = HashSet::new();
+    for &uid in uids {
+        let Some(entity) = state.instance_id_to_pointer.get(src, uid)? else {
+            continue;
+        };
+        types.extend(entity.type_id(src)?);
+    }
+    Ok(types)
+}
+
+fn companion_item_types<S: MemorySource + ?Sized>(
+    src: &S,
+    state: &State,
+    player: &Player,
+) -> Result<HashSet<u32>, MemError> {
+    let uids = state.instance_id_to_pointer;
+    let mut types = HashSet::new();
+    let mut seen = HashSet::new();
+    // Each hired hand links to the next one following the player
+    let mut uid = player.linked_companion_child;
+    while uid > 0 && seen.insert(uid) {
+        let Some(addr) = uids.entity_addr(src, uid as u32)? else {
+            break;
+        };
+        let companion = Player::read(src, addr)?;
+        types.extend(item_types(src, state, &companion.items.read(src)?)?);
+        uid = companion.linked_companion_child;
+    }
+    Ok(types)
+}
+
+fn mount<S: MemorySource + ?Sized>(
+    src: &S,
+    player: &Player,
+) -> Result<Option<MountFrame>, MemError> {
+    let Some(overlay) = player.overlay.read(src)? else {
+        return Ok(None);
+    };
+    let Some(entity_type) = overlay.type_id(src)? else {
+        return Ok(None);
+    };
+    if !EntityType::from_id(entity_type).is_some_and(|t| MOUNTS.contains(&t)) {
+        return Ok(None);
+    }
+    let mount = Mount::read(src, player.overlay.addr)?;
+    Ok(Some(MountFrame {
+        entity_type,
+        is_tamed: mount.is_tamed,
+    }))
+}
+
+/// Spawns within a level, skipping its start where everything in it spawns at once.
+fn new_entity_types<S: MemorySource + ?Sized>(
+    src: &S,
+    state: &State,
+    previous: Option<&RunFrame>,
+) -> Result<Vec<u32>, MemError> {
+    let Some(previous) = previous else {
+        return Ok(Vec::new());
+    };
+    let previous = &previous.state;
+    if state.screen != Screen::Level
+        || (previous.world, previous.level) != (state.world, state.level)
+    {
+        return Ok(Vec::new());
+    }
+    let mut types = Vec::new();
+    for uid in previous.next_entity_uid..state.next_entity_uid {
+        let Some(entity) = state.instance_id_to_pointer.get(src, uid)? else {
+            continue;
+        };
+        types.extend(entity.type_id(src)?);
+    }
+    Ok(types)
+}
+
+/// Formats a frame count as `m:ss.mmm`.
+pub fn format_time(frames: u32) -> String {
+    let millis = u64::from(frames) * 1000 / u64::from(FRAMES_PER_SECOND);
+    format!(
+        "{}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use ml2_entity_data::EntityType;
+    use ml2_mem::{MemoryDump, Snapshot};
+
+    use super::{format_time, MountFrame, RunFrame};
+
+    const STATE: u64 = 0x1_0000;
+    const ITEMS: u64 = 0x2_0000;
+    const PLAYER: u64 = 0x3_0000;
+    const UID_TABLE: u64 = 0x4_0000;
+    /// Each uid's entity is at `ENTITIES + uid * 0x200`, with its type right after it.
+    const ENTITIES: u64 = 0x5_0000;
+
+    fn put(bytes: &mut [u8], offset: usize, value: &[u8]) {
+        bytes[offset..offset + value.len()].copy_from_slice(value);
+    }
+
+    /// A player riding a tamed turkey and wearing a jetpack, followed by a hired hand holding an
+    /// ushabti, just after a ghost spawned.
+    fn sample() -> MemoryDump {
+        let mut state = vec![0; 0x1358];
+        put(&mut state, 0x0C, &12i32.to_le_bytes());
+        put(&mut state, 0x68, &[1, 1, 2, 2]);
+        put(&mut state, 0x74, &[1, 1]);
+        put(&mut state, 0x12E0, &6u32.to_le_bytes());
+        put(&mut state, 0x12F0, &ITEMS.to_le_bytes());
+        put(&mut state, 0x1348, &0xFFu64.to_le_bytes());
+        put(&mut state, 0x1350, &UID_TABLE.to_le_bytes());
+
+        let mut items = vec![0; 0x28];
+        put(&mut items, 0x08, &PLAYER.to_le_bytes());
+
+        let entity = |uid: u64| ENTITIES + uid * 0x200;
+        let mut player = vec![0; 0x154];
+        put(&mut player, 0x10, &entity(6).to_le_bytes());
+        put(&mut player, 0x20, &(PLAYER + 0x200).to_le_bytes());
+        put(&mut player, 0x2C, &1u32.to_le_bytes());
+        put(&mut player, 0x38, &1u32.to_le_bytes());
+        put(&mut player, 0x114, &[1, 1, 0, 4]);
+        put(&mut player, 0x150, &3i32.to_le_bytes());
+        player.resize(0x204, 0);
+        put(&mut player, 0x200, &2u32.to_le_bytes());
+
+        // Where each uid's hash, e.g. 0x53f1e9dd for 2, puts it in the table
+        let mut uid_table = vec![0; 0x100 * 16];
+        let mut entities = vec![0; 0x200 * 7];
+        for (uid, key, entity_type) in [
+            (2, 0x53f1_e9ddu32, EntityType::ITEM_JETPACK),
+            (3, 0xd97e_5ed1, EntityType::CHAR_ANA_SPELUNKY),
+            (4, 0x5c45_d53e, EntityType::ITEM_USHABTI),
+            (5, 0xa7e3_d3bb, EntityType::MONS_GHOST),
+            (6, 0x948b_a1e6, EntityType::MOUNT_TURKEY),
+        ] {
+            let index = (key & 0xFF) as usize;
+            put(&mut uid_table, index * 16, &key.to_le_bytes());
+            put(&mut uid_table, index * 16 + 8, &entity(uid).to_le_bytes());
+
+            let offset = uid as usize * 0x200;
+            let db = entity(uid) + 0x180;
+            put(&mut entities, offset + 0x08, &db.to_le_bytes());
+            put(&mut entities, offset + 0x38, &(uid as u32).to_le_bytes());
+            put(
+                &mut entities,
+                offset + 0x180 + 0x14,
+                &entity_type.id().to_le_bytes(),
+            );
+        }
+        // The hired hand holds the ushabti
+        let hired_hand = 3 * 0x200;
+        put(
+            &mut entities,
+            hired_hand + 0x20,
+            &(entity(4) + 0x100).to_le_bytes(),
+        );
+        put(&mut entities, hired_hand + 0x2C, &1u32.to_le_bytes());
+        put(&mut entities, hired_hand + 0x114, &[1, 1, 0, 4]);
+        put(&mut entities, 4 * 0x200 + 0x100, &4u32.to_le_bytes());
+        // The turkey is tamed
+        put(&mut entities, 6 * 0x200 + 0x151, &[1]);
+
+        MemoryDump {
+            regions: vec![
+                Snapshot::new(STATE, state),
+                Snapshot::new(ITEMS, items),
+                Snapshot::new(PLAYER, player),
+                Snapshot::new(UID_TABLE, uid_table),
+                Snapshot::new(ENTITIES, entities),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_read() {
+        let dump = sample();
+        let mut previous = RunFrame::read(&dump, STATE, None).unwrap();
+        assert!(previous.new_entity_types.is_empty());
+
+        let ids = |types: &[EntityType]| types.iter().map(|t| t.id()).collect::<HashSet<_>>();
+        let player = &previous.players[0];
+        assert_eq!(player.slot, 0);
+        assert_eq!(player.inventory, None);
+        assert_eq!(player.item_types, ids(&[EntityType::ITEM_JETPACK]));
+        assert_eq!(
+            player.companion_item_types,
+            ids(&[EntityType::ITEM_USHABTI])
+        );
+        assert_eq!(
+            player.mount,
+            Some(MountFrame {
+                entity_type: EntityType::MOUNT_TURKEY.id(),
+                is_tamed: true,
+            })
+        );
+
+        // Only entities spawned since the previous frame of the same level are new
+        previous.state.next_entity_uid = 5;
+        let frame = RunFrame::read(&dump, STATE, Some(&previous)).unwrap();
+        assert_eq!(frame.new_entity_types, [EntityType::MONS_GHOST.id()]);
+        previous.state.level = 1;
+        let frame = RunFrame::read(&dump, STATE, Some(&previous)).unwrap();
+        assert!(frame.new_entity_types.is_empty());
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0), "0:00.000");
+        assert_eq!(format_time(90), "0:01.500");
+        assert_eq!(format_time(60 * 61 + 1), "1:01.016");
+    }
+}
@@ -0,0 +1,14 @@
+//! Run trackers over decoded game state, independent of any UI.
+//!
+//! A [`CategoryTracker`] is fed a [`RunFrame`] per update and reports which categories the run
+//! still qualifies for, and when and why it broke the others.
+
+mod category;
+mod chain;
+mod frame;
+mod rules;
+
+pub use category::{Break, Category, CategoryTracker, TrackerEvent};
+pub use chain::{Chain, ChainStatus, ChainStepper, Step, SunkenChains};
+pub use frame::{format_time, MountFrame, PlayerFrame, RunFrame, FRAMES_PER_SECOND};
+pub use rules::{ChainRule, LowPercentRule, NoGoldRule, PacifistRule, Rule};
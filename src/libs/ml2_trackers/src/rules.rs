@@ -0,0 +1,457 @@
+use ml2_entity_data::EntityType;
+use ml2_mem::{CharState, State, Theme};
+
+use crate::chain::{Chain, ChainStatus, ChainStepper, SunkenChains};
+use crate::{Category, PlayerFrame, RunFrame, FRAMES_PER_SECOND};
+
+pub(crate) const MOUNTS: [EntityType; 5] = [
+    EntityType::MOUNT_TURKEY,
+    EntityType::MOUNT_ROCKDOG,
+    EntityType::MOUNT_AXOLOTL,
+    EntityType::MOUNT_MECH,
+    EntityType::MOUNT_QILIN,
+];
+
+const BACKPACKS: [EntityType; 6] = [
+    EntityType::ITEM_CAPE,
+    EntityType::ITEM_VLADS_CAPE,
+    EntityType::ITEM_HOVERPACK,
+    EntityType::ITEM_JETPACK,
+    EntityType::ITEM_POWERPACK,
+    EntityType::ITEM_TELEPORTER_BACKPACK,
+];
+
+const SHIELDS: [EntityType; 2] = [
+    EntityType::ITEM_METAL_SHIELD,
+    EntityType::ITEM_WOODEN_SHIELD,
+];
+
+/// Powerups that aren't part of the Abzu or Duat chains. The eggplant crown is missing as it's
+/// allowed in every category.
+const NON_CHAIN_POWERUPS: [EntityType; 12] = [
+    EntityType::ITEM_POWERUP_CLIMBING_GLOVES,
+    EntityType::ITEM_POWERUP_COMPASS,
+    EntityType::ITEM_POWERUP_KAPALA,
+    EntityType::ITEM_POWERUP_PARACHUTE,
+    EntityType::ITEM_POWERUP_PASTE,
+    EntityType::ITEM_POWERUP_PITCHERSMITT,
+    EntityType::ITEM_POWERUP_SKELETON_KEY,
+    EntityType::ITEM_POWERUP_SPECIALCOMPASS,
+    EntityType::ITEM_POWERUP_SPECTACLES,
+    EntityType::ITEM_POWERUP_SPIKE_SHOES,
+    EntityType::ITEM_POWERUP_SPRING_SHOES,
+    EntityType::ITEM_POWERUP_TRUECROWN,
+];
+
+/// Chain powerups that Low% only allows on the chain.
+const CHAIN_ONLY_POWERUPS: [EntityType; 2] = [
+    EntityType::ITEM_POWERUP_ANKH,
+    EntityType::ITEM_POWERUP_TABLETOFDESTINY,
+];
+
+/// Weapons Low% can't attack with, though the bow, Excalibur and mattock have exceptions.
+const BANNED_ATTACKABLES: [EntityType; 15] = [
+    EntityType::ITEM_WEBGUN,
+    EntityType::ITEM_SHOTGUN,
+    EntityType::ITEM_FREEZERAY,
+    EntityType::ITEM_CLONEGUN,
+    EntityType::ITEM_CAMERA,
+    EntityType::ITEM_TELEPORTER,
+    EntityType::ITEM_BOOMERANG,
+    EntityType::ITEM_MACHETE,
+    EntityType::ITEM_BROKENEXCALIBUR,
+    EntityType::ITEM_PLASMACANNON,
+    EntityType::ITEM_LIGHT_ARROW,
+    EntityType::ITEM_CROSSBOW,
+    EntityType::ITEM_HOUYIBOW,
+    EntityType::ITEM_EXCALIBUR,
+    EntityType::ITEM_MATTOCK,
+];
+
+const BACK_LAYER: u8 = 1;
+
+/// The ghost comes at 3:00, or 2:30 when cursed, and takes the clover away. Checking a few
+/// frames early catches the clover before it goes.
+const CLOVER_TIME: u32 = 3 * 60 * FRAMES_PER_SECOND - 5;
+const CURSED_CLOVER_TIME: u32 = 150 * FRAMES_PER_SECOND - 5;
+
+/// Decides whether a run has broken a category.
+pub trait Rule {
+    fn category(&self) -> Category;
+
+    /// Checks a frame, returning why the run broke the category.
+    ///
+    /// `previous` is the last frame of the same run, if there was one.
+    fn check(&mut self, previous: Option<&RunFrame>, frame: &RunFrame) -> Option<String>;
+
+    /// Whether an unbroken run so far counts for the category.
+    fn qualifies(&self) -> bool {
+        true
+    }
+
+    /// Forgets the last run.
+    fn reset(&mut self) {}
+}
+
+/// Broken when the game stops counting the run as pacifist in its run recap.
+#[derive(Clone, Debug, Default)]
+pub struct PacifistRule;
+
+impl Rule for PacifistRule {
+    fn category(&self) -> Category {
+        Category::Pacifist
+    }
+
+    fn check(&mut self, _previous: Option<&RunFrame>, frame: &RunFrame) -> Option<String> {
+        if frame.state.run_recap_flags & State::RECAP_PACIFIST != 0 {
+            return None;
+        }
+        let killer = frame.players.iter().find_map(|p| {
+            let kills = p.inventory.as_ref()?.kills_total;
+            (kills > 0).then(|| format!("Player {} has {kills} kills", p.slot + 1))
+        });
+        Some(killer.unwrap_or_else(|| "Killed something".to_string()))
+    }
+}
+
+/// Broken when the game stops counting the run as no gold in its run recap.
+#[derive(Clone, Debug, Default)]
+pub struct NoGoldRule;
+
+impl Rule for NoGoldRule {
+    fn category(&self) -> Category {
+        Category::NoGold
+    }
+
+    fn check(&mut self, _previous: Option<&RunFrame>, frame: &RunFrame) -> Option<String> {
+        if frame.state.run_recap_flags & State::RECAP_NO_GOLD != 0 {
+            return None;
+        }
+        let collector = frame.players.iter().find_map(|p| {
+            let inventory = p.inventory.as_ref()?;
+            // `money` is this level's gold, `collected_money_total` only covers earlier levels.
+            let money = u64::from(inventory.money) + u64::from(inventory.collected_money_total);
+            (money > 0).then(|| format!("Player {} collected ${money}", p.slot + 1))
+        });
+        Some(collector.unwrap_or_else(|| "Collected gold".to_string()))
+    }
+}
+
+/// What Low% remembers about each player between frames.
+#[derive(Clone, Debug)]
+struct LowPlayer {
+    level_start_ropes: u8,
+    poisoned: bool,
+    cursed: bool,
+}
+
+impl Default for LowPlayer {
+    fn default() -> Self {
+        Self {
+            level_start_ropes: 4,
+            poisoned: false,
+            cursed: false,
+        }
+    }
+}
+
+/// Broken by ending up with more than the starting health, bombs or ropes, curing poison or
+/// curses, keeping a clover until the ghost is due, or using mounts, backpacks, shields, powerups
+/// or weapons.
+///
+/// The ankh, tablet, riding a qilin in Tiamat and attacking with Excalibur in Abzu are allowed on
+/// the Abzu or Duat chain, which breaks Low% if it then fails. The mattock is allowed in the moon
+/// challenge if the run goes on to the Cosmic Ocean.
+#[derive(Clone, Debug)]
+pub struct LowPercentRule {
+    sunken: SunkenChains,
+    cosmic: ChainStepper,
+    players: [LowPlayer; 4],
+    /// Set once the run has done something only allowed on the chain.
+    needs_chain: bool,
+    swung_mattock: bool,
+    ghost_spawned: bool,
+}
+
+impl Default for LowPercentRule {
+    fn default() -> Self {
+        Self {
+            sunken: SunkenChains::default(),
+            cosmic: ChainStepper::new(Chain::CosmicOcean),
+            players: Default::default(),
+            needs_chain: false,
+            swung_mattock: false,
+            ghost_spawned: false,
+        }
+    }
+}
+
+impl Rule for LowPercentRule {
+    fn category(&self) -> Category {
+        Category::LowPercent
+    }
+
+    fn check(&mut self, previous: Option<&RunFrame>, frame: &RunFrame) -> Option<String> {
+        let state = &frame.state;
+        let level_started = previous.map_or(true, |p| {
+            (p.state.world, p.state.level) != (state.world, state.level)
+        });
+        for now in &frame.players {
+            let before = previous.and_then(|p| p.players.iter().find(|b| b.slot == now.slot));
+            if let Some(reason) = self.check_player(state, level_started, before, now) {
+                return Some(format!("Player {} {reason}", now.slot + 1));
+            }
+        }
+
+        if level_started {
+            self.ghost_spawned = false;
+        }
+        let ghost = EntityType::MONS_GHOST.id();
+        self.ghost_spawned |= frame.new_entity_types.contains(&ghost);
+        if !self.ghost_spawned && state.hud_flags & State::HUD_HAVE_CLOVER != 0 {
+            let cursed = frame.players.iter().any(|p| self.players[p.slot].cursed);
+            if state.time_level >= CLOVER_TIME || (cursed && state.time_level >= CURSED_CLOVER_TIME)
+            {
+                return Some("Kept a clover until the ghost was due".to_string());
+            }
+        }
+
+        // As in the Python tracker, the checks above see whether the run was on the chain before
+        // this frame, so leaving the chain is reported as such.
+        if let Some(first) = frame.players.iter().find(|p| p.slot == 0) {
+            self.sunken.evaluate(state, first);
+            self.cosmic.evaluate(state, first);
+        }
+        if self.swung_mattock && self.cosmic.status() == ChainStatus::Failed {
+            return Some(
+                "Swung the mattock in the moon challenge without going to the Cosmic Ocean"
+                    .to_string(),
+            );
+        }
+        if self.needs_chain && self.sunken.status() == ChainStatus::Failed {
+            return Some(format!(
+                "Left the chain after using what only it allows ({})",
+                self.sunken.describe_failure()
+            ));
+        }
+        None
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl LowPercentRule {
+    fn on_chain(&self) -> bool {
+        self.sunken.status() == ChainStatus::InProgress
+    }
+
+    /// Checks one player, returning what they did to break Low%.
+    fn check_player(
+        &mut self,
+        state: &State,
+        level_started: bool,
+        before: Option<&PlayerFrame>,
+        now: &PlayerFrame,
+    ) -> Option<String> {
+        let in_duat = state.theme() == Some(Theme::Duat);
+        let player = &now.player;
+        let dying = player.state == CharState::Dying;
+        let remembered = &mut self.players[now.slot];
+        if level_started {
+            remembered.level_start_ropes = before
+                .and_then(|b| b.inventory.as_ref())
+                .map_or(4, |inventory| inventory.ropes);
+            // Reaching Duat restores health and removes poison and curses
+            if in_duat {
+                remembered.poisoned = false;
+                remembered.cursed = false;
+            }
+        }
+
+        if let Some(mount) = now.mount {
+            if state.theme() == Some(Theme::Tiamat)
+                && mount.entity_type == EntityType::MOUNT_QILIN.id()
+            {
+                self.needs_chain = true;
+                if !self.on_chain() {
+                    return Some("rode a qilin in Tiamat off the chain".to_string());
+                }
+            } else if mount.is_tamed {
+                return Some(format!("rode a tamed {}", type_name(mount.entity_type)));
+            }
+        }
+
+        let health_before = match before {
+            Some(before) if !(level_started && in_duat) => before.player.health,
+            _ => 4,
+        };
+        if player.health > health_before && !dying {
+            return Some(format!(
+                "gained health ({health_before} -> {})",
+                player.health
+            ));
+        }
+        if player.health > 4 {
+            return Some(format!("has {} health", player.health));
+        }
+        if let Some(inventory) = &now.inventory {
+            let bombs_before = before
+                .and_then(|b| b.inventory.as_ref())
+                .map_or(4, |inventory| inventory.bombs);
+            if inventory.bombs > bombs_before {
+                return Some(format!(
+                    "gained bombs ({bombs_before} -> {})",
+                    inventory.bombs
+                ));
+            }
+            if inventory.bombs > 4 {
+                return Some(format!("has {} bombs", inventory.bombs));
+            }
+            let level_start_ropes = self.players[now.slot].level_start_ropes;
+            if inventory.ropes > level_start_ropes {
+                return Some(format!(
+                    "has more ropes than at the start of the level ({level_start_ropes} -> {})",
+                    inventory.ropes
+                ));
+            }
+            if inventory.ropes > 4 {
+                return Some(format!("has {} ropes", inventory.ropes));
+            }
+        }
+
+        // Effects are removed while going through doors, and are missing while loading
+        if !matches!(player.state, CharState::Entering | CharState::Loading) {
+            let remembered = &mut self.players[now.slot];
+            let poisoned = now.has(EntityType::LOGICAL_POISONED_EFFECT);
+            let cursed = now.has(EntityType::LOGICAL_CURSED_EFFECT);
+            let cured_poison = remembered.poisoned && !poisoned && !dying;
+            let cured_curse = remembered.cursed && !cursed && !dying;
+            remembered.poisoned = poisoned;
+            remembered.cursed = cursed;
+            if cured_poison {
+                return Some("was cured of poison".to_string());
+            }
+            if cured_curse {
+                return Some("was cured of a curse".to_string());
+            }
+        }
+
+        let holds = |types: &[EntityType]| types.iter().copied().find(|&t| now.has(t));
+        if let Some(backpack) = holds(&BACKPACKS) {
+            return Some(format!("wore {}", type_name(backpack.id())));
+        }
+        if let Some(shield) = holds(&SHIELDS) {
+            return Some(format!("held {}", type_name(shield.id())));
+        }
+        if let Some(powerup) = holds(&NON_CHAIN_POWERUPS) {
+            return Some(format!("collected {}", type_name(powerup.id())));
+        }
+        if let Some(reason) = self.check_attack(state, before, now) {
+            return Some(reason);
+        }
+        if !self.on_chain() {
+            if let Some(powerup) = holds(&CHAIN_ONLY_POWERUPS) {
+                return Some(format!(
+                    "collected {} off the chain",
+                    type_name(powerup.id())
+                ));
+            }
+        }
+        None
+    }
+
+    fn check_attack(
+        &mut self,
+        state: &State,
+        before: Option<&PlayerFrame>,
+        now: &PlayerFrame,
+    ) -> Option<String> {
+        let player = &now.player;
+        let had = |entity_type: EntityType| before.is_some_and(|b| b.has(entity_type));
+
+        if player.state == CharState::Throwing || player.last_state == CharState::Throwing {
+            let arrow = EntityType::ITEM_LIGHT_ARROW;
+            if now.has(arrow) || had(arrow) {
+                return Some(format!("threw {}", type_name(arrow.id())));
+            }
+        }
+
+        if player.state != CharState::Attacking && player.last_state != CharState::Attacking {
+            return None;
+        }
+        // A thrown boomerang leaves the player's items as the attack starts
+        let boomerang = EntityType::ITEM_BOOMERANG;
+        if player.last_state != CharState::Attacking && had(boomerang) && !now.has(boomerang) {
+            return Some(format!("attacked with {}", type_name(boomerang.id())));
+        }
+
+        let moon = state.presence_flags & State::PRESENCE_MOON_CHALLENGE != 0;
+        let sun = state.presence_flags & State::PRESENCE_SUN_CHALLENGE != 0;
+        let back_layer = player.layer == BACK_LAYER;
+        let world_level = (state.world, state.level);
+        for weapon in BANNED_ATTACKABLES.iter().copied().filter(|&t| now.has(t)) {
+            match weapon {
+                EntityType::ITEM_EXCALIBUR if state.theme() == Some(Theme::Abzu) => {
+                    self.needs_chain = true;
+                    if self.on_chain() {
+                        continue;
+                    }
+                }
+                EntityType::ITEM_MATTOCK if back_layer && moon => {
+                    self.swung_mattock = true;
+                    continue;
+                }
+                EntityType::ITEM_HOUYIBOW => {
+                    let waddler = matches!(world_level, (3, 1) | (5, 1) | (7, 1));
+                    if (back_layer && (moon || sun || waddler)) || world_level == (7, 4) {
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            return Some(format!("attacked with {}", type_name(weapon.id())));
+        }
+        None
+    }
+}
+
+/// An entity type's name without the `ENT_TYPE_` prefix, or its id if it's unknown.
+fn type_name(id: u32) -> String {
+    match EntityType::from_id(id) {
+        Some(entity_type) => entity_type
+            .name()
+            .trim_start_matches("ENT_TYPE_")
+            .to_string(),
+        None => id.to_string(),
+    }
+}
+
+/// Qualifies while the Abzu or Duat quest chain is in progress, and is broken once both fail.
+#[derive(Clone, Debug, Default)]
+pub struct ChainRule {
+    sunken: SunkenChains,
+}
+
+impl Rule for ChainRule {
+    fn category(&self) -> Category {
+        Category::Chain
+    }
+
+    fn check(&mut self, _previous: Option<&RunFrame>, frame: &RunFrame) -> Option<String> {
+        let first = frame.players.iter().find(|p| p.slot == 0)?;
+        match self.sunken.evaluate(&frame.state, first) {
+            ChainStatus::Failed => Some(self.sunken.describe_failure()),
+            _ => None,
+        }
+    }
+
+    fn qualifies(&self) -> bool {
+        self.sunken.status() == ChainStatus::InProgress
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
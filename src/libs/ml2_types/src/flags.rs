@@ -0,0 +1,184 @@
+//! Typed views of entity bitfields.
+
+use std::fmt;
+
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+
+bitflags! {
+    /// The game's entity masks, used by [`Entity::search_flags`](crate::Entity) to say what an
+    /// entity is, and by [`RectCollision::masks`](crate::RectCollision) to say what it collides
+    /// with.
+    ///
+    /// Serialized as e.g. `"MONSTER | ITEM"` in human-readable formats.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+    #[serde(transparent)]
+    pub struct Mask: u32 {
+        const PLAYER = 0x1;
+        const MOUNT = 0x2;
+        const MONSTER = 0x4;
+        const ITEM = 0x8;
+        const EXPLOSION = 0x10;
+        const ROPE = 0x20;
+        const FX = 0x40;
+        const ACTIVEFLOOR = 0x80;
+        const FLOOR = 0x100;
+        const DECORATION = 0x200;
+        const BG = 0x400;
+        const SHADOW = 0x800;
+        const LOGICAL = 0x1000;
+        const WATER = 0x2000;
+        const LAVA = 0x4000;
+
+        const LIQUID = Self::WATER.bits() | Self::LAVA.bits();
+    }
+}
+
+impl Mask {
+    /// Keeps every bit, including ones without a name, as the game does.
+    pub fn from_raw(raw: i64) -> Self {
+        Self::from_bits_retain(raw as u32)
+    }
+}
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("NONE");
+        }
+        bitflags::parser::to_writer(self, f)
+    }
+}
+
+/// How an entity is drawn, from [`Entity::technique`](crate::Entity).
+///
+/// The game doesn't name these, so each is named after the entities that use it. Values that
+/// don't appear in `entities.json` are kept as [`Technique::Other`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(from = "i64", into = "i64")]
+pub enum Technique {
+    /// On-screen elements such as the compass, buttons and sale dialogs.
+    Hud,
+    /// Solid overlays that hide what's behind them, such as cinematic black bars.
+    Occluder,
+    /// Almost every entity, including the player, monsters, items and floors.
+    Standard,
+    /// See-through sprites such as bubbles, webs, shadows and boss body parts.
+    Translucent,
+    /// Background marks that darken what's behind them, such as bomb soot.
+    Darken,
+    /// The red lantern's flame.
+    LanternFlame,
+    /// Constellation stars and shooting stars.
+    Twinkle,
+    /// Glowing projectiles and monsters, such as sparks, light arrows and ghists.
+    Glow,
+    /// Portals, the player ghost and the red lantern.
+    Portal,
+    /// Explosions, blasts and ghosts.
+    Blast,
+    /// Flames, fireballs and laser beams.
+    Flame,
+    /// The eggship's jet flames.
+    JetFlame,
+    /// The lavamander's glowing body.
+    Lavamander,
+    /// The ink that covers the screen when a player is blinded.
+    InkBlindness,
+    Other(i64),
+}
+
+impl Technique {
+    const KNOWN: [(Technique, i64, &'static str); 14] = [
+        (Technique::Hud, 2, "Hud"),
+        (Technique::Occluder, 6, "Occluder"),
+        (Technique::Standard, 7, "Standard"),
+        (Technique::Translucent, 11, "Translucent"),
+        (Technique::Darken, 12, "Darken"),
+        (Technique::LanternFlame, 13, "LanternFlame"),
+        (Technique::Twinkle, 16, "Twinkle"),
+        (Technique::Glow, 17, "Glow"),
+        (Technique::Portal, 18, "Portal"),
+        (Technique::Blast, 19, "Blast"),
+        (Technique::Flame, 21, "Flame"),
+        (Technique::JetFlame, 22, "JetFlame"),
+        (Technique::Lavamander, 23, "Lavamander"),
+        (Technique::InkBlindness, 26, "InkBlindness"),
+    ];
+}
+
+impl From<i64> for Technique {
+    fn from(raw: i64) -> Self {
+        Self::KNOWN
+            .iter()
+            .find(|(_, value, _)| *value == raw)
+            .map_or(Technique::Other(raw), |(technique, _, _)| *technique)
+    }
+}
+
+impl From<Technique> for i64 {
+    fn from(technique: Technique) -> Self {
+        match technique {
+            Technique::Other(raw) => raw,
+            known => Technique::KNOWN
+                .iter()
+                .find(|(technique, _, _)| *technique == known)
+                .map(|(_, value, _)| *value)
+                .expect("every named technique has a value"),
+        }
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Technique::Other(raw) => write!(f, "Technique {raw}"),
+            known => {
+                let (_, _, name) = Technique::KNOWN
+                    .iter()
+                    .find(|(technique, _, _)| technique == known)
+                    .expect("every named technique has a name");
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mask, Technique};
+
+    #[test]
+    fn test_mask() {
+        let mask = Mask::from_raw(0x4 | 0x8);
+        assert!(mask.contains(Mask::MONSTER));
+        assert!(mask.intersects(Mask::ITEM | Mask::FLOOR));
+        assert_eq!(mask.to_string(), "MONSTER | ITEM");
+        assert_eq!(Mask::LIQUID.to_string(), "WATER | LAVA");
+        assert_eq!(Mask::empty().to_string(), "NONE");
+        assert_eq!(Mask::from_raw(0x10000).bits(), 0x10000);
+
+        let json = serde_json::to_string(&mask).unwrap();
+        assert_eq!(json, "\"MONSTER | ITEM\"");
+        assert_eq!(serde_json::from_str::<Mask>(&json).unwrap(), mask);
+    }
+
+    #[test]
+    fn test_technique() {
+        assert_eq!(Technique::from(7), Technique::Standard);
+        assert_eq!(Technique::from(19), Technique::Blast);
+        assert_eq!(i64::from(Technique::from(19)), 19);
+        assert_eq!(Technique::Flame.to_string(), "Flame");
+        assert_eq!(Technique::from(99), Technique::Other(99));
+        assert_eq!(Technique::Other(99).to_string(), "Technique 99");
+        assert_eq!(serde_json::to_string(&Technique::Standard).unwrap(), "7");
+        assert_eq!(
+            serde_json::from_str::<Technique>("11").unwrap(),
+            Technique::Translucent
+        );
+        for (technique, value, _) in Technique::KNOWN {
+            assert_eq!(Technique::from(value), technique);
+            assert_eq!(i64::from(technique), value);
+        }
+    }
+}
@@ -1,8 +1,12 @@
+mod flags;
+
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+pub use flags::{Mask, Technique};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RectCollision {
     pub masks: i64,
     pub side: f64,
@@ -10,7 +14,13 @@ pub struct RectCollision {
     pub up_plus_down: f64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl RectCollision {
+    pub fn mask(&self) -> Mask {
+        Mask::from_raw(self.masks)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Animation {
     pub count: i64,
     pub interval: i64,
@@ -19,7 +29,7 @@ pub struct Animation {
     pub texture: i64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Entity {
     pub acceleration: f64,
     pub animations: HashMap<String, Animation>,
@@ -46,13 +56,37 @@ pub struct Entity {
     pub width: f64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Entity {
+    /// What kind of entity this is, as used when searching for entities.
+    pub fn search_mask(&self) -> Mask {
+        Mask::from_raw(self.search_flags)
+    }
+
+    pub fn draw_technique(&self) -> Technique {
+        Technique::from(self.technique)
+    }
+
+    /// The tile the entity is drawn with when idle, in a sheet `columns` tiles wide.
+    ///
+    /// Like the game, this is the first frame of its lowest-numbered animation, or its `tile_x`
+    /// and `tile_y` if it has none.
+    pub fn idle_tile(&self, columns: i64) -> i64 {
+        self.animations
+            .iter()
+            .min_by_key(|(id, _)| (id.parse::<i64>().unwrap_or(i64::MAX), *id))
+            .map_or(self.tile_y * columns + self.tile_x, |(_, animation)| {
+                animation.texture
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Size {
     pub height: i64,
     pub width: i64,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Texture {
     pub height: i64,
     pub width: i64,
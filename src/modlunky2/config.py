@@ -191,6 +191,8 @@ class Config:
         default=SPELUNKY_FYI_ROOT_DEFAULT, skip_if_default=True
     )
     spelunky_fyi_api_token: Optional[str] = field(default=None, skip_if_default=True)
+    # How many save backups mod management keeps, read by the Tauri app
+    save_backups: Optional[int] = field(default=None, skip_if_default=True)
     theme: Optional[str] = field(default=None, skip_if_default=True)
     last_install_browse: Path = field(
         default=Path(LAST_INSTALL_BROWSE_DEFAULT), skip_if_default=True
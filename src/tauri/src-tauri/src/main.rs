@@ -5,17 +5,21 @@
 
 mod mods;
 
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::time::Duration;
 
 use anyhow::anyhow;
 use directories::{BaseDirs, ProjectDirs};
+use ml2_mods::discovery::{auto_select, default_steam_roots, find_installs, Install};
 use ml2_mods::spelunkyfyi::http::DEFAULT_SERVICE_ROOT;
-use ml2_net::http::new_http_client;
+use ml2_net::http::{new_http_client, HttpClient};
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Runtime};
 use tokio::select;
+use tokio::sync::oneshot;
 use tokio_graceful_shutdown::{SubsystemHandle, Toplevel};
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::mods::{handle_mod_logo_request, setup_mod_management};
 
@@ -24,6 +28,8 @@ use crate::mods::{handle_mod_logo_request, setup_mod_management};
 #[serde(rename_all = "kebab-case")]
 pub(crate) struct Config {
     install_dir: Option<String>,
+    /// How many save backups to keep, 0 to not make any.
+    save_backups: Option<usize>,
     spelunky_fyi_api_token: Option<String>,
     spelunky_fyi_root: Option<String>,
 }
@@ -36,6 +42,101 @@ impl Config {
     }
 }
 
+/// Finds the install to use when none is configured.
+///
+/// Only an unambiguous install is picked. Otherwise the user chooses from [`list_installs`].
+fn discover_install_dir() -> Option<String> {
+    let installs = find_installs(&default_steam_roots());
+    let Some(install) = auto_select(&installs) else {
+        warn!("No single complete install to use among {:?}", installs);
+        return None;
+    };
+    let Some(install_dir) = install.path.to_str() else {
+        warn!("Install path {:?} isn't valid unicode", install.path);
+        return None;
+    };
+    info!("Using install {:?}", install);
+    Some(install_dir.to_string())
+}
+
+/// Saves the install to use to the config at `conf_path`, so the choice sticks.
+async fn save_install_dir(conf_path: &Path, install_dir: &str) -> anyhow::Result<()> {
+    // Only this field is written, since Config doesn't have all of them
+    let mut config: serde_json::Value = serde_json::from_slice(&tokio::fs::read(conf_path).await?)?;
+    config
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("Config isn't a JSON object"))?
+        .insert("install-dir".to_string(), install_dir.into());
+    // Formatted like the Python config, through a temporary file in case writing fails
+    let mut json = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    config.serialize(&mut serde_json::Serializer::with_formatter(
+        &mut json, formatter,
+    ))?;
+    let tmp_path = conf_path.with_extension("json.tmp");
+    tokio::fs::write(&tmp_path, json).await?;
+    tokio::fs::rename(&tmp_path, conf_path).await?;
+    Ok(())
+}
+
+/// Where the config lives, and how to hand an install chosen with [`set_install_dir`] to
+/// [`manage_mods`].
+struct InstallChoice {
+    conf_path: PathBuf,
+    chosen_tx: Mutex<Option<oneshot::Sender<String>>>,
+}
+
+/// Installs found in Steam's libraries, for the user to pick from.
+#[tauri::command]
+fn list_installs() -> Vec<Install> {
+    find_installs(&default_steam_roots())
+}
+
+/// Saves the install chosen from [`list_installs`] and starts managing its mods.
+///
+/// This only works while no install is being managed.
+#[tauri::command]
+async fn set_install_dir(
+    install_dir: String,
+    choice: tauri::State<'_, InstallChoice>,
+) -> Result<(), String> {
+    if choice.chosen_tx.lock().unwrap().is_none() {
+        return Err("An install is already being managed".to_string());
+    }
+    save_install_dir(&choice.conf_path, &install_dir)
+        .await
+        .map_err(|e| format!("Unable to save the install: {e}"))?;
+    let chosen_tx = choice.chosen_tx.lock().unwrap().take();
+    chosen_tx
+        .and_then(|tx| tx.send(install_dir).ok())
+        .ok_or_else(|| "An install is already being managed".to_string())
+}
+
+/// Manages the mods of `install_dir`, or of the install chosen with [`set_install_dir`] if
+/// there isn't one.
+async fn manage_mods<R: Runtime>(
+    subsys: SubsystemHandle,
+    app_handle: AppHandle<R>,
+    http_client: HttpClient,
+    config: Config,
+    install_dir: Option<String>,
+    chosen_rx: oneshot::Receiver<String>,
+) -> anyhow::Result<()> {
+    let install_dir = match install_dir {
+        Some(install_dir) => install_dir,
+        None => select! {
+            () = subsys.on_shutdown_requested() => return Ok(()),
+            chosen = chosen_rx => match chosen {
+                Ok(install_dir) => install_dir,
+                Err(_) => return Ok(()),
+            },
+        },
+    };
+    setup_mod_management(&subsys, app_handle, http_client, &config, &install_dir)?;
+    subsys.on_shutdown_requested().await;
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt::init();
@@ -50,11 +151,31 @@ async fn main() -> anyhow::Result<()> {
         .join(conf_subpath);
     info!("Config path: {:?}", conf_path);
     // This will fail if config doesn't exist!
-    let config_json = tokio::fs::read(conf_path).await?;
+    let config_json = tokio::fs::read(&conf_path).await?;
     let config: Config = serde_json::from_slice(&config_json[..])?;
 
+    let install_dir = match &config.install_dir {
+        Some(install_dir) => Some(install_dir.clone()),
+        None => {
+            let install_dir = discover_install_dir();
+            if let Some(install_dir) = &install_dir {
+                // The install can still be used this time
+                if let Err(e) = save_install_dir(&conf_path, install_dir).await {
+                    warn!("Unable to save install {:?}: {:?}", install_dir, e);
+                }
+            }
+            install_dir
+        }
+    };
+    let (chosen_tx, chosen_rx) = oneshot::channel();
+
     tauri::async_runtime::set(tokio::runtime::Handle::current());
     let tauri_app = tauri::Builder::default()
+        .manage(InstallChoice {
+            conf_path,
+            // Only offered while there's no install to manage
+            chosen_tx: Mutex::new(install_dir.is_none().then_some(chosen_tx)),
+        })
         .invoke_handler(tauri::generate_handler![
             mods::get_mod,
             mods::list_mods,
@@ -62,7 +183,13 @@ async fn main() -> anyhow::Result<()> {
             mods::install_local_mod,
             mods::install_remote_mod,
             mods::update_local_mod,
-            mods::update_remote_mod
+            mods::update_remote_mod,
+            mods::enable_mod,
+            mods::disable_mod,
+            mods::get_load_order,
+            mods::set_load_order,
+            list_installs,
+            set_install_dir
         ])
         .register_uri_scheme_protocol("mod-logo", handle_mod_logo_request)
         .build(tauri::generate_context!())?;
@@ -79,10 +206,18 @@ async fn main() -> anyhow::Result<()> {
     };
 
     let http_client = new_http_client();
-    let toplevel = if config.install_dir.is_some() {
-        setup_mod_management(toplevel, tauri_app.handle(), http_client, &config)?
-    } else {
-        toplevel
+    let toplevel = {
+        let app_handle = tauri_app.handle();
+        toplevel.start("Mod Management", |subsys| {
+            manage_mods(
+                subsys,
+                app_handle,
+                http_client,
+                config,
+                install_dir,
+                chosen_rx,
+            )
+        })
     };
 
     let graceful_handle =
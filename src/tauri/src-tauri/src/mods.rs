@@ -20,18 +20,19 @@ use tokio::{
     select,
     sync::broadcast::{self, error::RecvError},
 };
-use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle, Toplevel};
+use tokio_graceful_shutdown::{IntoSubsystem, SubsystemHandle};
 use tracing::{instrument, warn};
 
 use crate::Config;
 
+/// Starts the subsystems that manage the mods of the install at `install_path`.
 pub(crate) fn setup_mod_management<R: Runtime>(
-    toplevel: Toplevel,
+    subsys: &SubsystemHandle,
     app_handle: AppHandle<R>,
     http_client: HttpClient,
     config: &Config,
-) -> anyhow::Result<Toplevel> {
-    let install_path = config.install_dir.clone().unwrap();
+    install_path: &str,
+) -> anyhow::Result<()> {
     let token: Option<&String> = config.spelunky_fyi_api_token.as_ref();
     let service_root = config.spelunky_fyi_root();
 
@@ -39,13 +40,17 @@ pub(crate) fn setup_mod_management<R: Runtime>(
         .map(|token| HttpApiMods::new(service_root, token, http_client))
         .transpose()?;
 
+    let disk_mods = match config.save_backups {
+        Some(max_backups) => DiskMods::new(install_path).with_max_backups(max_backups),
+        None => DiskMods::new(install_path),
+    };
     let (detected_tx, detected_rx) = broadcast::channel(10);
     let (mod_cache, _) = ModCache::new(
         api_client.clone(),
         Duration::from_secs(60 * 60),
         Duration::from_secs(10),
         detected_tx,
-        DiskMods::new(&install_path),
+        disk_mods,
         Duration::from_secs(15),
     );
 
@@ -76,16 +81,15 @@ pub(crate) fn setup_mod_management<R: Runtime>(
         })
         .transpose()?;
 
-    let mut toplevel = toplevel
-        .start("Mod Cache", mod_cache.into_subsystem())
-        .start("Mod Manager", manager.into_subsystem())
-        .start("Mod Change Emitter", |subsys| {
-            emit_mod_changes(subsys, app_handle, changes_rx)
-        });
+    subsys.start("Mod Cache", mod_cache.into_subsystem());
+    subsys.start("Mod Manager", manager.into_subsystem());
+    subsys.start("Mod Change Emitter", |subsys| {
+        emit_mod_changes(subsys, app_handle, changes_rx)
+    });
     if let Some(web_socket_client) = web_socket_client {
-        toplevel = toplevel.start("WebSocket", web_socket_client.into_subsystem());
+        subsys.start("WebSocket", web_socket_client.into_subsystem());
     }
-    Ok(toplevel)
+    Ok(())
 }
 
 pub(crate) fn handle_mod_logo_request<R: Runtime>(
@@ -217,3 +221,34 @@ pub(crate) async fn update_remote_mod(
 ) -> Result<Mod, ManagerError> {
     Ok(manager_handle.update(&ModSource::Remote { code }).await?)
 }
+
+#[tauri::command]
+pub(crate) async fn enable_mod(
+    id: String,
+    manager_handle: tauri::State<'_, ModManagerHandle>,
+) -> Result<Mod, ManagerError> {
+    Ok(manager_handle.enable(&id).await?)
+}
+
+#[tauri::command]
+pub(crate) async fn disable_mod(
+    id: String,
+    manager_handle: tauri::State<'_, ModManagerHandle>,
+) -> Result<Mod, ManagerError> {
+    Ok(manager_handle.disable(&id).await?)
+}
+
+#[tauri::command]
+pub(crate) async fn get_load_order(
+    manager_handle: tauri::State<'_, ModManagerHandle>,
+) -> Result<Vec<String>, ManagerError> {
+    Ok(manager_handle.load_order().await?)
+}
+
+#[tauri::command]
+pub(crate) async fn set_load_order(
+    ids: Vec<String>,
+    manager_handle: tauri::State<'_, ModManagerHandle>,
+) -> Result<Vec<String>, ManagerError> {
+    Ok(manager_handle.set_load_order(&ids).await?)
+}
@@ -37,3 +37,16 @@ def test_from_path_last_tab_null(tmp_path, request):
 
     config = Config.from_path(config_path=file_name)
     assert config.last_tab is None
+
+
+def test_save_backups_kept(tmp_path, request):
+    file_name = tmp_path / f"config_{request.node.name}.json"
+    data = {"save-backups": 5}
+    with file_name.open("w", encoding="utf-8") as config_file:
+        json.dump(data, config_file)
+
+    config = Config.from_path(config_path=file_name)
+    assert config.save_backups == 5
+    config.save()
+    with file_name.open("r", encoding="utf-8") as config_file:
+        assert json.load(config_file)["save-backups"] == 5